@@ -20,7 +20,7 @@ import useNotifications from "./notifications";
 import "./Nav.scss";
 
 export default function Nav(): JSX.Element {
-    const { connected, account, symbols } = useContext(AppStateContext);
+    const { connected, account, symbols, auth_required } = useContext(AppStateContext);
     const [tickers, setTickers] = useState<string[]>([]);
 
     const [newTicker, setNewTicker] = useState('');
@@ -214,6 +214,10 @@ export default function Nav(): JSX.Element {
                     <a href="#" data-tooltip="Websocket Id" data-placement="bottom">({account.ws_id})</a>:&nbsp;
                     {connected ? <Connected height="25px" /> : <NotConnected height="25px" />}
                 </p>
+                {auth_required != null && <p className="auth-required">
+                    <a href={auth_required.login_url}>Log in to Schwab</a>, session expires
+                    at {new Date(auth_required.expires_at).toLocaleString()}
+                </p>}
             </div>
             <hr />
             <ul className="main-nav">
@@ -6,11 +6,7 @@ export type AppAction = {
     status: boolean,
 } | {
     action: 'UPDATE_ACCOUNT'
-    data: {
-        ws_id: number,
-        number: string,
-        balance: number,
-    }
+    data: Partial<Account>,
 } | {
     action: 'UPDATE_CHART',
     data: Symbol,
@@ -20,9 +16,36 @@ export type AppAction = {
 } | {
     action: 'UPDATE_QUOTE',
     quote: Quote,
+} | {
+    action: 'ORDER_BOOK',
+    data: OrderBookUpdate,
+} | {
+    action: 'ORDER_ACTIVITY',
+    data: OrderActivity,
 } | {
     action: 'REPLAY_MODE',
     data: ReplayMode,
+} | {
+    action: 'AUTH_REQUIRED',
+    data: AuthRequired | null,
+} | {
+    action: 'UPDATE_OPTION_QUOTE',
+    data: { symbol: string, quote: OptionQuote },
+} | {
+    action: 'UPDATE_OPTION_QUOTES',
+    data: { [key: string]: OptionQuote },
+} | {
+    action: 'REMOVE_OPTION_QUOTE',
+    data: string,
+} | {
+    action: 'UPDATE_POSITIONS',
+    data: PaperPortfolio,
+} | {
+    action: 'UPDATE_BROKERAGE',
+    data: BrokerageAccount[],
+} | {
+    action: 'UPDATE_SPREADS',
+    data: { symbol: string, candidates: SpreadCandidate[] },
 } | {
     action: 'HEARTBEAT',
     data: {},
@@ -32,6 +55,45 @@ export type Account = {
     ws_id: number,
     number: string,
     balance: number,
+    cash?: number,
+    starting_balance?: number,
+    realized_pnl?: number,
+    unrealized_pnl?: number,
+    commissions?: number,
+};
+
+export type PaperPosition = {
+    symbol: string,
+    asset_type: 'EQUITY' | 'OPTION',
+    quantity: number,
+    avg_price: number,
+    multiplier: number,
+    realized_pnl: number,
+    opened_at: string,
+    updated_at: string,
+    mark: number,
+    market_value: number,
+    unrealized_pnl: number,
+};
+
+export type PaperOrder = {
+    order_id: number,
+    symbol: string,
+    asset_type: 'EQUITY' | 'OPTION',
+    side: 'BUY' | 'SELL',
+    quantity: number,
+    order_type: 'MARKET' | 'LIMIT',
+    limit_price: number | null,
+    status: 'OPEN' | 'FILLED' | 'CANCELLED',
+    created_at: string,
+    filled_at: string | null,
+    fill_price: number | null,
+    commission: number,
+};
+
+export type PaperPortfolio = {
+    positions: PaperPosition[],
+    orders: PaperOrder[],
 };
 
 export type Price = {
@@ -95,9 +157,15 @@ export type Symbol = {
     priceLevelsOverridden: boolean,
     rejection: Rejection,
     isFavorite: boolean,
+    orderFlow: OrderFlow,
     charts: Chart[],
 };
 
+export type OrderFlow = {
+    buyVolume: number,
+    sellVolume: number,
+};
+
 export type Quote = {
     symbol: string,
     ask_price?: number,
@@ -105,10 +173,128 @@ export type Quote = {
     last_price?: number,
 };
 
+export type BrokeragePosition = {
+    symbol: string,
+    underlying: string,
+    description: string | null,
+    asset_type: string,
+    put_call: 'PUT' | 'CALL' | null,
+    quantity: number,
+    avg_price: number,
+    multiplier: number,
+    mark: number,
+    market_value: number,
+    unrealized_pnl: number,
+    day_pnl: number,
+};
+
+export type BrokerageAccount = {
+    number: string,
+    account_type: string,
+    round_trips: number,
+    is_day_trader: boolean,
+    balances: {
+        cashAvailableForTrading: number,
+        cashAvailableForWithdrawal: number,
+        cashBalance: number,
+        totalCash: number,
+        liquidationValue: number,
+        longMarketValue: number,
+        shortMarketValue: number,
+    },
+    balance: number,
+    unrealized_pnl: number,
+    day_pnl: number,
+    positions: BrokeragePosition[],
+    updated_at: string,
+};
+
+export type SpreadLeg = {
+    symbol: string,
+    put_call: 'PUT' | 'CALL',
+    strike: number,
+    quantity: number,
+    bid: number,
+    ask: number,
+    delta: number,
+    volatility: number,
+};
+
+export type SpreadCandidate = {
+    kind: 'PutVertical' | 'CallVertical' | 'IronCondor',
+    symbol: string,
+    expiration: string,
+    underlying_price: number,
+    legs: SpreadLeg[],
+    anchor_levels: number[],
+    credit: number,
+    mid_credit: number,
+    max_loss: number,
+    breakevens: number[],
+    pop: number,
+};
+
+export type OptionQuote = {
+    bidPrice: number,
+    bidSize: number,
+    askPrice: number,
+    askSize: number,
+    lastPrice: number,
+    lastSize: number,
+    totalVolume: number,
+    netChange?: number,
+    delta?: number,
+    gamma?: number,
+    theta?: number,
+    vega?: number,
+    volatility?: number,
+    openInterest?: number,
+    quoteTime?: string,
+};
+
+export type BookLevel = {
+    price: number,
+    size: number,
+    entries: { mpid: string, size: number }[],
+};
+
+export type Liquidity = {
+    trend: 'Bullish' | 'Bearish',
+    price_level: number,
+    price: number,
+    size: number,
+};
+
+export type OrderBookUpdate = {
+    symbol: string,
+    book: {
+        venue: string,
+        time: string,
+        bids: BookLevel[],
+        asks: BookLevel[],
+    },
+    liquidity: Liquidity[],
+};
+
+export type OrderActivity = {
+    account: string,
+    message_type: string,
+    order_id: number | null,
+    symbol: string | null,
+    quantity: number | null,
+    price: number | null,
+    time: string,
+};
+
 export type ReplayMode = {
     playing: boolean,
-    symbol: string,
     speed: number,
+    time: string | null,
+};
+
+export type AuthRequired = {
+    expires_at: string,
+    login_url: string,
 };
 
 export type AppState = {
@@ -116,7 +302,14 @@ export type AppState = {
     account: Account,
     symbols: { [key: string]: Symbol },
     quotes: { [key: string]: Quote },
+    option_quotes: { [key: string]: OptionQuote },
+    paper: PaperPortfolio,
+    brokerage: BrokerageAccount[],
+    spreads: { [key: string]: SpreadCandidate[] },
+    order_books: { [key: string]: OrderBookUpdate },
+    order_activity: OrderActivity[],
     replay_mode: ReplayMode | null,
+    auth_required: AuthRequired | null,
 };
 
 export const DEFAULT_APP_STATE: AppState = {
@@ -128,7 +321,17 @@ export const DEFAULT_APP_STATE: AppState = {
     },
     symbols: {},
     quotes: {},
+    option_quotes: {},
+    paper: {
+        positions: [],
+        orders: [],
+    },
+    brokerage: [],
+    spreads: {},
+    order_books: {},
+    order_activity: [],
     replay_mode: null,
+    auth_required: null,
 };
 
 export const AppStateContext = createContext<AppState>(DEFAULT_APP_STATE);
@@ -146,7 +349,7 @@ export function appReducer(state: AppState, action: AppAction): AppState {
         case 'UPDATE_ACCOUNT': {
             return {
                 ...state,
-                account: action.data,
+                account: { ...state.account, ...action.data },
             };
         }
         case 'UPDATE_CHART': {
@@ -177,12 +380,80 @@ export function appReducer(state: AppState, action: AppAction): AppState {
                 }
             }
         }
+        case 'ORDER_BOOK': {
+            return {
+                ...state,
+                order_books: {
+                    ...state.order_books,
+                    [action.data.symbol]: action.data,
+                }
+            }
+        }
+        case 'ORDER_ACTIVITY': {
+            return {
+                ...state,
+                order_activity: [action.data, ...state.order_activity].slice(0, 100),
+            }
+        }
         case 'REPLAY_MODE': {
             return {
                 ...state,
                 replay_mode: action.data,
             }
         }
+        case 'AUTH_REQUIRED': {
+            return {
+                ...state,
+                auth_required: action.data,
+            }
+        }
+        case 'UPDATE_OPTION_QUOTE': {
+            return {
+                ...state,
+                option_quotes: {
+                    ...state.option_quotes,
+                    [action.data.symbol]: action.data.quote,
+                }
+            }
+        }
+        case 'UPDATE_OPTION_QUOTES': {
+            return {
+                ...state,
+                option_quotes: {
+                    ...state.option_quotes,
+                    ...action.data,
+                }
+            }
+        }
+        case 'REMOVE_OPTION_QUOTE': {
+            const option_quotes = { ...state.option_quotes };
+            delete option_quotes[action.data];
+            return {
+                ...state,
+                option_quotes,
+            }
+        }
+        case 'UPDATE_POSITIONS': {
+            return {
+                ...state,
+                paper: action.data,
+            }
+        }
+        case 'UPDATE_BROKERAGE': {
+            return {
+                ...state,
+                brokerage: action.data,
+            }
+        }
+        case 'UPDATE_SPREADS': {
+            return {
+                ...state,
+                spreads: {
+                    ...state.spreads,
+                    [action.data.symbol]: action.data.candidates,
+                }
+            }
+        }
         case 'HEARTBEAT': {
             // Don't do anything
             return state;
@@ -0,0 +1,41 @@
+import { useContext } from "react";
+import { AppStateContext, type BookLevel } from "../State";
+
+export function OrderBook({ ticker }: { ticker: string }) {
+    const { order_books } = useContext(AppStateContext);
+    const update = order_books[ticker];
+    if (update == null) {
+        return null;
+    }
+
+    const { book, liquidity } = update;
+    const isLarge = (level: BookLevel) => liquidity.some(l => l.price === level.price);
+    const rows = Array.from({ length: Math.max(book.bids.length, book.asks.length) }, (_, i) => [book.bids[i], book.asks[i]]);
+    return (
+        <section className="order-book">
+            <table>
+                <thead>
+                    <tr>
+                        <th>MPIDs</th>
+                        <th>Bid Size</th>
+                        <th>Bid</th>
+                        <th>Ask</th>
+                        <th>Ask Size</th>
+                        <th>MPIDs</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {rows.map(([bid, ask], i) => (<tr key={i}>
+                        <td>{bid?.entries.map(e => e.mpid).join(' ')}</td>
+                        <td className={bid != null && isLarge(bid) ? 'large' : ''}>{bid?.size}</td>
+                        <td className="bid">{bid?.price.toFixed(2)}</td>
+                        <td className="ask">{ask?.price.toFixed(2)}</td>
+                        <td className={ask != null && isLarge(ask) ? 'large' : ''}>{ask?.size}</td>
+                        <td>{ask?.entries.map(e => e.mpid).join(' ')}</td>
+                    </tr>))}
+                </tbody>
+            </table>
+            <p>{book.venue} at {new Date(book.time).toLocaleTimeString()}</p>
+        </section>
+    );
+}
@@ -1,6 +1,11 @@
-import { useContext, useEffect, useRef } from "react";
+import { useContext, useRef } from "react";
 import { AppReducerContext, AppStateContext, type ReplayMode } from "../State";
 
+type ReplayCommand =
+    | { command: 'step', candles: number }
+    | { command: 'seek', time: string }
+    | { command: 'rewind' };
+
 export function Replay({ ticker }: { ticker: string }) {
     const { replay_mode: mode } = useContext(AppStateContext);
     if (mode == null) {
@@ -9,9 +14,9 @@ export function Replay({ ticker }: { ticker: string }) {
 
     const dispatcher = useContext(AppReducerContext);
     const fetchAbortController = useRef<AbortController>(null);
+    const seekInput = useRef<HTMLInputElement>(null);
 
-    const onReplayUpdate = (newMode: ReplayMode) => {
-        const replayMode = { ...newMode, symbol: ticker };
+    const onReplayUpdate = (replayMode: ReplayMode) => {
         dispatcher({
             action: 'REPLAY_MODE',
             data: replayMode,
@@ -28,10 +33,23 @@ export function Replay({ ticker }: { ticker: string }) {
         }).catch(e => console.warn('Failed to update replay mode:', e));
     };
 
-    useEffect(() => {
-        onReplayUpdate({ ...mode, symbol: ticker });
-    }, [ticker]);
-    
+    const sendCommand = (command: ReplayCommand) => {
+        fetch('/api/ticker/replay', {
+            method: 'post',
+            headers: {
+                'content-type': 'application/json',
+            },
+            body: JSON.stringify(command),
+        }).catch(e => console.warn('Failed to send replay command:', e));
+    };
+
+    const onSeek = () => {
+        const value = seekInput.current?.value;
+        if (value) {
+            sendCommand({ command: 'seek', time: new Date(value).toISOString() });
+        }
+    };
+
     return (
         <>
             <input title="Playback speed for Replay"
@@ -45,10 +63,23 @@ export function Replay({ ticker }: { ticker: string }) {
                 onClick={() => fetch(`/api/ticker/reload?ticker=${ticker}`)}>
                 Reset
             </button>
-            <button title={`${mode.playing ? 'Pause' : 'Play'} ${ticker} candles in replay mode`}
+            <button title={`${mode.playing ? 'Pause' : 'Play'} the candles of all the symbols in replay mode`}
                 onClick={() => onReplayUpdate({ ...mode, playing: !mode.playing })}>
                 {mode.playing ? 'Pause' : 'Play'}
             </button>
+            <button title="Replay the next candle of all the symbols"
+                onClick={() => sendCommand({ command: 'step', candles: 1 })}>
+                Step
+            </button>
+            <button title="Restart the replay of all the symbols"
+                onClick={() => sendCommand({ command: 'rewind' })}>
+                Rewind
+            </button>
+            <input title="Time to seek the replay to" type="datetime-local" ref={seekInput} />
+            <button title="Rebuild all the symbols up to the chosen time" onClick={onSeek}>
+                Seek
+            </button>
+            {mode.time != null && <span>{new Date(mode.time).toLocaleTimeString()}</span>}
         </>
     );
 }
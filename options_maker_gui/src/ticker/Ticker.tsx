@@ -3,6 +3,7 @@ import { useParams } from "react-router";
 import { AppStateContext, type PriceLevel } from "../State";
 import Chart from "./Chart";
 import { Replay } from "./Replay";
+import { OrderBook } from "./OrderBook";
 import { useLastPrice } from "../utils";
 
 import { TextEdit } from "../common";
@@ -132,7 +133,11 @@ export default function Ticker() {
                     </pre>}
                 </div>))}
             </section>
+            <OrderBook ticker={ticker} />
             <section className="metainfo">
+                {symbol.orderFlow.buyVolume + symbol.orderFlow.sellVolume > 0 && <p>
+                    Bought: {symbol.orderFlow.buyVolume} | Sold: {symbol.orderFlow.sellVolume} |&nbsp;
+                </p>}
                 <p>Last Updated: {new Date(symbol.lastUpdated * 1000).toLocaleString()}</p>
             </section>
         </div>
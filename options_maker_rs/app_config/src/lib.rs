@@ -1,4 +1,5 @@
-use chrono::{Duration, NaiveTime};
+use chrono::{Duration, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
@@ -32,9 +33,17 @@ pub struct AppConfig {
     pub openssl_cert_file: String,
     pub openssl_key_file: String,
     pub token_file: String,
+    /// Key of the encrypted `token_file`, generated if missing. `OPTIONS_MAKER_TOKEN_KEY` takes
+    /// precedence, the file is left in plaintext without either.
+    #[serde(default)]
+    pub token_key_file: Option<String>,
     pub schwab_client_id: String,
     pub schwab_client_secret: String,
+    /// OAuth redirect, `/api/auth/callback` of this server, for the first login and to log in
+    /// again without a restart.
     pub schwab_callback_url: String,
+    #[serde(default)]
+    pub schwab_api: SchwabApiConfig,
     pub db_url: String,
     pub http_port: u16,
     pub use_https: bool,
@@ -42,10 +51,29 @@ pub struct AppConfig {
     #[serde(default)]
     pub disable_ws_compression: bool,
 
+    /// Allows placing real orders, needs `schwab_client` to be built with the `trading` feature.
+    #[serde(default)]
+    pub enable_trading: bool,
+
     pub replay_mode: bool,
     pub replay_start_time: Option<String>,
+    /// Replays OHLCV files of other vendors instead of the candles in the database.
+    pub file_provider: Option<FileProviderConfig>,
+    /// Replays generated candles of any symbol, for demos and load tests.
+    pub synthetic_provider: Option<SyntheticProviderConfig>,
 
     pub trade_config: TradeConfig,
+
+    pub backtest: Option<BacktestConfig>,
+
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+
+    #[serde(default)]
+    pub paper_trading: PaperTradingConfig,
+
+    #[serde(default)]
+    pub brokerage: BrokerageConfig,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,14 +82,34 @@ pub struct TradeConfig {
     pub use_extended_hour: bool,
     pub look_back_days: u64,
     pub use_tick_data: bool,
+    /// Builds the tick candles off the time & sales prints instead of the level one quotes.
+    #[serde(default)]
+    pub use_time_sales: bool,
+    /// Keeps the time & sales prints in the `trades` table.
+    #[serde(default)]
+    pub save_trades: bool,
     #[serde(deserialize_with = "parse_trading_hours")]
     pub open_hours: (NaiveTime, NaiveTime),
     #[serde(deserialize_with = "parse_trading_hours")]
     pub trading_hours: (NaiveTime, NaiveTime),
+    /// Session of the futures such as `/ES`, starting in the evening and ending the next day.
+    #[serde(
+        default = "default_futures_hours",
+        deserialize_with = "parse_trading_hours"
+    )]
+    pub futures_hours: (NaiveTime, NaiveTime),
+    /// Timezone of the exchanges, the hours above and the trading days are its wall clock.
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
+    /// Overrides of `timezone` keyed by exchange, the futures go by "CME".
+    #[serde(default)]
+    pub exchange_timezones: HashMap<String, Tz>,
     pub sr_threshold_perc: f64,
     pub enable_gap_fill_sr: bool,
     pub auto_compute_sr: bool,
     pub chart_configs: Vec<ChartConfig>,
+    #[serde(default)]
+    pub order_book: OrderBookConfig,
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,6 +134,205 @@ pub enum DivIndicator {
     Stochastic,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileProviderConfig {
+    /// Directory with a file per symbol, such as `SPY.csv` or `SPY.parquet`.
+    pub dir: PathBuf,
+    #[serde(default)]
+    pub columns: ColumnMapping,
+    /// `chrono` format of the textual times, the only one tried when set. Without it epoch
+    /// seconds or millis and RFC 3339 are recognized.
+    pub time_format: Option<String>,
+    /// Timezone of the times without an offset, the exchange's by default.
+    pub timezone: Option<Tz>,
+    /// Length of a candle in seconds.
+    #[serde(default = "default_candle_secs")]
+    pub candle_secs: i64,
+}
+
+/// Shape of the generated market, the same seed generates the same candles.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyntheticProviderConfig {
+    pub seed: u64,
+    /// Annualized volatility of the prices.
+    pub volatility: f64,
+    /// Average volume of a minute candle.
+    pub base_volume: f64,
+    /// Standard deviation of the overnight gaps, a fraction of the price.
+    pub gap_volatility: f64,
+    /// Chance of a day dipping into the previous day's low and bouncing off it.
+    pub bounce_chance: f64,
+}
+
+impl Default for SyntheticProviderConfig {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            volatility: 0.3,
+            base_volume: 20_000.0,
+            gap_volatility: 0.01,
+            bounce_chance: 0.5,
+        }
+    }
+}
+
+/// Column names of the OHLCV files.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnMapping {
+    pub time: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume: String,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            time: String::from("time"),
+            open: String::from("open"),
+            high: String::from("high"),
+            low: String::from("low"),
+            close: String::from("close"),
+            volume: String::from("volume"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BacktestConfig {
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    #[serde(deserialize_with = "parse_date_ranges")]
+    pub date_ranges: Vec<(NaiveDate, NaiveDate)>,
+    pub report_file: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaperTradingConfig {
+    pub starting_balance: f64,
+    pub commission_per_contract: f64,
+    pub commission_per_share: f64,
+}
+
+impl Default for PaperTradingConfig {
+    fn default() -> Self {
+        Self {
+            starting_balance: 100_000.0,
+            commission_per_contract: 0.65,
+            commission_per_share: 0.0,
+        }
+    }
+}
+
+/// HTTP settings of the Schwab client, `url` can point it at `schwab_mock`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchwabApiConfig {
+    pub url: String,
+    pub connect_timeout_secs: u64,
+    pub timeout_secs: u64,
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>,
+    pub max_retries: u32,
+    pub retry_backoff_ms: u64,
+    /// Schwab allows 120 requests a minute, 0 disables the limit.
+    pub requests_per_minute: u32,
+    /// Requests which may go out back to back before the rate limit kicks in.
+    pub burst: u32,
+}
+
+impl Default for SchwabApiConfig {
+    fn default() -> Self {
+        Self {
+            url: String::from("https://api.schwabapi.com"),
+            connect_timeout_secs: 5,
+            timeout_secs: 30,
+            proxy: None,
+            user_agent: None,
+            max_retries: 3,
+            retry_backoff_ms: 500,
+            requests_per_minute: 120,
+            burst: 10,
+        }
+    }
+}
+
+/// Level 2 books of the tracked symbols, used to spot size resting near the price levels.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OrderBookConfig {
+    pub enabled: bool,
+    /// A book level is large once it holds this many times the median size of its side.
+    pub large_size_ratio: f64,
+    /// Levels per side published to the UI.
+    pub depth: usize,
+}
+
+impl Default for OrderBookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            large_size_ratio: 5.0,
+            depth: 10,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrokerageConfig {
+    pub enabled: bool,
+    pub poll_interval_secs: u64,
+}
+
+impl Default for BrokerageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NotifierConfig {
+    Webhook {
+        url: String,
+    },
+    Ntfy {
+        url: String,
+        topic: String,
+        token: Option<String>,
+    },
+    Smtp {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        security: SmtpSecurity,
+        username: Option<String>,
+        password: Option<String>,
+        from: String,
+        to: Vec<String>,
+    },
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    None,
+    #[default]
+    StartTls,
+    Tls,
+}
+
 #[derive(Debug, Deserialize, Default)]
 #[serde(deny_unknown_fields)]
 pub struct CrawlerConf {
@@ -130,6 +377,19 @@ fn parse_duration(input: &str) -> Result<Duration, Box<dyn std::error::Error>> {
     }
 }
 
+fn default_futures_hours() -> (NaiveTime, NaiveTime) {
+    let hour = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+    (hour(18), hour(17))
+}
+
+fn default_candle_secs() -> i64 {
+    60
+}
+
+fn default_timezone() -> Tz {
+    chrono_tz::America::New_York
+}
+
 fn parse_trading_hours<'de, D>(deserializer: D) -> Result<(NaiveTime, NaiveTime), D::Error>
 where
     D: Deserializer<'de>,
@@ -144,3 +404,26 @@ where
     });
     Ok((times.next().unwrap()?, times.next().unwrap()?))
 }
+
+fn parse_date_ranges<'de, D>(deserializer: D) -> Result<Vec<(NaiveDate, NaiveDate)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let ranges: Vec<(String, String)> = Deserialize::deserialize(deserializer)?;
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let parse = |s: &str| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .map_err(|e| Error::custom(format!("Failed to parse '{s}' as NaiveDate: {e}")))
+            };
+            let (start, end) = (parse(&start)?, parse(&end)?);
+            if start > end {
+                return Err(Error::custom(format!(
+                    "Invalid date range: {start} > {end}"
+                )));
+            }
+            Ok((start, end))
+        })
+        .collect()
+}
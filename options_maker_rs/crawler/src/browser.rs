@@ -61,12 +61,11 @@ fn try_connect_existing_session() -> anyhow::Result<Browser> {
                 .map(|process| process.to_string_lossy().to_lowercase().contains("chrome"))
                 .unwrap_or_default()
         })
-        .filter(|p| {
+        .find(|p| {
             p.cmd()
                 .iter()
                 .any(|arg| arg.to_string_lossy().starts_with(REMOTE_DEBUG_ARG))
         })
-        .next()
         .ok_or_else(|| anyhow::anyhow!("No Chrome process with remote debug port found"))?;
     info!(
         "Found a chrome process with debug enabled: {:?}",
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::{task, time};
 use tracing::{debug, error, info, warn};
-use util::time::TradingDay;
+use util::time::{MarketTime, TradingDay};
 
 mod browser;
 mod fundamentals;
@@ -38,10 +38,10 @@ async fn run_scanner() -> anyhow::Result<()> {
     let now = util::time::now();
     let last_updated = persist::crawler::scanner_last_updated().await?;
     if let Some(last_updated) = last_updated
-        && (now.date_naive().is_weekend()
-            || now.naive_local().time() <= trading_end
-            || (now.date_naive() == last_updated.date_naive()
-                && last_updated.naive_local().time() > trading_end))
+        && (!now.market_date().is_trading_day()
+            || now.market_time() <= trading_end
+            || (now.market_date() == last_updated.market_date()
+                && last_updated.market_time() > trading_end))
     {
         debug!("Scanned result was updated recently {last_updated:?}, no need to update now");
         return Ok(());
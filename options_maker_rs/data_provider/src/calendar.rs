@@ -0,0 +1,46 @@
+use crate::provider;
+use std::time::Duration;
+use tracing::{info, warn};
+use util::calendar::MarketCalendar;
+use util::time::MarketTime;
+
+/// Market hours are fetched for today and this many days after it.
+const DAYS_AHEAD: usize = 7;
+const SYNC_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// Feeds the calendar with the days cached in the database, going back a year and a half.
+pub(crate) async fn load_calendar() -> anyhow::Result<()> {
+    let start = util::time::days_ago(550).date_naive();
+    let days = persist::calendar::load_market_days(start).await?;
+    info!("Loaded {} market days from the database", days.len());
+    MarketCalendar::update(days);
+    Ok(())
+}
+
+/// Keeps the upcoming days in sync with the data provider, unscheduled closures get announced
+/// on short notice.
+pub(crate) fn start_calendar_sync() {
+    tokio::spawn(async {
+        loop {
+            if let Err(e) = sync_calendar().await {
+                warn!("Failed to sync the market calendar: {e}");
+            }
+            tokio::time::sleep(SYNC_INTERVAL).await;
+        }
+    });
+}
+
+async fn sync_calendar() -> anyhow::Result<()> {
+    let today = util::time::now().market_date();
+    let mut days = Vec::with_capacity(DAYS_AHEAD + 1);
+    for date in today.iter_days().take(DAYS_AHEAD + 1) {
+        let Some(day) = provider().fetch_market_day(date).await? else {
+            return Ok(()); // The provider doesn't know market hours, the bundled table it is
+        };
+        days.push(day);
+    }
+    persist::calendar::save_market_days(&days).await?;
+    info!("Synced {} market days, starting {today}", days.len());
+    MarketCalendar::update(days);
+    Ok(())
+}
@@ -0,0 +1,451 @@
+use crate::replay::Replayer;
+use crate::schwab::log_candles;
+use crate::{DataProvider, ReplayCmd, ReplayInfo};
+use anyhow::{Context, anyhow};
+use app_config::{ColumnMapping, FileProviderConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use schwab_client::streaming_client::StreamResponse;
+use schwab_client::{
+    Account, Candle, FUTURES_EXCHANGE, Instrument, OptionChain, OptionChainParams, is_futures,
+};
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tracing::info;
+
+/// Textual times tried when no `time_format` is configured, all in the wall clock of the file.
+const TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+];
+
+/// Replays OHLCV files of other vendors, a CSV or Parquet file per symbol in a directory.
+pub struct FileProvider {
+    config: &'static FileProviderConfig,
+    replayer: Replayer,
+}
+
+impl FileProvider {
+    pub async fn init(config: &'static FileProviderConfig) -> anyhow::Result<Self> {
+        if !config.dir.is_dir() {
+            return Err(anyhow!("{:?} is not a directory", config.dir));
+        }
+        info!("Serving price history from the files in {:?}", config.dir);
+        Ok(Self {
+            config,
+            replayer: Replayer::start(),
+        })
+    }
+
+    /// File of `symbol`, futures go without their leading slash, e.g. `ES.csv` for `/ES`.
+    fn find_file(&self, symbol: &str) -> Option<PathBuf> {
+        let name = symbol.trim_start_matches('/');
+        ["csv", "parquet"]
+            .iter()
+            .map(|ext| self.config.dir.join(format!("{name}.{ext}")))
+            .find(|path| path.is_file())
+    }
+
+    async fn read_candles(&self, symbol: &str) -> anyhow::Result<Vec<Candle>> {
+        let path = self.find_file(symbol).with_context(|| {
+            format!(
+                "No CSV or Parquet file of {symbol} in {:?}",
+                self.config.dir
+            )
+        })?;
+        let reader = CandleReader {
+            columns: &self.config.columns,
+            time_format: self.config.time_format.as_deref(),
+            tz: self.config.timezone.unwrap_or_else(util::time::market_tz),
+            duration: self.config.candle_secs,
+        };
+        let mut candles = tokio::task::spawn_blocking(move || {
+            let file = File::open(&path).with_context(|| format!("Failed to open {path:?}"))?;
+            if path.extension().is_some_and(|ext| ext == "parquet") {
+                reader.read_parquet(file)
+            } else {
+                reader.read_csv(file)
+            }
+            .with_context(|| format!("Failed to read {path:?}"))
+        })
+        .await??;
+        candles.sort_by_key(|c| c.time);
+        candles.dedup_by_key(|c| c.time);
+        Ok(candles)
+    }
+}
+
+#[async_trait]
+impl DataProvider for FileProvider {
+    async fn search_symbol(&self, symbol: &str) -> anyhow::Result<Instrument> {
+        let symbol = symbol.trim().to_uppercase();
+        let path = self.find_file(&symbol).with_context(|| {
+            format!(
+                "No CSV or Parquet file of {symbol} in {:?}",
+                self.config.dir
+            )
+        })?;
+        let (exchange, asset_type) = if is_futures(&symbol) {
+            (FUTURES_EXCHANGE, "FUTURE")
+        } else {
+            ("FILE", "EQUITY")
+        };
+        Ok(Instrument {
+            description: format!("{symbol} from {}", path.display()),
+            symbol,
+            exchange: exchange.to_owned(),
+            asset_type: asset_type.to_owned(),
+            cusip: None,
+            fundamental: None,
+        })
+    }
+
+    async fn fetch_price_history(
+        &self,
+        symbol: &str,
+        start: DateTime<Local>,
+    ) -> anyhow::Result<(Vec<Candle>, Vec<Candle>)> {
+        let mut candles = self.read_candles(symbol).await?;
+        candles.retain(|c| c.time >= start);
+        log_candles(format!("Loaded from file for {symbol}"), &candles);
+        self.replayer.load(symbol, candles).await
+    }
+
+    async fn fetch_option_chain(
+        &self,
+        _symbol: &str,
+        _params: &OptionChainParams,
+    ) -> anyhow::Result<OptionChain> {
+        Err(anyhow!("Option chains are not available from files"))
+    }
+
+    async fn fetch_accounts(&self) -> anyhow::Result<Vec<Account>> {
+        Err(anyhow!("Accounts are not available from files"))
+    }
+
+    fn listener(&self) -> mpsc::UnboundedReceiver<StreamResponse> {
+        self.replayer.listener()
+    }
+
+    fn sub_charts(&self, _symbols: Vec<String>) {}
+
+    fn unsub_charts(&self, _symbols: Vec<String>) {}
+
+    fn sub_tick(&self, _symbols: Vec<String>) {}
+
+    fn unsub_tick(&self, _symbols: Vec<String>) {}
+
+    fn sub_options(&self, _symbols: Vec<String>) {}
+
+    fn unsub_options(&self, _symbols: Vec<String>) {}
+
+    async fn replay_info(&self, update: Option<ReplayInfo>) -> Option<ReplayInfo> {
+        Some(self.replayer.replay_info(update).await)
+    }
+
+    async fn replay_cmd(&self, cmd: ReplayCmd) -> anyhow::Result<()> {
+        self.replayer.control(cmd).await;
+        Ok(())
+    }
+}
+
+/// Turns the rows of an OHLCV file into candles.
+struct CandleReader<'a> {
+    columns: &'a ColumnMapping,
+    time_format: Option<&'a str>,
+    tz: Tz,
+    duration: i64,
+}
+
+impl CandleReader<'_> {
+    fn read_csv(&self, reader: impl Read) -> anyhow::Result<Vec<Candle>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+        let headers = reader.headers()?.clone();
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.eq_ignore_ascii_case(name))
+                .with_context(|| format!("Missing column {name:?} in {headers:?}"))
+        };
+        let columns = self.columns;
+        let time = column(&columns.time)?;
+        let open = column(&columns.open)?;
+        let high = column(&columns.high)?;
+        let low = column(&columns.low)?;
+        let close = column(&columns.close)?;
+        let volume = column(&columns.volume)?;
+
+        let mut candles = Vec::new();
+        for (line, record) in reader.records().enumerate() {
+            let record = record?;
+            let field = |idx: usize| record.get(idx).unwrap_or_default();
+            let number = |idx: usize| {
+                field(idx)
+                    .parse::<f64>()
+                    .with_context(|| format!("Invalid number {:?} in row {}", field(idx), line + 1))
+            };
+            let time = self
+                .parse_time(field(time))
+                .with_context(|| format!("Invalid time {:?} in row {}", field(time), line + 1))?;
+            candles.push(Candle {
+                open: number(open)?,
+                low: number(low)?,
+                high: number(high)?,
+                close: number(close)?,
+                volume: number(volume)? as u64,
+                time,
+                duration: self.duration,
+            });
+        }
+        Ok(candles)
+    }
+
+    #[cfg(feature = "parquet")]
+    fn read_parquet(&self, file: File) -> anyhow::Result<Vec<Candle>> {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::Field;
+
+        let number = |field: &Field| match *field {
+            Field::Byte(n) => Some(n as f64),
+            Field::Short(n) => Some(n as f64),
+            Field::Int(n) => Some(n as f64),
+            Field::Long(n) => Some(n as f64),
+            Field::UByte(n) => Some(n as f64),
+            Field::UShort(n) => Some(n as f64),
+            Field::UInt(n) => Some(n as f64),
+            Field::ULong(n) => Some(n as f64),
+            Field::Float(n) => Some(n as f64),
+            Field::Double(n) => Some(n),
+            _ => None,
+        };
+        let time = |field: &Field| match field {
+            Field::TimestampMillis(millis) => Some(util::time::from_ts_millis(*millis)),
+            Field::TimestampMicros(micros) => Some(util::time::from_ts_millis(micros / 1000)),
+            Field::Int(n) => Some(from_epoch(*n as i64)),
+            Field::Long(n) => Some(from_epoch(*n)),
+            Field::Str(s) => self.parse_time(s),
+            Field::Date(days) => {
+                let date = NaiveDate::from_num_days_from_ce_opt(days + 719_163)?;
+                Some(util::time::from_wall_clock(
+                    self.tz,
+                    date.and_time(Default::default()),
+                ))
+            }
+            _ => None,
+        };
+
+        let reader = SerializedFileReader::new(file)?;
+        let columns = self.columns;
+        let mut candles = Vec::new();
+        for (line, row) in reader.get_row_iter(None)?.enumerate() {
+            let row = row?;
+            let column = |name: &str| {
+                row.get_column_iter()
+                    .find(|(column, _)| column.eq_ignore_ascii_case(name))
+                    .map(|(_, field)| field)
+                    .with_context(|| format!("Missing column {name:?} in row {}", line + 1))
+            };
+            let number = |name: &str| {
+                column(name).and_then(|field| {
+                    number(field)
+                        .with_context(|| format!("Invalid number {field} in row {}", line + 1))
+                })
+            };
+            let field = column(&columns.time)?;
+            let time =
+                time(field).with_context(|| format!("Invalid time {field} in row {}", line + 1))?;
+            candles.push(Candle {
+                open: number(&columns.open)?,
+                low: number(&columns.low)?,
+                high: number(&columns.high)?,
+                close: number(&columns.close)?,
+                volume: number(&columns.volume)? as u64,
+                time,
+                duration: self.duration,
+            });
+        }
+        Ok(candles)
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    fn read_parquet(&self, _file: File) -> anyhow::Result<Vec<Candle>> {
+        Err(anyhow!(
+            "Parquet files need data_provider built with the `parquet` feature"
+        ))
+    }
+
+    /// An explicit `time_format` wins, compact formats like `%Y%m%d%H%M` are all digits too.
+    fn parse_time(&self, input: &str) -> Option<DateTime<Local>> {
+        if let Some(format) = self.time_format {
+            let naive = NaiveDateTime::parse_from_str(input, format)
+                .ok()
+                .or_else(|| {
+                    NaiveDate::parse_from_str(input, format)
+                        .ok()
+                        .map(|date| date.and_time(Default::default()))
+                })?;
+            return Some(util::time::from_wall_clock(self.tz, naive));
+        }
+        if let Ok(epoch) = input.parse::<i64>() {
+            return Some(from_epoch(epoch));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+            return Some(time.with_timezone(&Local));
+        }
+        TIME_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+            .map(|naive| util::time::from_wall_clock(self.tz, naive))
+    }
+}
+
+/// Epoch seconds or millis, anything past the year 5138 in seconds is taken as millis.
+fn from_epoch(epoch: i64) -> DateTime<Local> {
+    if epoch.abs() >= 100_000_000_000 {
+        util::time::from_ts_millis(epoch)
+    } else {
+        util::time::from_ts(epoch)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CandleReader;
+    use app_config::ColumnMapping;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_read_csv() {
+        let columns = ColumnMapping {
+            time: "Date".to_owned(),
+            volume: "Vol".to_owned(),
+            ..ColumnMapping::default()
+        };
+        let reader = CandleReader {
+            columns: &columns,
+            time_format: None,
+            tz: chrono_tz::America::New_York,
+            duration: 60,
+        };
+        let csv = "\
+Date, Open, High, Low, Close, Vol
+2025-07-01 09:30, 100.0, 101.0, 99.5, 100.5, 1200
+1751376660000, 100.5, 101.5, 100.0, 101.0, 800.0
+2025-07-01T09:32:00-04:00, 101.0, 101.2, 100.8, 101.1, 500
+";
+        let candles = reader.read_csv(csv.as_bytes()).unwrap();
+        assert_eq!(candles.len(), 3);
+        let utc = |minute| Utc.with_ymd_and_hms(2025, 7, 1, 13, minute, 0).unwrap();
+        assert_eq!(candles[0].time, utc(30));
+        assert_eq!(candles[1].time, utc(31));
+        assert_eq!(candles[2].time, utc(32));
+        assert_eq!(candles[0].high, 101.0);
+        assert_eq!(candles[1].volume, 800);
+
+        assert!(reader.read_csv("Date,Open\n".as_bytes()).is_err());
+        let invalid = "Date,Open,High,Low,Close,Vol\nyesterday,1,1,1,1,1\n";
+        assert!(reader.read_csv(invalid.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_read_compact_time() {
+        let columns = ColumnMapping::default();
+        let mut reader = CandleReader {
+            columns: &columns,
+            time_format: Some("%Y%m%d%H%M"),
+            tz: chrono_tz::America::New_York,
+            duration: 60,
+        };
+        let csv = "\
+time,open,high,low,close,volume
+202507010930,100.0,101.0,99.5,100.5,1200
+";
+        let candles = reader.read_csv(csv.as_bytes()).unwrap();
+        let utc = Utc.with_ymd_and_hms(2025, 7, 1, 13, 30, 0).unwrap();
+        assert_eq!(candles[0].time, utc);
+
+        // Daily bars only have the date
+        reader.time_format = Some("%Y%m%d");
+        let csv = "time,open,high,low,close,volume\n20250701,100,101,99,100,1200\n";
+        let candles = reader.read_csv(csv.as_bytes()).unwrap();
+        let utc = Utc.with_ymd_and_hms(2025, 7, 1, 4, 0, 0).unwrap();
+        assert_eq!(candles[0].time, utc);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_read_parquet() -> anyhow::Result<()> {
+        use parquet::data_type::{DoubleType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::fs::File;
+        use std::sync::Arc;
+
+        let schema = parse_message_type(
+            "message candle {
+                REQUIRED INT64 time (TIMESTAMP(MILLIS, true));
+                REQUIRED DOUBLE open;
+                REQUIRED DOUBLE high;
+                REQUIRED DOUBLE low;
+                REQUIRED DOUBLE close;
+                REQUIRED INT64 volume;
+            }",
+        )?;
+        let path = std::env::temp_dir().join(format!("candles-{}.parquet", std::process::id()));
+        let properties = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(File::create(&path)?, Arc::new(schema), properties)?;
+        let mut row_group = writer.next_row_group()?;
+        let mut idx = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            match idx {
+                0 => column.typed::<Int64Type>().write_batch(
+                    &[1751376600000, 1751376660000],
+                    None,
+                    None,
+                )?,
+                5 => column
+                    .typed::<Int64Type>()
+                    .write_batch(&[1200, 800], None, None)?,
+                _ => column.typed::<DoubleType>().write_batch(
+                    &[100.0 + idx as f64, 101.0],
+                    None,
+                    None,
+                )?,
+            };
+            column.close()?;
+            idx += 1;
+        }
+        row_group.close()?;
+        writer.close()?;
+
+        let columns = ColumnMapping::default();
+        let reader = CandleReader {
+            columns: &columns,
+            time_format: None,
+            tz: chrono_tz::America::New_York,
+            duration: 60,
+        };
+        let candles = reader.read_parquet(File::open(&path)?);
+        std::fs::remove_file(&path)?;
+        let candles = candles?;
+        assert_eq!(candles.len(), 2);
+        assert_eq!(
+            candles[0].time,
+            Utc.with_ymd_and_hms(2025, 7, 1, 13, 30, 0).unwrap()
+        );
+        assert_eq!((candles[0].open, candles[0].close), (101.0, 104.0));
+        assert_eq!(candles[1].volume, 800);
+        Ok(())
+    }
+}
@@ -1,27 +1,57 @@
+mod calendar;
+mod file;
 mod replay;
 mod schwab;
+mod subscriptions;
+pub mod synthetic;
 mod time_helper;
 
+use crate::file::FileProvider;
 use crate::replay::ReplayProvider;
 use crate::schwab::SchwabProvider;
+pub use crate::subscriptions::{sub_tick, unsub_tick};
+use crate::synthetic::SyntheticProvider;
 use app_config::APP_CONFIG;
 use async_trait::async_trait;
-use chrono::{DateTime, Local};
-use schwab_client::{Candle, Instrument};
+use chrono::{DateTime, Local, NaiveDate};
+use schwab_client::{Account, Candle, Instrument, OptionChain, OptionChainParams};
 
 use schwab_client::streaming_client::StreamResponse;
 use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
 use tokio::sync::mpsc;
 use tracing::info;
+use util::calendar::MarketDay;
 
 static PROVIDER: OnceLock<Box<dyn DataProvider + Send + Sync>> = OnceLock::new();
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReplayInfo {
     playing: bool,
+    /// Milliseconds between two steps of the replay clock.
     speed: u64,
-    symbol: String,
+    /// Time of the last candles replayed.
+    #[serde(default)]
+    time: Option<DateTime<Local>>,
+}
+
+/// Moves the replay clock, playback pauses on any of them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ReplayCmd {
+    /// Replays the next `candles` steps of the clock right away.
+    Step { candles: usize },
+    /// Jumps to `time`, the analyzer has to rebuild its state up to it.
+    Seek { time: DateTime<Local> },
+    /// Jumps back to the start of the replay, the analyzer has to rebuild its state.
+    Rewind,
+}
+
+impl ReplayCmd {
+    /// Whether the candles the analyzer has seen no longer match the replay clock.
+    pub fn needs_rebuild(&self) -> bool {
+        !matches!(self, ReplayCmd::Step { .. })
+    }
 }
 
 #[async_trait]
@@ -33,6 +63,20 @@ pub trait DataProvider {
         start: DateTime<Local>,
     ) -> anyhow::Result<(Vec<Candle>, Vec<Candle>)>;
 
+    async fn fetch_option_chain(
+        &self,
+        symbol: &str,
+        params: &OptionChainParams,
+    ) -> anyhow::Result<OptionChain>;
+
+    /// Equity market sessions of `date`, `None` when the provider doesn't know them.
+    async fn fetch_market_day(&self, _date: NaiveDate) -> anyhow::Result<Option<MarketDay>> {
+        Ok(None)
+    }
+
+    /// Brokerage accounts along with their open positions.
+    async fn fetch_accounts(&self) -> anyhow::Result<Vec<Account>>;
+
     fn listener(&self) -> mpsc::UnboundedReceiver<StreamResponse>;
 
     fn sub_charts(&self, symbols: Vec<String>);
@@ -43,15 +87,66 @@ pub trait DataProvider {
 
     fn unsub_tick(&self, symbols: Vec<String>);
 
+    fn sub_options(&self, symbols: Vec<String>);
+
+    fn unsub_options(&self, symbols: Vec<String>);
+
+    /// Level 2 books of equities and option contracts, streamed as [StreamResponse::Book].
+    fn sub_book(&self, _symbols: Vec<String>) {}
+
+    fn unsub_book(&self, _symbols: Vec<String>) {}
+
+    /// Time & sales prints, streamed as [StreamResponse::TimeSale].
+    fn sub_trades(&self, _symbols: Vec<String>) {}
+
+    fn unsub_trades(&self, _symbols: Vec<String>) {}
+
+    /// Whether [Self::sub_trades] streams the prints of `symbol`.
+    fn streams_trades(&self, _symbol: &str) -> bool {
+        false
+    }
+
+    /// Order fills, cancels and status changes of the brokerage accounts.
+    fn sub_account_activity(&self) {}
+
     async fn replay_info(&self, _update: Option<ReplayInfo>) -> Option<ReplayInfo> {
         None
     }
+
+    async fn replay_cmd(&self, _cmd: ReplayCmd) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "Replay commands are only available in REPLAY mode"
+        ))
+    }
+
+    /// Expiry of the brokerage login, `None` when the provider doesn't need one.
+    fn login_expires_at(&self) -> Option<DateTime<Local>> {
+        None
+    }
+
+    /// Brokerage login page which redirects back to `redirect_uri` with a code for [Self::login].
+    fn login_url(&self, _redirect_uri: &str, _state: &str) -> anyhow::Result<String> {
+        Err(anyhow::anyhow!("Data provider doesn't need a login"))
+    }
+
+    async fn login(&self, _code: &str, _redirect_uri: &str) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("Data provider doesn't need a login"))
+    }
 }
 
 pub async fn init() -> anyhow::Result<()> {
     let provider = if APP_CONFIG.replay_mode {
         info!("\n\n================= Running the server in REPLAY mode =================\n");
-        Box::new(ReplayProvider::init().await?) as Box<dyn DataProvider + Send + Sync>
+        match (&APP_CONFIG.file_provider, &APP_CONFIG.synthetic_provider) {
+            (Some(_), Some(_)) => {
+                anyhow::bail!("Only one of file_provider and synthetic_provider can be configured")
+            }
+            (Some(config), None) => {
+                Box::new(FileProvider::init(config).await?) as Box<dyn DataProvider + Send + Sync>
+            }
+            (None, Some(config)) => Box::new(SyntheticProvider::init(config).await?),
+            (None, None) => Box::new(ReplayProvider::init().await?),
+        }
     } else {
         info!("Initializing Schwab client");
         Box::new(SchwabProvider::init().await?) as Box<dyn DataProvider + Send + Sync>
@@ -59,9 +154,34 @@ pub async fn init() -> anyhow::Result<()> {
     PROVIDER
         .set(provider)
         .unwrap_or_else(|_| panic!("Failed to initialize DataProvider"));
+
+    calendar::load_calendar().await?;
+    calendar::start_calendar_sync();
     Ok(())
 }
 
+/// Whether [init] is done, the Schwab provider waits for the first login without a valid refresh
+/// token.
+pub fn is_ready() -> bool {
+    PROVIDER.get().is_some()
+}
+
+/// Login page of the provider, or of the first login it's waiting for.
+pub fn login_url(redirect_uri: &str, state: &str) -> anyhow::Result<String> {
+    match PROVIDER.get() {
+        Some(provider) => provider.login_url(redirect_uri, state),
+        None => schwab::first_login_url(redirect_uri, state),
+    }
+}
+
+/// Completes the login started off [login_url].
+pub async fn login(code: &str, redirect_uri: &str) -> anyhow::Result<()> {
+    match PROVIDER.get() {
+        Some(provider) => provider.login(code, redirect_uri).await,
+        None => schwab::first_login(code, redirect_uri).await,
+    }
+}
+
 #[allow(clippy::borrowed_box)]
 pub fn provider() -> &'static Box<dyn DataProvider + Send + Sync> {
     PROVIDER.get().unwrap()
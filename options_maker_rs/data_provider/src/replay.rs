@@ -1,116 +1,252 @@
 use crate::schwab::log_candles;
 use crate::time_helper::{parse_datetime, split_by_last_work_day};
-use crate::{DataProvider, ReplayInfo};
+use crate::{DataProvider, ReplayCmd, ReplayInfo};
 use app_config::APP_CONFIG;
 use async_trait::async_trait;
 use chrono::{DateTime, Local};
 use rustc_hash::FxHashMap;
 use schwab_client::streaming_client::StreamResponse;
-use schwab_client::{Candle, Instrument};
+use schwab_client::{Account, Candle, Instrument, OptionChain, OptionChainParams, is_futures};
 use std::sync::{Arc, RwLock};
-use std::time::{Duration, Instant};
-use tokio::sync::{Mutex, mpsc};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, mpsc};
 use tracing::info;
 
 pub struct ReplayProvider {
-    replay_data: Arc<Mutex<FxHashMap<String, Vec<Candle>>>>,
+    replayer: Replayer,
+}
+
+/// Streams the candles held back from the price history at the replay speed. A single clock
+/// drives every loaded symbol, so the watchlist moves together. Shared by the providers which
+/// replay recorded data.
+pub(crate) struct Replayer {
+    clock: Arc<Mutex<ReplayClock>>,
     replay_info: Arc<Mutex<ReplayInfo>>,
+    changed: Arc<Notify>,
     senders: Arc<RwLock<Vec<mpsc::UnboundedSender<StreamResponse>>>>,
 }
 
+/// Candles left to replay of every symbol, released in timestamp order.
+#[derive(Default)]
+pub(crate) struct ReplayClock {
+    /// Oldest candle last, so the next one pops off the end.
+    candles: FxHashMap<String, Vec<Candle>>,
+    time: Option<DateTime<Local>>,
+}
+
 impl ReplayProvider {
     pub async fn init() -> anyhow::Result<Self> {
-        let replay_data = Arc::new(Mutex::new(FxHashMap::<String, Vec<Candle>>::default()));
+        Ok(Self {
+            replayer: Replayer::start(),
+        })
+    }
+}
+
+impl Replayer {
+    pub fn start() -> Self {
+        let clock = Arc::new(Mutex::new(ReplayClock::default()));
         let replay_info = Arc::new(Mutex::new(ReplayInfo {
             playing: false,
             speed: 500,
-            symbol: String::default(),
+            time: None,
         }));
+        let changed = Arc::new(Notify::new());
         let senders = Arc::new(RwLock::new(Vec::<mpsc::UnboundedSender<_>>::new()));
 
         tokio::spawn({
-            let replay_data = replay_data.clone();
+            let clock = clock.clone();
             let replay_info = replay_info.clone();
+            let changed = changed.clone();
             let senders = senders.clone();
             async move {
-                let mut last_sent = Instant::now();
                 loop {
-                    tokio::time::sleep(Duration::from_millis(50)).await;
-                    let replay_info = replay_info.lock().await;
-                    if !replay_info.playing
-                        || replay_info.symbol.is_empty()
-                        || last_sent.elapsed() < Duration::from_millis(replay_info.speed)
-                    {
+                    let ReplayInfo { playing, speed, .. } = replay_info.lock().await.clone();
+                    if !playing {
+                        changed.notified().await;
                         continue;
                     }
+                    // A speed change shouldn't wait for the previous interval to run out
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_millis(speed)) => {}
+                        _ = changed.notified() => continue,
+                    }
 
-                    if let Some(candles) = replay_data.lock().await.get_mut(&replay_info.symbol)
-                        && let Some(candle) = candles.pop()
-                    {
-                        let symbol = replay_info.symbol.clone();
-                        let response = StreamResponse::Equity { symbol, candle };
-                        for sender in &*senders.read().unwrap() {
-                            sender.send(response.clone()).ok();
-                        }
-
-                        last_sent = Instant::now();
+                    for (symbol, candle) in clock.lock().await.step() {
+                        send_candle(&senders, symbol, candle);
                     }
                 }
             }
         });
 
-        Ok(Self {
-            replay_data,
+        Self {
+            clock,
             replay_info,
+            changed,
             senders,
-        })
-    }
-}
-
-#[async_trait]
-impl DataProvider for ReplayProvider {
-    async fn search_symbol(&self, _symbol: &str) -> anyhow::Result<Instrument> {
-        Err(anyhow::anyhow!("Can't add new symbols in REPLAY mode"))
+        }
     }
 
-    async fn fetch_price_history(
+    /// Splits the candles into the history the analyzer starts with and the ones to replay,
+    /// at `replay_start_time` or else the last working day.
+    pub async fn load(
         &self,
         symbol: &str,
-        start: DateTime<Local>,
+        candles: Vec<Candle>,
     ) -> anyhow::Result<(Vec<Candle>, Vec<Candle>)> {
         let replay_start = APP_CONFIG
             .replay_start_time
             .as_ref()
             .map(|t| parse_datetime(t))
             .transpose()?;
-        let candles = persist::prices::load_prices(symbol, start, None).await?;
-        log_candles("Loaded for replay", &candles);
-        let (init_batch, mut replay_batch) = if let Some(replay_start) = replay_start {
+        let (init_batch, replay_batch) = if let Some(replay_start) = replay_start {
             info!("Will replay data after {replay_start}");
             candles.into_iter().partition(|c| c.time < replay_start)
         } else {
             info!("Will replay data after last working day");
-            split_by_last_work_day(candles)
+            split_by_last_work_day(symbol, candles)
         };
         log_candles(format!("Replay for {symbol}"), &replay_batch);
-        replay_batch.reverse();
-        let mut update_batch = Vec::new();
-        if let Some(last) = replay_batch.pop() {
-            update_batch.push(last);
-        }
-        self.replay_data
-            .lock()
-            .await
-            .insert(symbol.to_owned(), replay_batch);
+        let update_batch = self.clock.lock().await.load(symbol, replay_batch);
         Ok((init_batch, update_batch))
     }
 
-    fn listener(&self) -> mpsc::UnboundedReceiver<StreamResponse> {
+    pub fn listener(&self) -> mpsc::UnboundedReceiver<StreamResponse> {
         let (tx, rx) = mpsc::unbounded_channel();
         self.senders.write().unwrap().push(tx);
         rx
     }
 
+    /// Applies the play state and speed of `update`, the clock time can't be set from outside.
+    pub async fn replay_info(&self, update: Option<ReplayInfo>) -> ReplayInfo {
+        let mut replay_info = self.replay_info.lock().await;
+        if let Some(update) = update {
+            replay_info.playing = update.playing;
+            replay_info.speed = update.speed;
+            self.changed.notify_one();
+        }
+        replay_info.time = self.clock.lock().await.time;
+        replay_info.clone()
+    }
+
+    /// Seeking and rewinding only move the clock, the symbols are queued again as they reload.
+    pub async fn control(&self, cmd: ReplayCmd) {
+        info!("Replay command: {cmd:?}");
+        self.replay_info.lock().await.playing = false;
+        self.changed.notify_one();
+
+        let mut clock = self.clock.lock().await;
+        match cmd {
+            ReplayCmd::Step { candles } => {
+                for _ in 0..candles {
+                    let step = clock.step();
+                    if step.is_empty() {
+                        break;
+                    }
+                    for (symbol, candle) in step {
+                        send_candle(&self.senders, symbol, candle);
+                    }
+                }
+            }
+            ReplayCmd::Seek { time } => clock.reset(Some(time)),
+            ReplayCmd::Rewind => clock.reset(None),
+        }
+    }
+}
+
+fn send_candle(
+    senders: &RwLock<Vec<mpsc::UnboundedSender<StreamResponse>>>,
+    symbol: String,
+    candle: Candle,
+) {
+    let response = if is_futures(&symbol) {
+        StreamResponse::Futures { symbol, candle }
+    } else {
+        StreamResponse::Equity { symbol, candle }
+    };
+    for sender in &*senders.read().unwrap() {
+        sender.send(response.clone()).ok();
+    }
+}
+
+impl ReplayClock {
+    /// Queues the replay candles of `symbol` and returns the ones the clock has already passed,
+    /// at least the first one.
+    pub fn load(&mut self, symbol: &str, mut candles: Vec<Candle>) -> Vec<Candle> {
+        candles.sort_by_key(|c| c.time);
+        let passed = self
+            .time
+            .map_or(0, |time| candles.partition_point(|c| c.time <= time))
+            .max(candles.len().min(1));
+        let mut remaining = candles.split_off(passed);
+        remaining.reverse();
+        self.candles.insert(symbol.to_owned(), remaining);
+        candles
+    }
+
+    /// Advances to the earliest pending timestamp and releases the candles of every symbol at
+    /// that time, nothing once all the symbols are exhausted.
+    pub fn step(&mut self) -> Vec<(String, Candle)> {
+        let Some(next) = self
+            .candles
+            .values()
+            .filter_map(|candles| candles.last())
+            .map(|c| c.time)
+            .min()
+        else {
+            return Vec::new();
+        };
+        let mut released = self
+            .candles
+            .iter_mut()
+            .filter(|(_, candles)| candles.last().is_some_and(|c| c.time == next))
+            .filter_map(|(symbol, candles)| Some((symbol.clone(), candles.pop()?)))
+            .collect::<Vec<_>>();
+        released.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.time = Some(self.time.map_or(next, |time| time.max(next)));
+        released
+    }
+
+    /// Moves the clock to `time` and drops the queued candles, `None` restarts the replay.
+    pub fn reset(&mut self, time: Option<DateTime<Local>>) {
+        self.time = time;
+        self.candles.clear();
+    }
+}
+
+#[async_trait]
+impl DataProvider for ReplayProvider {
+    async fn search_symbol(&self, _symbol: &str) -> anyhow::Result<Instrument> {
+        Err(anyhow::anyhow!("Can't add new symbols in REPLAY mode"))
+    }
+
+    async fn fetch_price_history(
+        &self,
+        symbol: &str,
+        start: DateTime<Local>,
+    ) -> anyhow::Result<(Vec<Candle>, Vec<Candle>)> {
+        let candles = persist::prices::load_prices(symbol, start, None).await?;
+        log_candles("Loaded for replay", &candles);
+        self.replayer.load(symbol, candles).await
+    }
+
+    async fn fetch_option_chain(
+        &self,
+        _symbol: &str,
+        _params: &OptionChainParams,
+    ) -> anyhow::Result<OptionChain> {
+        Err(anyhow::anyhow!(
+            "Option chains are not available in REPLAY mode"
+        ))
+    }
+
+    async fn fetch_accounts(&self) -> anyhow::Result<Vec<Account>> {
+        Err(anyhow::anyhow!("Accounts are not available in REPLAY mode"))
+    }
+
+    fn listener(&self) -> mpsc::UnboundedReceiver<StreamResponse> {
+        self.replayer.listener()
+    }
+
     fn sub_charts(&self, _symbols: Vec<String>) {}
 
     fn unsub_charts(&self, _symbols: Vec<String>) {}
@@ -119,11 +255,79 @@ impl DataProvider for ReplayProvider {
 
     fn unsub_tick(&self, _symbols: Vec<String>) {}
 
+    fn sub_options(&self, _symbols: Vec<String>) {}
+
+    fn unsub_options(&self, _symbols: Vec<String>) {}
+
     async fn replay_info(&self, update: Option<ReplayInfo>) -> Option<ReplayInfo> {
-        let mut replay_info = self.replay_info.lock().await;
-        if let Some(update) = update {
-            *replay_info = update;
+        Some(self.replayer.replay_info(update).await)
+    }
+
+    async fn replay_cmd(&self, cmd: ReplayCmd) -> anyhow::Result<()> {
+        self.replayer.control(cmd).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReplayClock;
+    use chrono::{Local, TimeZone, Timelike};
+    use schwab_client::Candle;
+
+    fn candle(minute: u32) -> Candle {
+        Candle {
+            open: 100.0,
+            low: 100.0,
+            high: 100.0,
+            close: 100.0,
+            volume: 100,
+            time: Local.with_ymd_and_hms(2025, 7, 1, 9, minute, 0).unwrap(),
+            duration: 60,
         }
-        Some(replay_info.clone())
+    }
+
+    fn minutes(candles: &[Candle]) -> Vec<u32> {
+        candles.iter().map(|c| c.time.minute()).collect()
+    }
+
+    fn step(clock: &mut ReplayClock) -> Vec<(String, u32)> {
+        clock
+            .step()
+            .into_iter()
+            .map(|(symbol, c)| (symbol, c.time.minute()))
+            .collect()
+    }
+
+    #[test]
+    fn test_replay_clock() {
+        let mut clock = ReplayClock::default();
+        let first = clock.load("SPY", vec![candle(32), candle(30), candle(31)]);
+        assert_eq!(minutes(&first), [30]);
+        let first = clock.load("QQQ", vec![candle(31), candle(33)]);
+        assert_eq!(minutes(&first), [31]);
+
+        let spy = |minute| ("SPY".to_owned(), minute);
+        let qqq = |minute| ("QQQ".to_owned(), minute);
+        assert_eq!(step(&mut clock), [spy(31)]);
+        assert_eq!(clock.time, Some(candle(31).time));
+
+        // A symbol loaded mid replay catches up with the clock
+        let passed = clock.load("IWM", vec![candle(30), candle(31), candle(32)]);
+        assert_eq!(minutes(&passed), [30, 31]);
+
+        assert_eq!(step(&mut clock), [("IWM".to_owned(), 32), spy(32)]);
+        assert_eq!(step(&mut clock), [qqq(33)]);
+        assert!(clock.step().is_empty());
+        assert_eq!(clock.time, Some(candle(33).time));
+
+        clock.reset(Some(candle(31).time));
+        let passed = clock.load("SPY", vec![candle(30), candle(31), candle(32)]);
+        assert_eq!(minutes(&passed), [30, 31]);
+        // Seeking before the replay still hands out the first candle
+        clock.reset(Some(candle(10).time));
+        let passed = clock.load("SPY", vec![candle(30), candle(31)]);
+        assert_eq!(minutes(&passed), [30]);
+        assert_eq!(step(&mut clock), [spy(31)]);
     }
 }
@@ -3,12 +3,27 @@ use crate::time_helper::split_by_last_work_day;
 
 use app_config::APP_CONFIG;
 use async_trait::async_trait;
-use chrono::{DateTime, Duration, Local};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use schwab_client::SchwabError;
 use schwab_client::schwab_client::{Frequency, SchwabClient, SearchProjection};
 use schwab_client::streaming_client::{StreamResponse, StreamingClient, Subscription};
-use schwab_client::{Candle, Instrument};
-use tokio::sync::mpsc;
-use tracing::{debug, info};
+use schwab_client::{
+    ACCOUNT_ACTIVITY_KEY, Account, Candle, FUTURES_EXCHANGE, Instrument, OptionChain,
+    OptionChainParams, is_futures, is_option,
+};
+use std::sync::Mutex;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+use util::calendar::MarketDay;
+
+/// The first login the provider is waiting on, the code comes in through [first_login].
+static FIRST_LOGIN: Mutex<Option<mpsc::UnboundedSender<FirstLogin>>> = Mutex::new(None);
+
+struct FirstLogin {
+    code: String,
+    redirect_uri: String,
+    done: oneshot::Sender<anyhow::Result<()>>,
+}
 
 pub struct SchwabProvider {
     client: SchwabClient,
@@ -17,19 +32,95 @@ pub struct SchwabProvider {
 
 impl SchwabProvider {
     pub async fn init() -> anyhow::Result<Self> {
-        let client = SchwabClient::init().await?;
+        let client = match SchwabClient::init().await {
+            Err(SchwabError::LoginRequired) => wait_for_login().await,
+            client => client?,
+        };
         let streaming_client = client.create_streaming_client().await?;
         Ok(Self {
             client,
             streaming_client,
         })
     }
+
+    fn subscribe(&self, subscription: Subscription, symbols: Vec<String>) {
+        if !symbols.is_empty() {
+            self.streaming_client.subscribe(subscription, symbols);
+        }
+    }
+
+    fn unsubscribe(&self, subscription: Subscription, symbols: Vec<String>) {
+        if !symbols.is_empty() {
+            self.streaming_client.unsubscribe(subscription, symbols);
+        }
+    }
+}
+
+/// Waits for the first login, a failed one can be retried.
+async fn wait_for_login() -> SchwabClient {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<FirstLogin>();
+    FIRST_LOGIN.lock().unwrap().replace(sender);
+    warn!("No valid refresh token, waiting for the login at /api/auth/login");
+    loop {
+        let Some(login) = receiver.recv().await else {
+            unreachable!("The sender is kept in FIRST_LOGIN");
+        };
+        match SchwabClient::init_with_code(&login.code, &login.redirect_uri).await {
+            Ok(client) => {
+                FIRST_LOGIN.lock().unwrap().take();
+                login.done.send(Ok(())).ok();
+                return client;
+            }
+            Err(e) => {
+                warn!("First login failed: {e}");
+                login.done.send(Err(e.into())).ok();
+            }
+        }
+    }
+}
+
+/// Schwab's login page for the first login, before the provider is up.
+pub(crate) fn first_login_url(redirect_uri: &str, state: &str) -> anyhow::Result<String> {
+    if FIRST_LOGIN.lock().unwrap().is_none() {
+        anyhow::bail!("The data provider isn't waiting for a login");
+    }
+    Ok(SchwabClient::first_login_url(redirect_uri, state)?)
+}
+
+/// Hands the code of the first login to the waiting provider, returns once it's exchanged.
+pub(crate) async fn first_login(code: &str, redirect_uri: &str) -> anyhow::Result<()> {
+    let sender = FIRST_LOGIN
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("The data provider isn't waiting for a login"))?;
+    let (done, result) = oneshot::channel();
+    sender
+        .send(FirstLogin {
+            code: code.to_owned(),
+            redirect_uri: redirect_uri.to_owned(),
+            done,
+        })
+        .map_err(|_| anyhow::anyhow!("The data provider stopped waiting for a login"))?;
+    result.await?
 }
 
 #[async_trait]
 impl DataProvider for SchwabProvider {
     async fn search_symbol(&self, symbol: &str) -> anyhow::Result<Instrument> {
         let symbol = symbol.trim().to_uppercase();
+        if is_futures(&symbol) {
+            // The instruments endpoint doesn't know futures, a quote at least proves it trades
+            self.client.get_quote(&symbol).await?;
+            return Ok(Instrument {
+                description: format!("{symbol} futures"),
+                symbol,
+                exchange: FUTURES_EXCHANGE.to_owned(),
+                asset_type: "FUTURE".to_owned(),
+                cusip: None,
+                fundamental: None,
+            });
+        }
         Ok(self
             .client
             .search(symbol, SearchProjection::SymbolSearch)
@@ -64,7 +155,8 @@ impl DataProvider for SchwabProvider {
                 Frequency::Minute(if use_5min { 5 } else { 1 }),
                 Some((fetch_from, util::time::now())),
                 None,
-                APP_CONFIG.trade_config.use_extended_hour,
+                // Futures trade nearly around the clock, leaving most of it to the extended hours
+                APP_CONFIG.trade_config.use_extended_hour || is_futures(symbol),
             )
             .await?;
         log_candles("Fetched", &candles);
@@ -76,7 +168,35 @@ impl DataProvider for SchwabProvider {
             return Err(anyhow::anyhow!("No candles loaded for {symbol}"));
         }
 
-        Ok(split_by_last_work_day(candles))
+        Ok(split_by_last_work_day(symbol, candles))
+    }
+
+    async fn fetch_option_chain(
+        &self,
+        symbol: &str,
+        params: &OptionChainParams,
+    ) -> anyhow::Result<OptionChain> {
+        Ok(self.client.get_option_chain(symbol, params).await?)
+    }
+
+    async fn fetch_market_day(&self, date: NaiveDate) -> anyhow::Result<Option<MarketDay>> {
+        Ok(Some(self.client.get_market_hours(date).await?))
+    }
+
+    async fn fetch_accounts(&self) -> anyhow::Result<Vec<Account>> {
+        Ok(self.client.get_accounts().await?)
+    }
+
+    fn login_expires_at(&self) -> Option<DateTime<Local>> {
+        Some(self.client.refresh_token_expires_at())
+    }
+
+    fn login_url(&self, redirect_uri: &str, state: &str) -> anyhow::Result<String> {
+        Ok(self.client.authorize_url(redirect_uri, state)?)
+    }
+
+    async fn login(&self, code: &str, redirect_uri: &str) -> anyhow::Result<()> {
+        Ok(self.client.login(code, redirect_uri).await?)
     }
 
     fn listener(&self) -> mpsc::UnboundedReceiver<StreamResponse> {
@@ -84,32 +204,81 @@ impl DataProvider for SchwabProvider {
     }
 
     fn sub_charts(&self, symbols: Vec<String>) {
-        if !symbols.is_empty() {
-            self.streaming_client
-                .subscribe(Subscription::EquityChart, symbols);
-        }
+        let (futures, equities) = symbols.into_iter().partition(|s| is_futures(s));
+        self.subscribe(Subscription::EquityChart, equities);
+        self.subscribe(Subscription::FuturesChart, futures);
     }
 
     fn unsub_charts(&self, symbols: Vec<String>) {
-        if !symbols.is_empty() {
-            self.streaming_client
-                .unsubscribe(Subscription::EquityChart, symbols);
-        }
+        let (futures, equities) = symbols.into_iter().partition(|s| is_futures(s));
+        self.unsubscribe(Subscription::EquityChart, equities);
+        self.unsubscribe(Subscription::FuturesChart, futures);
     }
 
     fn sub_tick(&self, symbols: Vec<String>) {
-        if !symbols.is_empty() {
-            self.streaming_client
-                .subscribe(Subscription::EquityLevelOne, symbols);
-        }
+        let (futures, equities) = symbols.into_iter().partition(|s| is_futures(s));
+        self.subscribe(Subscription::EquityLevelOne, equities);
+        self.subscribe(Subscription::FuturesLevelOne, futures);
     }
 
     fn unsub_tick(&self, symbols: Vec<String>) {
-        if !symbols.is_empty() {
-            self.streaming_client
-                .unsubscribe(Subscription::EquityLevelOne, symbols);
-        }
+        let (futures, equities) = symbols.into_iter().partition(|s| is_futures(s));
+        self.unsubscribe(Subscription::EquityLevelOne, equities);
+        self.unsubscribe(Subscription::FuturesLevelOne, futures);
+    }
+
+    fn sub_options(&self, symbols: Vec<String>) {
+        self.subscribe(Subscription::OptionsLevelOne, symbols);
     }
+
+    fn unsub_options(&self, symbols: Vec<String>) {
+        self.unsubscribe(Subscription::OptionsLevelOne, symbols);
+    }
+
+    fn sub_account_activity(&self) {
+        self.subscribe(
+            Subscription::AccountActivity,
+            vec![ACCOUNT_ACTIVITY_KEY.to_owned()],
+        );
+    }
+
+    fn sub_trades(&self, symbols: Vec<String>) {
+        let equities = symbols.into_iter().filter(|s| !is_futures(s)).collect();
+        self.subscribe(Subscription::EquityTimeSale, equities);
+    }
+
+    fn unsub_trades(&self, symbols: Vec<String>) {
+        let equities = symbols.into_iter().filter(|s| !is_futures(s)).collect();
+        self.unsubscribe(Subscription::EquityTimeSale, equities);
+    }
+
+    fn streams_trades(&self, symbol: &str) -> bool {
+        // Schwab has no time & sales of the futures
+        !is_futures(symbol)
+    }
+
+    fn sub_book(&self, symbols: Vec<String>) {
+        // Only the listing exchange's book has data, so equities go to both of them
+        let (options, equities) = book_symbols(symbols);
+        self.subscribe(Subscription::OptionsBook, options);
+        self.subscribe(Subscription::NasdaqBook, equities.clone());
+        self.subscribe(Subscription::NyseBook, equities);
+    }
+
+    fn unsub_book(&self, symbols: Vec<String>) {
+        let (options, equities) = book_symbols(symbols);
+        self.unsubscribe(Subscription::OptionsBook, options);
+        self.unsubscribe(Subscription::NasdaqBook, equities.clone());
+        self.unsubscribe(Subscription::NyseBook, equities);
+    }
+}
+
+/// Splits the symbols into option contracts and equities, futures have no book.
+fn book_symbols(symbols: Vec<String>) -> (Vec<String>, Vec<String>) {
+    symbols
+        .into_iter()
+        .filter(|s| !is_futures(s))
+        .partition(|s| is_option(s))
 }
 
 pub fn log_candles(msg: impl AsRef<str>, candles: &[Candle]) {
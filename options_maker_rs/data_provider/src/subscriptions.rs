@@ -0,0 +1,78 @@
+use crate::provider;
+use rustc_hash::FxHashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Level one quotes are needed by the analyzer, the paper account and the brokerage positions
+/// alike, a symbol stays subscribed until none of them is interested in it anymore.
+static TICKS: LazyLock<Mutex<Interests>> = LazyLock::new(|| Mutex::new(Interests::default()));
+
+/// Adds an interest in the level one quotes of each of `symbols`.
+pub fn sub_tick(symbols: Vec<String>) {
+    let mut ticks = TICKS.lock().unwrap();
+    provider().sub_tick(ticks.add(symbols));
+}
+
+/// Drops an interest added by [sub_tick], the quotes stop once nobody else is interested.
+pub fn unsub_tick(symbols: Vec<String>) {
+    let mut ticks = TICKS.lock().unwrap();
+    provider().unsub_tick(ticks.remove(symbols));
+}
+
+#[derive(Debug, Default)]
+struct Interests(FxHashMap<String, usize>);
+
+impl Interests {
+    /// Symbols nobody was interested in before.
+    fn add(&mut self, symbols: Vec<String>) -> Vec<String> {
+        symbols
+            .into_iter()
+            .filter(|symbol| {
+                let count = self.0.entry(symbol.clone()).or_default();
+                *count += 1;
+                *count == 1
+            })
+            .collect()
+    }
+
+    /// Symbols nobody is interested in anymore.
+    fn remove(&mut self, symbols: Vec<String>) -> Vec<String> {
+        symbols
+            .into_iter()
+            .filter(|symbol| match self.0.get_mut(symbol) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+                    false
+                }
+                Some(_) => {
+                    self.0.remove(symbol);
+                    true
+                }
+                None => false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Interests;
+
+    #[test]
+    fn test_interests() {
+        let symbols = |symbols: &[&str]| symbols.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut interests = Interests::default();
+        assert_eq!(
+            interests.add(symbols(&["SPY", "QQQ"])),
+            symbols(&["SPY", "QQQ"])
+        );
+        // The paper account holds SPY too, the watchlist removing it keeps it streaming
+        assert!(interests.add(symbols(&["SPY"])).is_empty());
+        assert!(interests.remove(symbols(&["SPY"])).is_empty());
+        assert_eq!(
+            interests.remove(symbols(&["SPY", "QQQ"])),
+            symbols(&["SPY", "QQQ"])
+        );
+        assert!(interests.remove(symbols(&["SPY"])).is_empty());
+        assert_eq!(interests.add(symbols(&["SPY"])), symbols(&["SPY"]));
+    }
+}
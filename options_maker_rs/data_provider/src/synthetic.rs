@@ -0,0 +1,456 @@
+use crate::replay::Replayer;
+use crate::schwab::log_candles;
+use crate::{DataProvider, ReplayCmd, ReplayInfo};
+use anyhow::anyhow;
+use app_config::{APP_CONFIG, SyntheticProviderConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use chrono_tz::Tz;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use schwab_client::streaming_client::StreamResponse;
+use schwab_client::{Account, Candle, Instrument, OptionChain, OptionChainParams, is_futures};
+use std::f64::consts::PI;
+use tokio::sync::mpsc;
+use tracing::info;
+use util::calendar::MarketCalendar;
+use util::time::MarketTime;
+
+/// The volatility is annualized over this many minutes of regular sessions.
+const MINUTES_PER_YEAR: f64 = 252.0 * 390.0;
+/// Minutes it takes a scripted dip to reach the previous day's low.
+const DIP_MINUTES: u32 = 45;
+/// Minutes the price keeps drifting up after touching the low.
+const BOUNCE_MINUTES: u32 = 30;
+
+/// Replays generated candles, any symbol can be added.
+pub struct SyntheticProvider {
+    params: SyntheticParams,
+    replayer: Replayer,
+}
+
+impl SyntheticProvider {
+    pub async fn init(config: &SyntheticProviderConfig) -> anyhow::Result<Self> {
+        info!("Generating synthetic candles with seed {}", config.seed);
+        Ok(Self {
+            params: SyntheticParams {
+                seed: config.seed,
+                volatility: config.volatility,
+                base_volume: config.base_volume,
+                gap_volatility: config.gap_volatility,
+                bounce_chance: config.bounce_chance,
+                tz: util::time::market_tz(),
+                extended_hours: APP_CONFIG.trade_config.use_extended_hour,
+            },
+            replayer: Replayer::start(),
+        })
+    }
+}
+
+#[async_trait]
+impl DataProvider for SyntheticProvider {
+    async fn search_symbol(&self, symbol: &str) -> anyhow::Result<Instrument> {
+        let symbol = symbol.trim().to_uppercase();
+        if symbol.is_empty() {
+            return Err(anyhow!("Symbol is empty"));
+        }
+        let asset_type = if is_futures(&symbol) {
+            "FUTURE"
+        } else {
+            "EQUITY"
+        };
+        Ok(Instrument {
+            description: format!("Synthetic {symbol}"),
+            symbol,
+            exchange: "SYNTHETIC".to_owned(),
+            asset_type: asset_type.to_owned(),
+            cusip: None,
+            fundamental: None,
+        })
+    }
+
+    /// Generates the days through yesterday, the last of them gets replayed.
+    async fn fetch_price_history(
+        &self,
+        symbol: &str,
+        start: DateTime<Local>,
+    ) -> anyhow::Result<(Vec<Candle>, Vec<Candle>)> {
+        let end = util::time::now().market_date().pred_opt().unwrap();
+        let mut market = SyntheticMarket::new(symbol, self.params.clone());
+        let mut candles = market.candles(start.market_date(), end);
+        candles.retain(|c| c.time >= start);
+        log_candles(format!("Generated for {symbol}"), &candles);
+        self.replayer.load(symbol, candles).await
+    }
+
+    async fn fetch_option_chain(
+        &self,
+        _symbol: &str,
+        _params: &OptionChainParams,
+    ) -> anyhow::Result<OptionChain> {
+        Err(anyhow!("Option chains are not generated"))
+    }
+
+    async fn fetch_accounts(&self) -> anyhow::Result<Vec<Account>> {
+        Err(anyhow!("Accounts are not generated"))
+    }
+
+    fn listener(&self) -> mpsc::UnboundedReceiver<StreamResponse> {
+        self.replayer.listener()
+    }
+
+    fn sub_charts(&self, _symbols: Vec<String>) {}
+
+    fn unsub_charts(&self, _symbols: Vec<String>) {}
+
+    fn sub_tick(&self, _symbols: Vec<String>) {}
+
+    fn unsub_tick(&self, _symbols: Vec<String>) {}
+
+    fn sub_options(&self, _symbols: Vec<String>) {}
+
+    fn unsub_options(&self, _symbols: Vec<String>) {}
+
+    async fn replay_info(&self, update: Option<ReplayInfo>) -> Option<ReplayInfo> {
+        Some(self.replayer.replay_info(update).await)
+    }
+
+    async fn replay_cmd(&self, cmd: ReplayCmd) -> anyhow::Result<()> {
+        self.replayer.control(cmd).await;
+        Ok(())
+    }
+}
+
+/// Shape of a generated market, see [SyntheticProviderConfig].
+#[derive(Clone, Debug)]
+pub struct SyntheticParams {
+    pub seed: u64,
+    pub volatility: f64,
+    pub base_volume: f64,
+    pub gap_volatility: f64,
+    pub bounce_chance: f64,
+    /// Timezone of the sessions of the market calendar.
+    pub tz: Tz,
+    pub extended_hours: bool,
+}
+
+/// A scripted dip which touched the previous day's low and bounced off it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScriptedBounce {
+    pub level: f64,
+    pub touched_at: DateTime<Local>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Script {
+    None,
+    /// Starts dipping into `level` at minute `start` of the session.
+    Scheduled {
+        start: u32,
+        level: f64,
+    },
+    /// Touches `level` in `left` minutes.
+    Dip {
+        level: f64,
+        left: u32,
+    },
+    /// Drifts up for `left` more minutes.
+    Bounce {
+        left: u32,
+    },
+}
+
+/// Minute candles of a geometric Brownian motion drifting in trend regimes, with overnight gaps,
+/// a U-shaped intraday volume and scripted bounces off the previous day's low. The walk of a
+/// symbol only depends on the seed, the symbol and the days generated.
+pub struct SyntheticMarket {
+    params: SyntheticParams,
+    rng: StdRng,
+    price: f64,
+    /// Drift per minute in units of the minute volatility, and for how many more minutes.
+    regime: (f64, u32),
+    bounces: Vec<ScriptedBounce>,
+}
+
+impl SyntheticMarket {
+    pub fn new(symbol: &str, params: SyntheticParams) -> Self {
+        let mut rng = StdRng::seed_from_u64(params.seed ^ fnv1a(symbol));
+        let price = round(rng.random_range(20.0..500.0));
+        Self {
+            params,
+            rng,
+            price,
+            regime: (0.0, 0),
+            bounces: Vec::new(),
+        }
+    }
+
+    pub fn bounces(&self) -> &[ScriptedBounce] {
+        &self.bounces
+    }
+
+    /// Candles of the trading days from `start` through `end`.
+    pub fn candles(&mut self, start: NaiveDate, end: NaiveDate) -> Vec<Candle> {
+        let mut candles = Vec::new();
+        let mut prev_low = None;
+        for date in start.iter_days().take_while(|date| *date <= end) {
+            let day = MarketCalendar::day(date);
+            let Some((open, close)) = day.regular else {
+                continue;
+            };
+            let (first, last) = match day.extended {
+                Some(extended) if self.params.extended_hours => extended,
+                _ => (open, close),
+            };
+            if prev_low.is_some() {
+                self.gap();
+            }
+
+            let minutes = (last - first).num_minutes() as u32;
+            let regular = (
+                (open - first).num_minutes() as u32,
+                (close - first).num_minutes() as u32,
+            );
+            let mut script = self.script(prev_low, regular);
+            let mut day_low = f64::MAX;
+            for minute in 0..minutes {
+                if let Script::Scheduled { start, level } = script
+                    && start == minute
+                {
+                    // Already through the level, there's nothing left to dip into
+                    script = if self.price > level {
+                        Script::Dip {
+                            level,
+                            left: DIP_MINUTES,
+                        }
+                    } else {
+                        Script::None
+                    };
+                }
+                let session_start = date.and_time(first) + Duration::minutes(minute as i64);
+                let time = util::time::from_wall_clock(self.params.tz, session_start);
+                let volume_factor = if regular.0 <= minute && minute < regular.1 {
+                    let progress = (minute - regular.0) as f64 / (regular.1 - regular.0) as f64;
+                    // U-shape, busy at the open and the close, averaging 1 over the session
+                    0.6 * (1.0 + 2.0 * (2.0 * progress - 1.0).powi(2))
+                } else {
+                    0.05
+                };
+                let candle = self.next_candle(time, volume_factor, &mut script);
+                if regular.0 <= minute && minute < regular.1 {
+                    day_low = day_low.min(candle.low);
+                }
+                candles.push(candle);
+            }
+            prev_low = Some(day_low);
+        }
+        candles
+    }
+
+    /// Schedules a dip into the previous day's low for some days, when the price is above it.
+    fn script(&mut self, prev_low: Option<f64>, regular: (u32, u32)) -> Script {
+        let Some(level) = prev_low else {
+            return Script::None;
+        };
+        let earliest = regular.0 + 30;
+        let latest = regular.1.saturating_sub(DIP_MINUTES + BOUNCE_MINUTES);
+        if self.price <= level
+            || earliest >= latest
+            || !self.rng.random_bool(self.params.bounce_chance)
+        {
+            return Script::None;
+        }
+        Script::Scheduled {
+            start: self.rng.random_range(earliest..latest),
+            level,
+        }
+    }
+
+    fn gap(&mut self) {
+        let mut gap = self.params.gap_volatility * self.normal();
+        if self.rng.random_bool(0.1) {
+            gap *= 3.0;
+        }
+        self.price = round(self.price * gap.exp());
+    }
+
+    fn next_candle(
+        &mut self,
+        time: DateTime<Local>,
+        volume_factor: f64,
+        script: &mut Script,
+    ) -> Candle {
+        let sigma = self.params.volatility / MINUTES_PER_YEAR.sqrt();
+        let (drift, noise) = match *script {
+            Script::Dip { level, left } => ((level / self.price).ln() / left as f64, 0.3),
+            Script::Bounce { .. } => (0.5 * sigma, 1.0),
+            Script::None | Script::Scheduled { .. } => (self.regime_drift() * sigma, 1.0),
+        };
+        let change = drift - sigma * sigma / 2.0 + sigma * noise * self.normal();
+        let open = self.price;
+        let mut close = round(open * change.exp());
+        let mut high = round(open.max(close) * (0.5 * sigma * self.normal().abs()).exp());
+        let mut low = round(open.min(close) * (-0.5 * sigma * self.normal().abs()).exp());
+
+        match script {
+            Script::Dip { level, left: 1 } => {
+                // A hammer off the level, the low is the level itself
+                let level = *level;
+                low = level;
+                close = round(open.max(close).max(level) * (1.0 + sigma * self.normal().abs()));
+                high = high.max(close);
+                self.bounces.push(ScriptedBounce {
+                    level,
+                    touched_at: time,
+                });
+                *script = Script::Bounce {
+                    left: BOUNCE_MINUTES,
+                };
+            }
+            Script::Dip { level, left } => {
+                // Nothing trades through the level before the scripted touch
+                let floor = round(*level * (1.0 + sigma * 0.1)).max(*level + 0.01);
+                low = low.max(floor);
+                close = close.max(floor);
+                high = high.max(close);
+                *left -= 1;
+            }
+            Script::Bounce { left } => {
+                *left -= 1;
+                if *left == 0 {
+                    *script = Script::None;
+                }
+            }
+            Script::None | Script::Scheduled { .. } => {}
+        }
+
+        let moved = (change / sigma).abs();
+        let volume = self.params.base_volume
+            * volume_factor
+            * (1.0 + 0.2 * moved)
+            * (0.3 * self.normal() - 0.045).exp();
+        self.price = close;
+        Candle {
+            open,
+            low: low.min(open).min(close),
+            high: high.max(open).max(close),
+            close,
+            volume: volume.max(1.0) as u64,
+            time,
+            duration: 60,
+        }
+    }
+
+    /// Trends up, down or sideways for one to ten hours before switching.
+    fn regime_drift(&mut self) -> f64 {
+        if self.regime.1 == 0 {
+            let direction = [-1.0, 0.0, 1.0][self.rng.random_range(0..3)];
+            let strength = self.rng.random_range(0.05..0.15);
+            self.regime = (direction * strength, self.rng.random_range(60..600));
+        }
+        self.regime.1 -= 1;
+        self.regime.0
+    }
+
+    /// Standard normal sample, Box-Muller.
+    fn normal(&mut self) -> f64 {
+        let u1 = self.rng.random_range(f64::EPSILON..1.0);
+        let u2 = self.rng.random::<f64>();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+}
+
+fn round(price: f64) -> f64 {
+    (price * 100.0).round() / 100.0
+}
+
+/// FNV-1a of the symbol, unlike the std and Fx hashers it's the same across releases and builds
+/// so a seed keeps generating the same candles.
+fn fnv1a(symbol: &str) -> u64 {
+    symbol.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SyntheticMarket, SyntheticParams, fnv1a};
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::America::New_York;
+    use util::calendar::MarketCalendar;
+
+    fn params() -> SyntheticParams {
+        SyntheticParams {
+            seed: 7,
+            volatility: 0.3,
+            base_volume: 10_000.0,
+            gap_volatility: 0.01,
+            bounce_chance: 1.0,
+            tz: New_York,
+            extended_hours: false,
+        }
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a("foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_synthetic_market() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        let mut market = SyntheticMarket::new("SPY", params());
+        let candles = market.candles(date(1), date(8));
+
+        // Same seed and symbol, same candles
+        let mut again = SyntheticMarket::new("SPY", params());
+        let same = again.candles(date(1), date(8));
+        let ohlcv = |c: &schwab_client::Candle| (c.open, c.high, c.low, c.close, c.volume, c.time);
+        assert!(candles.iter().map(ohlcv).eq(same.iter().map(ohlcv)));
+        let other = SyntheticMarket::new("QQQ", params()).candles(date(1), date(8));
+        assert!(!candles.iter().map(ohlcv).eq(other.iter().map(ohlcv)));
+
+        // Regular sessions of the trading days, July 3rd closes early and the 4th is a holiday
+        let first = New_York.with_ymd_and_hms(2025, 7, 1, 9, 30, 0).unwrap();
+        assert_eq!(candles[0].time, first);
+        let days = candles
+            .iter()
+            .map(|c| c.time.with_timezone(&New_York).date_naive())
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(
+            days.into_iter().collect::<Vec<_>>(),
+            [1, 2, 3, 7, 8].map(date)
+        );
+        let july_3 = candles
+            .iter()
+            .filter(|c| c.time.with_timezone(&New_York).date_naive() == date(3));
+        assert_eq!(july_3.count(), 210);
+        assert_eq!(candles.len(), 4 * 390 + 210);
+        assert!(candles.iter().all(|c| c.low <= c.open.min(c.close)
+            && c.high >= c.open.max(c.close)
+            && c.volume > 0));
+
+        // Every scripted dip touches the previous day's low with a green candle
+        assert!(!market.bounces().is_empty());
+        for bounce in market.bounces() {
+            assert!(
+                MarketCalendar::day(bounce.touched_at.with_timezone(&New_York).date_naive())
+                    .is_open()
+            );
+            let idx = candles
+                .iter()
+                .position(|c| c.time == bounce.touched_at)
+                .unwrap();
+            let touch = &candles[idx];
+            assert_eq!(touch.low, bounce.level);
+            assert!(touch.close > bounce.level && touch.close >= touch.open);
+            assert!(
+                candles[idx - super::DIP_MINUTES as usize + 1..idx]
+                    .iter()
+                    .all(|c| c.low > bounce.level)
+            );
+        }
+    }
+}
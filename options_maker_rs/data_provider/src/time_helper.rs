@@ -1,46 +1,44 @@
 use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
-use schwab_client::Candle;
+use schwab_client::{Candle, trading_date};
 use util::time::TradingDay;
 
-pub fn split_by_last_work_day(candles: Vec<Candle>) -> (Vec<Candle>, Vec<Candle>) {
+/// Splits `candles` of `symbol` ahead of the last full trading day before today, at the opening
+/// of its session the evening before for the futures.
+pub fn split_by_last_work_day(symbol: &str, candles: Vec<Candle>) -> (Vec<Candle>, Vec<Candle>) {
     if candles.is_empty() {
         return (Vec::new(), Vec::new());
     }
 
-    let last_working_day = get_last_working_day(&candles);
+    let last_working_day = get_last_working_day(symbol, &candles);
     candles
         .into_iter()
-        .partition(|candle| candle.time.date_naive() < last_working_day)
+        .partition(|candle| trading_date(symbol, candle.time) < last_working_day)
 }
 
-fn get_last_working_day(candles: &[Candle]) -> NaiveDate {
-    let first_day = candles
-        .first()
-        .expect("Candle list is empty")
-        .time
-        .date_naive();
-    let mut candidate = util::time::now().date_naive();
+fn get_last_working_day(symbol: &str, candles: &[Candle]) -> NaiveDate {
+    let first_day = trading_date(symbol, candles.first().expect("Candle list is empty").time);
+    let mut candidate = trading_date(symbol, util::time::now());
     while candidate > first_day {
         candidate = candidate.pred_opt().unwrap();
-        if is_working_day(candidate, candles) {
+        if is_working_day(symbol, candidate, candles) {
             return candidate;
         }
     }
     first_day
 }
 
-fn is_working_day(date: NaiveDate, candles: &[Candle]) -> bool {
-    if date.is_weekend() {
-        return false; // Skip weekends
+fn is_working_day(symbol: &str, date: NaiveDate, candles: &[Candle]) -> bool {
+    if !date.is_trading_day() {
+        return false; // Skip weekends and holidays
     }
 
-    let min_working_hours = util::time::regular_trading_hours();
+    let min_working_hours = util::time::regular_trading_hours(date);
     let first = candles
         .iter()
-        .find(|candle| candle.time.date_naive() == date);
+        .find(|candle| trading_date(symbol, candle.time) == date);
     let last = candles
         .iter()
-        .rfind(|candle| candle.time.date_naive() == date);
+        .rfind(|candle| trading_date(symbol, candle.time) == date);
     match (first, last) {
         (Some(first), Some(last)) => (last.time - first.time) >= min_working_hours,
         _ => false,
@@ -57,12 +55,18 @@ pub fn parse_datetime(input: &str) -> anyhow::Result<DateTime<Local>> {
 
     for format in &formats {
         if let Ok(datetime) = NaiveDateTime::parse_from_str(input, format) {
-            return Ok(datetime.and_local_timezone(Local).unwrap());
+            return Ok(util::time::from_wall_clock(
+                util::time::market_tz(),
+                datetime,
+            ));
         }
         // Try parsing as date and convert to datetime
         if let Ok(date) = NaiveDate::parse_from_str(input, format) {
             let datetime = date.and_hms_opt(0, 0, 0).unwrap();
-            return Ok(datetime.and_local_timezone(Local).unwrap());
+            return Ok(util::time::from_wall_clock(
+                util::time::market_tz(),
+                datetime,
+            ));
         }
     }
 
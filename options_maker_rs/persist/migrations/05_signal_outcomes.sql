@@ -0,0 +1,20 @@
+CREATE TABLE signal_outcomes
+(
+    outcome_id  INTEGER     NOT NULL PRIMARY KEY AUTOINCREMENT,
+    symbol      VARCHAR(16) NOT NULL,
+    trend       VARCHAR(16) NOT NULL,
+    is_gap_fill BOOLEAN     NOT NULL,
+    price_level REAL        NOT NULL,
+    found_at    DATETIME    NOT NULL,
+    entry       REAL        NOT NULL,
+    stop        REAL        NOT NULL,
+    atr         REAL        NOT NULL,
+    mfe_atr     REAL        NOT NULL,
+    mae_atr     REAL        NOT NULL,
+    hit_1r      BOOLEAN     NOT NULL,
+    hit_2r      BOOLEAN     NOT NULL,
+    stopped     BOOLEAN     NOT NULL,
+    r_multiple  REAL        NOT NULL,
+    resolved_at DATETIME    NOT NULL,
+    UNIQUE (symbol, found_at, trend)
+);
@@ -0,0 +1,15 @@
+CREATE TABLE signals
+(
+    signal_id   INTEGER     NOT NULL PRIMARY KEY AUTOINCREMENT,
+    symbol      VARCHAR(16) NOT NULL,
+    kind        VARCHAR(16) NOT NULL,
+    trend       VARCHAR(16) NOT NULL,
+    price_level REAL        NOT NULL,
+    found_at    DATETIME    NOT NULL,
+    points      JSONB       NOT NULL,
+    is_gap_fill BOOLEAN     NOT NULL,
+    is_imminent BOOLEAN     NOT NULL,
+    UNIQUE (symbol, kind, found_at, price_level)
+);
+
+CREATE INDEX signals_found_at ON signals (found_at);
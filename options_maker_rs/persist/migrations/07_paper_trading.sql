@@ -0,0 +1,39 @@
+CREATE TABLE paper_account
+(
+    account_id       INTEGER  NOT NULL PRIMARY KEY,
+    starting_balance REAL     NOT NULL,
+    cash             REAL     NOT NULL,
+    realized_pnl     REAL     NOT NULL,
+    commissions      REAL     NOT NULL,
+    created_at       DATETIME NOT NULL
+);
+
+CREATE TABLE paper_orders
+(
+    order_id    INTEGER     NOT NULL PRIMARY KEY AUTOINCREMENT,
+    symbol      VARCHAR(32) NOT NULL,
+    asset_type  VARCHAR(16) NOT NULL,
+    side        VARCHAR(8)  NOT NULL,
+    quantity    INTEGER     NOT NULL,
+    order_type  VARCHAR(8)  NOT NULL,
+    limit_price REAL,
+    status      VARCHAR(16) NOT NULL,
+    created_at  DATETIME    NOT NULL,
+    filled_at   DATETIME,
+    fill_price  REAL,
+    commission  REAL        NOT NULL
+);
+
+CREATE INDEX paper_orders_created_at ON paper_orders (created_at);
+
+CREATE TABLE paper_positions
+(
+    symbol       VARCHAR(32) NOT NULL PRIMARY KEY,
+    asset_type   VARCHAR(16) NOT NULL,
+    quantity     INTEGER     NOT NULL,
+    avg_price    REAL        NOT NULL,
+    multiplier   REAL        NOT NULL,
+    realized_pnl REAL        NOT NULL,
+    opened_at    DATETIME    NOT NULL,
+    updated_at   DATETIME    NOT NULL
+);
@@ -0,0 +1,13 @@
+-- The sequence numbers restart every session, a print is only identified along with its time.
+CREATE TABLE trades
+(
+    trade_id INTEGER     NOT NULL PRIMARY KEY AUTOINCREMENT,
+    symbol   VARCHAR(16) NOT NULL,
+    ts       DATETIME    NOT NULL,
+    price    REAL        NOT NULL,
+    size     INTEGER     NOT NULL,
+    sequence INTEGER     NOT NULL,
+    UNIQUE (symbol, ts, sequence)
+);
+
+CREATE INDEX trades_symbol_ts ON trades (symbol, ts);
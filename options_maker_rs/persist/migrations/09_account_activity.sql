@@ -0,0 +1,14 @@
+CREATE TABLE account_activity
+(
+    activity_id  INTEGER     NOT NULL PRIMARY KEY AUTOINCREMENT,
+    account      VARCHAR(32) NOT NULL,
+    message_type VARCHAR(64) NOT NULL,
+    order_id     INTEGER,
+    symbol       VARCHAR(32),
+    quantity     REAL,
+    price        REAL,
+    created_at   DATETIME    NOT NULL,
+    data         JSONB       NOT NULL
+);
+
+CREATE INDEX account_activity_created_at ON account_activity (created_at);
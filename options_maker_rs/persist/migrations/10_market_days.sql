@@ -0,0 +1,9 @@
+CREATE TABLE market_days
+(
+    date           DATE     NOT NULL PRIMARY KEY,
+    regular_open   TIME,
+    regular_close  TIME,
+    extended_open  TIME,
+    extended_close TIME,
+    updated_at     DATETIME NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Candle times were stored in the wall clock of the machine running the server, SQLite's 'utc'
+-- modifier converts them from the local timezone of the machine running the migration.
+UPDATE prices
+SET ts = datetime(ts, 'utc');
@@ -0,0 +1,31 @@
+-- Like the candle times, the rest of the timestamps were stored in the wall clock of the machine
+-- running the server. Converted to UTC keeping their fractional seconds.
+UPDATE price_levels
+SET updated_at = strftime('%Y-%m-%d %H:%M:%f', updated_at, 'utc');
+
+UPDATE signal_outcomes
+SET found_at    = strftime('%Y-%m-%d %H:%M:%f', found_at, 'utc'),
+    resolved_at = strftime('%Y-%m-%d %H:%M:%f', resolved_at, 'utc');
+
+UPDATE signals
+SET found_at = strftime('%Y-%m-%d %H:%M:%f', found_at, 'utc');
+
+UPDATE paper_account
+SET created_at = strftime('%Y-%m-%d %H:%M:%f', created_at, 'utc');
+
+UPDATE paper_orders
+SET created_at = strftime('%Y-%m-%d %H:%M:%f', created_at, 'utc'),
+    filled_at  = strftime('%Y-%m-%d %H:%M:%f', filled_at, 'utc');
+
+UPDATE paper_positions
+SET opened_at  = strftime('%Y-%m-%d %H:%M:%f', opened_at, 'utc'),
+    updated_at = strftime('%Y-%m-%d %H:%M:%f', updated_at, 'utc');
+
+UPDATE trades
+SET ts = strftime('%Y-%m-%d %H:%M:%f', ts, 'utc');
+
+UPDATE account_activity
+SET created_at = strftime('%Y-%m-%d %H:%M:%f', created_at, 'utc');
+
+UPDATE market_days
+SET updated_at = strftime('%Y-%m-%d %H:%M:%f', updated_at, 'utc');
@@ -0,0 +1,57 @@
+use crate::db;
+use sqlx::types::chrono::{NaiveDate, NaiveTime};
+use util::calendar::MarketDay;
+
+pub async fn save_market_days(days: &[MarketDay]) -> sqlx::Result<()> {
+    let updated_at = util::time::now().naive_utc();
+    let mut trans = db().begin().await?;
+    for day in days {
+        let (regular_open, regular_close) = day.regular.unzip();
+        let (extended_open, extended_close) = day.extended.unzip();
+        sqlx::query!(
+            r"
+            INSERT INTO market_days
+                (date, regular_open, regular_close, extended_open, extended_close, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (date) DO UPDATE SET
+                regular_open = excluded.regular_open,
+                regular_close = excluded.regular_close,
+                extended_open = excluded.extended_open,
+                extended_close = excluded.extended_close,
+                updated_at = excluded.updated_at
+            ",
+            day.date,
+            regular_open,
+            regular_close,
+            extended_open,
+            extended_close,
+            updated_at,
+        )
+        .execute(&mut *trans)
+        .await?;
+    }
+    trans.commit().await
+}
+
+pub async fn load_market_days(start: NaiveDate) -> sqlx::Result<Vec<MarketDay>> {
+    sqlx::query!(
+        r#"
+            SELECT date,
+                   regular_open as "regular_open: NaiveTime",
+                   regular_close as "regular_close: NaiveTime",
+                   extended_open as "extended_open: NaiveTime",
+                   extended_close as "extended_close: NaiveTime"
+            FROM market_days
+            WHERE date >= $1
+            ORDER BY date
+        "#,
+        start,
+    )
+    .map(|rec| MarketDay {
+        date: rec.date,
+        regular: rec.regular_open.zip(rec.regular_close),
+        extended: rec.extended_open.zip(rec.extended_close),
+    })
+    .fetch_all(db())
+    .await
+}
@@ -0,0 +1,57 @@
+use crate::db;
+use schwab_client::AccountActivity;
+use sqlx::types::Json;
+use sqlx::types::chrono::{DateTime, Local};
+
+/// Records an event of the brokerage account activity stream in the trade journal.
+pub async fn save_activity(activity: &AccountActivity) -> sqlx::Result<()> {
+    let created_at = activity.time.naive_utc();
+    let data = Json(&activity.data);
+    sqlx::query!(
+        r"
+            INSERT INTO account_activity (account, message_type, order_id, symbol, quantity, price, created_at, data)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ",
+        activity.account,
+        activity.message_type,
+        activity.order_id,
+        activity.symbol,
+        activity.quantity,
+        activity.price,
+        created_at,
+        data,
+    )
+    .execute(db())
+    .await?;
+    Ok(())
+}
+
+pub async fn load_activity(
+    order_id: Option<i64>,
+    start: DateTime<Local>,
+) -> sqlx::Result<Vec<AccountActivity>> {
+    let start = start.naive_utc();
+    sqlx::query!(
+        r#"
+            SELECT account, message_type, order_id, symbol, quantity, price, created_at,
+                   data as "data: Json<serde_json::Value>"
+            FROM account_activity
+            WHERE ($1 IS NULL OR order_id = $1) AND created_at >= $2
+            ORDER BY created_at ASC
+        "#,
+        order_id,
+        start,
+    )
+    .map(|rec| AccountActivity {
+        account: rec.account,
+        message_type: rec.message_type,
+        order_id: rec.order_id,
+        symbol: rec.symbol,
+        quantity: rec.quantity,
+        price: rec.price,
+        time: rec.created_at.and_utc().with_timezone(&Local),
+        data: rec.data.0,
+    })
+    .fetch_all(db())
+    .await
+}
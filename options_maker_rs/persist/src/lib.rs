@@ -11,11 +11,17 @@ use std::time::Duration;
 pub use sqlx::Error;
 pub use sqlx::Result;
 
+pub mod calendar;
 pub mod crawler;
 pub mod groups;
+pub mod journal;
+pub mod outcome;
+pub mod paper;
 pub mod price_level;
 pub mod prices;
+pub mod signal;
 pub mod ticker;
+pub mod trades;
 
 static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
 
@@ -0,0 +1,93 @@
+use crate::db;
+use serde::Serialize;
+use sqlx::types::chrono::{DateTime, Local};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalOutcome {
+    pub symbol: String,
+    pub trend: String,
+    pub is_gap_fill: bool,
+    pub price_level: f64,
+    pub found_at: DateTime<Local>,
+    pub entry: f64,
+    pub stop: f64,
+    pub atr: f64,
+    pub mfe_atr: f64,
+    pub mae_atr: f64,
+    pub hit_1r: bool,
+    pub hit_2r: bool,
+    pub stopped: bool,
+    pub r_multiple: f64,
+    pub resolved_at: DateTime<Local>,
+}
+
+pub async fn save_outcomes(outcomes: &[SignalOutcome]) -> sqlx::Result<()> {
+    let mut trans = db().begin().await?;
+    for outcome in outcomes {
+        let found_at = outcome.found_at.naive_utc();
+        let resolved_at = outcome.resolved_at.naive_utc();
+        sqlx::query!(
+            r"
+            INSERT INTO signal_outcomes (symbol, trend, is_gap_fill, price_level, found_at, entry,
+                stop, atr, mfe_atr, mae_atr, hit_1r, hit_2r, stopped, r_multiple, resolved_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            ON CONFLICT (symbol, found_at, trend) DO NOTHING
+            ",
+            outcome.symbol,
+            outcome.trend,
+            outcome.is_gap_fill,
+            outcome.price_level,
+            found_at,
+            outcome.entry,
+            outcome.stop,
+            outcome.atr,
+            outcome.mfe_atr,
+            outcome.mae_atr,
+            outcome.hit_1r,
+            outcome.hit_2r,
+            outcome.stopped,
+            outcome.r_multiple,
+            resolved_at,
+        )
+        .execute(&mut *trans)
+        .await?;
+    }
+    trans.commit().await
+}
+
+pub async fn load_outcomes(
+    symbol: Option<&str>,
+    start: DateTime<Local>,
+) -> sqlx::Result<Vec<SignalOutcome>> {
+    let start = start.naive_utc();
+    sqlx::query!(
+        r"
+            SELECT symbol, trend, is_gap_fill, price_level, found_at, entry, stop, atr,
+                   mfe_atr, mae_atr, hit_1r, hit_2r, stopped, r_multiple, resolved_at
+            FROM signal_outcomes
+            WHERE ($1 IS NULL OR symbol = $1) AND found_at >= $2
+            ORDER BY found_at ASC
+        ",
+        symbol,
+        start,
+    )
+    .map(|rec| SignalOutcome {
+        symbol: rec.symbol,
+        trend: rec.trend,
+        is_gap_fill: rec.is_gap_fill,
+        price_level: rec.price_level,
+        found_at: rec.found_at.and_utc().with_timezone(&Local),
+        entry: rec.entry,
+        stop: rec.stop,
+        atr: rec.atr,
+        mfe_atr: rec.mfe_atr,
+        mae_atr: rec.mae_atr,
+        hit_1r: rec.hit_1r,
+        hit_2r: rec.hit_2r,
+        stopped: rec.stopped,
+        r_multiple: rec.r_multiple,
+        resolved_at: rec.resolved_at.and_utc().with_timezone(&Local),
+    })
+    .fetch_all(db())
+    .await
+}
@@ -0,0 +1,222 @@
+use crate::db;
+use serde::Serialize;
+use sqlx::types::chrono::{DateTime, Local};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperAccount {
+    pub starting_balance: f64,
+    pub cash: f64,
+    pub realized_pnl: f64,
+    pub commissions: f64,
+    pub created_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperOrder {
+    pub order_id: i64,
+    pub symbol: String,
+    pub asset_type: String,
+    pub side: String,
+    pub quantity: i64,
+    pub order_type: String,
+    pub limit_price: Option<f64>,
+    pub status: String,
+    pub created_at: DateTime<Local>,
+    pub filled_at: Option<DateTime<Local>>,
+    pub fill_price: Option<f64>,
+    pub commission: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaperPosition {
+    pub symbol: String,
+    pub asset_type: String,
+    pub quantity: i64,
+    pub avg_price: f64,
+    pub multiplier: f64,
+    pub realized_pnl: f64,
+    pub opened_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+pub async fn load_account() -> sqlx::Result<Option<PaperAccount>> {
+    sqlx::query!(
+        r"
+            SELECT starting_balance, cash, realized_pnl, commissions, created_at
+            FROM paper_account WHERE account_id = 1
+        "
+    )
+    .map(|rec| PaperAccount {
+        starting_balance: rec.starting_balance,
+        cash: rec.cash,
+        realized_pnl: rec.realized_pnl,
+        commissions: rec.commissions,
+        created_at: rec.created_at.and_utc().with_timezone(&Local),
+    })
+    .fetch_optional(db())
+    .await
+}
+
+pub async fn save_account(account: &PaperAccount) -> sqlx::Result<()> {
+    let created_at = account.created_at.naive_utc();
+    sqlx::query!(
+        r"
+            INSERT INTO paper_account (account_id, starting_balance, cash, realized_pnl, commissions, created_at)
+            VALUES (1, $1, $2, $3, $4, $5)
+            ON CONFLICT (account_id) DO UPDATE
+            SET cash = excluded.cash,
+                realized_pnl = excluded.realized_pnl,
+                commissions = excluded.commissions
+        ",
+        account.starting_balance,
+        account.cash,
+        account.realized_pnl,
+        account.commissions,
+        created_at,
+    )
+    .execute(db())
+    .await?;
+    Ok(())
+}
+
+/// Inserts a new order when its `order_id` is 0 and returns the assigned id, otherwise updates it.
+pub async fn save_order(order: &PaperOrder) -> sqlx::Result<i64> {
+    let created_at = order.created_at.naive_utc();
+    let filled_at = order.filled_at.map(|t| t.naive_utc());
+    if order.order_id == 0 {
+        let rec = sqlx::query!(
+            r"
+                INSERT INTO paper_orders (symbol, asset_type, side, quantity, order_type, limit_price,
+                                          status, created_at, filled_at, fill_price, commission)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                RETURNING order_id
+            ",
+            order.symbol,
+            order.asset_type,
+            order.side,
+            order.quantity,
+            order.order_type,
+            order.limit_price,
+            order.status,
+            created_at,
+            filled_at,
+            order.fill_price,
+            order.commission,
+        )
+        .fetch_one(db())
+        .await?;
+        Ok(rec.order_id)
+    } else {
+        sqlx::query!(
+            r"
+                UPDATE paper_orders
+                SET status = $2, filled_at = $3, fill_price = $4, commission = $5
+                WHERE order_id = $1
+            ",
+            order.order_id,
+            order.status,
+            filled_at,
+            order.fill_price,
+            order.commission,
+        )
+        .execute(db())
+        .await?;
+        Ok(order.order_id)
+    }
+}
+
+pub async fn load_orders(
+    status: Option<&str>,
+    start: DateTime<Local>,
+) -> sqlx::Result<Vec<PaperOrder>> {
+    let start = start.naive_utc();
+    sqlx::query!(
+        r"
+            SELECT order_id, symbol, asset_type, side, quantity, order_type, limit_price,
+                   status, created_at, filled_at, fill_price, commission
+            FROM paper_orders
+            WHERE ($1 IS NULL OR status = $1) AND created_at >= $2
+            ORDER BY created_at ASC
+        ",
+        status,
+        start,
+    )
+    .map(|rec| PaperOrder {
+        order_id: rec.order_id,
+        symbol: rec.symbol,
+        asset_type: rec.asset_type,
+        side: rec.side,
+        quantity: rec.quantity,
+        order_type: rec.order_type,
+        limit_price: rec.limit_price,
+        status: rec.status,
+        created_at: rec.created_at.and_utc().with_timezone(&Local),
+        filled_at: rec.filled_at.map(|t| t.and_utc().with_timezone(&Local)),
+        fill_price: rec.fill_price,
+        commission: rec.commission,
+    })
+    .fetch_all(db())
+    .await
+}
+
+/// Upserts the position, flat positions are removed.
+pub async fn save_position(position: &PaperPosition) -> sqlx::Result<()> {
+    if position.quantity == 0 {
+        sqlx::query!(
+            "DELETE FROM paper_positions WHERE symbol = $1",
+            position.symbol
+        )
+        .execute(db())
+        .await?;
+        return Ok(());
+    }
+
+    let opened_at = position.opened_at.naive_utc();
+    let updated_at = position.updated_at.naive_utc();
+    sqlx::query!(
+        r"
+            INSERT INTO paper_positions (symbol, asset_type, quantity, avg_price, multiplier,
+                                         realized_pnl, opened_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (symbol) DO UPDATE
+            SET quantity = excluded.quantity,
+                avg_price = excluded.avg_price,
+                realized_pnl = excluded.realized_pnl,
+                opened_at = excluded.opened_at,
+                updated_at = excluded.updated_at
+        ",
+        position.symbol,
+        position.asset_type,
+        position.quantity,
+        position.avg_price,
+        position.multiplier,
+        position.realized_pnl,
+        opened_at,
+        updated_at,
+    )
+    .execute(db())
+    .await?;
+    Ok(())
+}
+
+pub async fn load_positions() -> sqlx::Result<Vec<PaperPosition>> {
+    sqlx::query!(
+        r"
+            SELECT symbol, asset_type, quantity, avg_price, multiplier, realized_pnl, opened_at, updated_at
+            FROM paper_positions
+            ORDER BY opened_at ASC
+        "
+    )
+    .map(|rec| PaperPosition {
+        symbol: rec.symbol,
+        asset_type: rec.asset_type,
+        quantity: rec.quantity,
+        avg_price: rec.avg_price,
+        multiplier: rec.multiplier,
+        realized_pnl: rec.realized_pnl,
+        opened_at: rec.opened_at.and_utc().with_timezone(&Local),
+        updated_at: rec.updated_at.and_utc().with_timezone(&Local),
+    })
+    .fetch_all(db())
+    .await
+}
@@ -1,6 +1,6 @@
 use crate::db;
 use itertools::Itertools;
-use sqlx::types::chrono::NaiveDateTime;
+use sqlx::types::chrono::{DateTime, Local};
 
 pub async fn delete_price_levels(symbol: &str) -> sqlx::Result<()> {
     sqlx::query!("DELETE FROM price_levels WHERE symbol = $1", symbol)
@@ -11,7 +11,7 @@ pub async fn delete_price_levels(symbol: &str) -> sqlx::Result<()> {
 
 pub async fn save_price_levels(symbol: &str, price_levels: &[f64]) -> sqlx::Result<()> {
     let price_levels = price_levels.iter().map(|d| format!("{d:.2}")).join(",");
-    let now = util::time::now().naive_local();
+    let now = util::time::now().naive_utc();
 
     sqlx::query!(
         r"
@@ -30,7 +30,7 @@ pub async fn save_price_levels(symbol: &str, price_levels: &[f64]) -> sqlx::Resu
     Ok(())
 }
 
-pub async fn fetch_price_levels(symbol: &str) -> sqlx::Result<Vec<(f64, NaiveDateTime)>> {
+pub async fn fetch_price_levels(symbol: &str) -> sqlx::Result<Vec<(f64, DateTime<Local>)>> {
     let Some(rec) = sqlx::query!(
         r"SELECT price_levels, updated_at FROM price_levels WHERE symbol = $1",
         symbol,
@@ -41,6 +41,7 @@ pub async fn fetch_price_levels(symbol: &str) -> sqlx::Result<Vec<(f64, NaiveDat
         return Ok(vec![]);
     };
 
+    let updated_at = rec.updated_at.and_utc().with_timezone(&Local);
     let price_levels = rec
         .price_levels
         .split(',')
@@ -51,7 +52,7 @@ pub async fn fetch_price_levels(symbol: &str) -> sqlx::Result<Vec<(f64, NaiveDat
                 sqlx::Error::Decode(format!("Cannot convert {s:} into float {e}").into())
             })
         })
-        .map_ok(|p| (p, rec.updated_at))
+        .map_ok(|p| (p, updated_at))
         .collect::<Result<Vec<_>, _>>()?;
     Ok(price_levels)
 }
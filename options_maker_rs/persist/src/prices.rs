@@ -1,5 +1,5 @@
 use crate::db;
-use schwab_client::Candle;
+use schwab_client::{Candle, is_futures};
 use sqlx::types::chrono::{DateTime, Local};
 
 pub async fn recent_price(symbol: &str) -> sqlx::Result<Option<Candle>> {
@@ -19,22 +19,30 @@ pub async fn recent_price(symbol: &str) -> sqlx::Result<Option<Candle>> {
         high: rec.high,
         close: rec.close,
         volume: rec.volume as u64,
-        time: rec.ts.and_local_timezone(Local).unwrap(),
+        time: rec.ts.and_utc().with_timezone(&Local),
         duration: rec.duration,
     })
     .fetch_optional(db())
     .await
 }
 
+/// The futures trade from the evening before their trading date, their candles are loaded from
+/// the opening of the session `start` falls in so that the first session is whole.
 pub async fn load_prices(
     symbol: &str,
     start: DateTime<Local>,
     end: Option<DateTime<Local>>,
 ) -> sqlx::Result<Vec<Candle>> {
-    let start = start.naive_local();
+    let start = if is_futures(symbol) {
+        let session = schwab_client::trading_date(symbol, start);
+        start.min(schwab_client::futures_session_open(session))
+    } else {
+        start
+    };
+    let start = start.naive_utc();
     let end = end
-        .map(|e| e.naive_local())
-        .unwrap_or_else(|| util::time::now().naive_local());
+        .map(|e| e.naive_utc())
+        .unwrap_or_else(|| util::time::now().naive_utc());
     sqlx::query!(
         r"
             SELECT ts, open, low, high, close, volume, duration
@@ -52,17 +60,18 @@ pub async fn load_prices(
         high: rec.high,
         close: rec.close,
         volume: rec.volume as u64,
-        time: rec.ts.and_local_timezone(Local).unwrap(),
+        time: rec.ts.and_utc().with_timezone(&Local),
         duration: rec.duration,
     })
     .fetch_all(db())
     .await
 }
 
+/// Candle times are stored in UTC, independent of where the server runs.
 pub async fn save_prices(symbol: &str, candles: impl AsRef<[Candle]>) -> sqlx::Result<()> {
     let mut trans = db().begin().await?;
     for candle in candles.as_ref() {
-        let ts = candle.time.naive_local();
+        let ts = candle.time.naive_utc();
         let volume = candle.volume as i64;
         sqlx::query!(
             r"
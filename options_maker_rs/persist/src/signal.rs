@@ -0,0 +1,100 @@
+use crate::db;
+use serde::Serialize;
+use sqlx::types::Json;
+use sqlx::types::chrono::{DateTime, Local};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalRecord {
+    pub symbol: String,
+    pub kind: String,
+    pub trend: String,
+    pub price_level: f64,
+    pub found_at: DateTime<Local>,
+    pub points: Vec<(i64, f64)>,
+    pub is_gap_fill: bool,
+    pub is_imminent: bool,
+}
+
+pub async fn save_signals(signals: &[SignalRecord]) -> sqlx::Result<()> {
+    let mut trans = db().begin().await?;
+    for signal in signals {
+        let found_at = signal.found_at.naive_utc();
+        let points = Json(&signal.points);
+        sqlx::query!(
+            r"
+            INSERT INTO signals (symbol, kind, trend, price_level, found_at, points, is_gap_fill, is_imminent)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (symbol, kind, found_at, price_level) DO NOTHING
+            ",
+            signal.symbol,
+            signal.kind,
+            signal.trend,
+            signal.price_level,
+            found_at,
+            points,
+            signal.is_gap_fill,
+            signal.is_imminent,
+        )
+        .execute(&mut *trans)
+        .await?;
+    }
+    trans.commit().await
+}
+
+pub async fn load_signals(
+    symbol: Option<&str>,
+    kind: Option<&str>,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+) -> sqlx::Result<Vec<SignalRecord>> {
+    let start = start.naive_utc();
+    let end = end.naive_utc();
+    sqlx::query!(
+        r#"
+            SELECT symbol, kind, trend, price_level, found_at,
+                   points as "points: Json<Vec<(i64, f64)>>",
+                   is_gap_fill, is_imminent
+            FROM signals
+            WHERE ($1 IS NULL OR symbol = $1)
+              AND ($2 IS NULL OR kind = $2)
+              AND found_at >= $3 AND found_at <= $4
+            ORDER BY found_at ASC
+        "#,
+        symbol,
+        kind,
+        start,
+        end,
+    )
+    .map(|rec| SignalRecord {
+        symbol: rec.symbol,
+        kind: rec.kind,
+        trend: rec.trend,
+        price_level: rec.price_level,
+        found_at: rec.found_at.and_utc().with_timezone(&Local),
+        points: rec.points.0,
+        is_gap_fill: rec.is_gap_fill,
+        is_imminent: rec.is_imminent,
+    })
+    .fetch_all(db())
+    .await
+}
+
+pub async fn signal_symbols(
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+) -> sqlx::Result<Vec<String>> {
+    let start = start.naive_utc();
+    let end = end.naive_utc();
+    sqlx::query!(
+        r"
+            SELECT DISTINCT symbol FROM signals
+            WHERE found_at >= $1 AND found_at <= $2
+            ORDER BY symbol
+        ",
+        start,
+        end,
+    )
+    .map(|rec| rec.symbol)
+    .fetch_all(db())
+    .await
+}
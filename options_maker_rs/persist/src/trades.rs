@@ -0,0 +1,55 @@
+use crate::db;
+use schwab_client::Trade;
+use sqlx::types::chrono::{DateTime, Local};
+
+pub async fn save_trades(trades: &[(String, Trade)]) -> sqlx::Result<()> {
+    let mut trans = db().begin().await?;
+    for (symbol, trade) in trades {
+        let ts = trade.time.naive_utc();
+        let size = trade.size as i64;
+        let sequence = trade.sequence as i64;
+        sqlx::query!(
+            r"
+            INSERT INTO trades (symbol, ts, price, size, sequence)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (symbol, ts, sequence) DO NOTHING
+            ",
+            symbol,
+            ts,
+            trade.price,
+            size,
+            sequence,
+        )
+        .execute(&mut *trans)
+        .await?;
+    }
+    trans.commit().await
+}
+
+pub async fn load_trades(
+    symbol: &str,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+) -> sqlx::Result<Vec<Trade>> {
+    let start = start.naive_utc();
+    let end = end.naive_utc();
+    sqlx::query!(
+        r"
+            SELECT ts, price, size, sequence
+            FROM trades
+            WHERE symbol = $1 AND ts >= $2 AND ts <= $3
+            ORDER BY ts, sequence
+        ",
+        symbol,
+        start,
+        end,
+    )
+    .map(|rec| Trade {
+        price: rec.price,
+        size: rec.size as u64,
+        time: rec.ts.and_utc().with_timezone(&Local),
+        sequence: rec.sequence as u64,
+    })
+    .fetch_all(db())
+    .await
+}
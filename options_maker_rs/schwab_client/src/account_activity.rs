@@ -0,0 +1,159 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Key of the ACCT_ACTIVITY subscription, the service streams every account of the login.
+pub const ACCOUNT_ACTIVITY_KEY: &str = "Account Activity";
+
+/// An order event of the ACCT_ACTIVITY stream. The payload layout differs per message type and
+/// asset, so the commonly needed fields are picked out of it and the rest is kept in `data`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountActivity {
+    pub account: String,
+    pub message_type: String,
+    pub order_id: Option<i64>,
+    pub symbol: Option<String>,
+    pub quantity: Option<f64>,
+    pub price: Option<f64>,
+    pub time: DateTime<Local>,
+    pub data: Value,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ActivityKind {
+    Fill,
+    Cancel,
+    PositionChange,
+    OrderStatus,
+}
+
+impl AccountActivity {
+    /// Parses a content item: "1" is the account, "2" the message type and "3" its JSON payload.
+    pub(crate) fn from_stream(value: &Value) -> Option<(ActivityKind, Self)> {
+        let message_type = value.get("2").and_then(Value::as_str)?;
+        if message_type == "SUBSCRIBED" {
+            return None;
+        }
+        let data = match value.get("3") {
+            Some(Value::String(data)) => serde_json::from_str(data).unwrap_or(Value::Null),
+            Some(data) => data.clone(),
+            None => Value::Null,
+        };
+        let account = value
+            .get("1")
+            .and_then(Value::as_str)
+            .filter(|account| !account.is_empty())
+            .map(str::to_owned)
+            .or_else(|| find_str(&data, &["AccountNumber"]))
+            .unwrap_or_default();
+        let activity = Self {
+            account,
+            message_type: message_type.to_owned(),
+            order_id: find_i64(&data, &["SchwabOrderID", "OrderId", "orderId"]),
+            symbol: find_str(&data, &["Symbol", "symbol"]),
+            quantity: find_f64(
+                &data,
+                &["LastFillQuantity", "ExecutionQuantity", "Quantity"],
+            ),
+            price: find_f64(
+                &data,
+                &["LastFillPrice", "ExecutionPrice", "LimitPrice", "Price"],
+            ),
+            time: util::time::now(),
+            data,
+        };
+        Some((ActivityKind::of(message_type), activity))
+    }
+}
+
+impl ActivityKind {
+    fn of(message_type: &str) -> Self {
+        if message_type.contains("Fill") || message_type == "ExecutionCreated" {
+            ActivityKind::Fill
+        } else if message_type.contains("Cancel") || message_type.contains("UROut") {
+            // "UROut" is Schwab's "you are out" confirmation of a canceled order
+            ActivityKind::Cancel
+        } else if message_type.contains("Position") {
+            ActivityKind::PositionChange
+        } else {
+            ActivityKind::OrderStatus
+        }
+    }
+}
+
+/// Depth first search for the first of `keys`, the payloads nest the fields at varying depths.
+fn find<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => keys
+            .iter()
+            .find_map(|key| map.get(*key).filter(|v| !v.is_null()))
+            .or_else(|| map.values().find_map(|v| find(v, keys))),
+        Value::Array(values) => values.iter().find_map(|v| find(v, keys)),
+        _ => None,
+    }
+}
+
+fn find_str(value: &Value, keys: &[&str]) -> Option<String> {
+    match find(value, keys)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn find_i64(value: &Value, keys: &[&str]) -> Option<i64> {
+    match find(value, keys)? {
+        Value::String(s) => s.parse().ok(),
+        v => v.as_i64(),
+    }
+}
+
+fn find_f64(value: &Value, keys: &[&str]) -> Option<f64> {
+    match find(value, keys)? {
+        Value::String(s) => s.parse().ok(),
+        v => v.as_f64(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AccountActivity, ActivityKind};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_activity() {
+        let data = json!({
+            "SchwabOrderID": "1002938475",
+            "AccountNumber": "12345678",
+            "BaseEvent": {
+                "EventType": "OrderFillCompleted",
+                "ExecutionLeg": {
+                    "Symbol": "AAPL",
+                    "LastFillQuantity": "10",
+                    "LastFillPrice": 190.25
+                }
+            }
+        });
+        let value = json!({
+            "key": "Account Activity",
+            "1": "12345678",
+            "2": "OrderFillCompleted",
+            "3": data.to_string()
+        });
+        let (kind, activity) = AccountActivity::from_stream(&value).unwrap();
+        assert_eq!(kind, ActivityKind::Fill);
+        assert_eq!(activity.account, "12345678");
+        assert_eq!(activity.order_id, Some(1002938475));
+        assert_eq!(activity.symbol.as_deref(), Some("AAPL"));
+        assert_eq!(activity.quantity, Some(10.0));
+        assert_eq!(activity.price, Some(190.25));
+
+        let cancel = json!({ "1": "12345678", "2": "OrderUROutCompleted", "3": "{}" });
+        let (kind, activity) = AccountActivity::from_stream(&cancel).unwrap();
+        assert_eq!(kind, ActivityKind::Cancel);
+        assert_eq!(activity.order_id, None);
+
+        let subscribed = json!({ "1": "", "2": "SUBSCRIBED", "3": "" });
+        assert!(AccountActivity::from_stream(&subscribed).is_none());
+    }
+}
@@ -0,0 +1,277 @@
+use crate::auth::Authenticator;
+use crate::{SchwabError, SchwabResult};
+use app_config::SchwabApiConfig;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use util::http::{
+    Client, Error, Method, Proxy, RequestBuilder, Response, StatusCode, header::RETRY_AFTER,
+};
+
+const USER_AGENT: &str = concat!("options_maker/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP client of the Schwab API built off [SchwabApiConfig]. Requests share a token bucket
+/// matching Schwab's per-minute limit, and are retried with jittered exponential backoff on
+/// 429s and connection failures. Only GETs and DELETEs are retried on timeouts and server errors
+/// too, a POST or PUT that timed out may still have placed its order.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiClient {
+    url: String,
+    client: Client,
+    max_retries: u32,
+    retry_backoff: Duration,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+pub(crate) struct ApiRequest<'a> {
+    api: &'a ApiClient,
+    method: Method,
+    builder: RequestBuilder,
+    auth: Option<&'a Authenticator>,
+}
+
+/// Token bucket holding up to `burst` requests, refilled at `per_minute` requests a minute.
+/// Callers reserve a token up front and the bucket may go negative, so that waiters are
+/// served in order instead of racing for every refill.
+#[derive(Debug)]
+struct RateLimiter {
+    rate_per_sec: f64,
+    burst: f64,
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl ApiClient {
+    pub fn new(config: &SchwabApiConfig) -> SchwabResult<Self> {
+        let mut builder = Client::builder()
+            .user_agent(config.user_agent.as_deref().unwrap_or(USER_AGENT))
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .timeout(Duration::from_secs(config.timeout_secs));
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        Ok(Self {
+            url: config.url.trim_end_matches('/').to_owned(),
+            client: builder.build()?,
+            max_retries: config.max_retries,
+            retry_backoff: Duration::from_millis(config.retry_backoff_ms),
+            limiter: (config.requests_per_minute > 0)
+                .then(|| Arc::new(RateLimiter::new(config.requests_per_minute, config.burst))),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn get(&self, path: impl Display) -> ApiRequest<'_> {
+        self.request(Method::GET, path)
+    }
+
+    pub fn post(&self, path: impl Display) -> ApiRequest<'_> {
+        self.request(Method::POST, path)
+    }
+
+    #[cfg_attr(not(feature = "trading"), allow(dead_code))]
+    pub fn put(&self, path: impl Display) -> ApiRequest<'_> {
+        self.request(Method::PUT, path)
+    }
+
+    #[cfg_attr(not(feature = "trading"), allow(dead_code))]
+    pub fn delete(&self, path: impl Display) -> ApiRequest<'_> {
+        self.request(Method::DELETE, path)
+    }
+
+    fn request(&self, method: Method, path: impl Display) -> ApiRequest<'_> {
+        ApiRequest {
+            api: self,
+            builder: self
+                .client
+                .request(method.clone(), format!("{}{path}", self.url)),
+            method,
+            auth: None,
+        }
+    }
+
+    /// Exponential backoff of the attempt with "equal jitter": somewhere between half and all
+    /// of it, so that the requests rejected together don't come back together.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .retry_backoff
+            .saturating_mul(2u32.saturating_pow(attempt));
+        let millis = backoff.as_millis() as u64;
+        Duration::from_millis(rand::random_range(millis / 2..=millis))
+    }
+}
+
+impl ApiRequest<'_> {
+    pub fn basic_auth(mut self, username: impl Display, password: Option<impl Display>) -> Self {
+        self.builder = self.builder.basic_auth(username, password);
+        self
+    }
+
+    pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+        self.builder = self.builder.query(query);
+        self
+    }
+
+    pub fn form<T: Serialize + ?Sized>(mut self, form: &T) -> Self {
+        self.builder = self.builder.form(form);
+        self
+    }
+
+    #[cfg_attr(not(feature = "trading"), allow(dead_code))]
+    pub fn json<T: Serialize + ?Sized>(mut self, json: &T) -> Self {
+        self.builder = self.builder.json(json);
+        self
+    }
+}
+
+impl<'a> ApiRequest<'a> {
+    /// Sends the current access token of `auth`, a 401 refreshes it and retries once.
+    pub fn authorized(mut self, auth: &'a Authenticator) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub async fn send(self) -> SchwabResult<Response> {
+        let Self {
+            api,
+            method,
+            builder: mut request,
+            auth,
+        } = self;
+        // A replace (PUT) cancels the order and places a new one, retrying it could place two
+        let is_idempotent = matches!(method, Method::GET | Method::DELETE);
+        let mut attempt = 0;
+        let mut refreshed = false;
+        loop {
+            if let Some(limiter) = &api.limiter {
+                limiter.acquire().await;
+            }
+            let next = request.try_clone();
+            let token = auth.map(Authenticator::access_token);
+            if let Some(token) = &token {
+                request = request.bearer_auth(token);
+            }
+            let result = request.send().await;
+            let Some(next) = next else {
+                return Ok(result?);
+            };
+
+            if let (Ok(response), Some(auth)) = (&result, auth)
+                && response.status() == StatusCode::UNAUTHORIZED
+                && !refreshed
+            {
+                warn!(
+                    "Access token was rejected by {}, refreshing it",
+                    response.url()
+                );
+                // Boxed as refreshing sends a request of its own
+                Box::pin(auth.refresh(api, token.as_deref()))
+                    .await
+                    .map_err(SchwabError::AuthError)?;
+                refreshed = true;
+                request = next;
+                continue;
+            }
+
+            let delay = match &result {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    Some(retry_after(response).unwrap_or_else(|| api.backoff(attempt)))
+                }
+                Ok(response) if is_idempotent && response.status().is_server_error() => {
+                    Some(api.backoff(attempt))
+                }
+                Err(e) if e.is_connect() || (is_idempotent && e.is_timeout()) => {
+                    Some(api.backoff(attempt))
+                }
+                _ => None,
+            };
+            let Some(delay) = delay.filter(|_| attempt < api.max_retries) else {
+                return Ok(result?);
+            };
+
+            warn!(
+                "Request failed with {}, retrying in {delay:?}",
+                describe(&result)
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            request = next;
+        }
+    }
+}
+
+impl RateLimiter {
+    fn new(per_minute: u32, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        Self {
+            rate_per_sec: per_minute as f64 / 60.0,
+            burst,
+            bucket: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let (tokens, refilled_at) = &mut *bucket;
+            let now = Instant::now();
+            *tokens =
+                (*tokens + (now - *refilled_at).as_secs_f64() * self.rate_per_sec).min(self.burst);
+            *refilled_at = now;
+            *tokens -= 1.0;
+            Duration::from_secs_f64((-*tokens).max(0.0) / self.rate_per_sec)
+        };
+        if !wait.is_zero() {
+            debug!("Rate limited, waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+fn describe(result: &Result<Response, Error>) -> String {
+    match result {
+        Ok(response) => format!("{} from {}", response.status(), response.url()),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        // Paused, the clock only moves as the limiter sleeps
+        tokio::time::pause();
+        let limiter = RateLimiter::new(600, 3);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // Past the burst, requests are spaced at the refill rate of 10 a second
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        // Timers round the sleeps up to the next millisecond
+        assert!((0.3..0.305).contains(&elapsed), "elapsed {elapsed}s");
+    }
+}
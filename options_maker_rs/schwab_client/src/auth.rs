@@ -1,21 +1,14 @@
-use crate::API_URL;
+use crate::api_client::{ApiClient, ApiRequest};
 use anyhow::Context;
 use app_config::APP_CONFIG;
-use axum::Router;
-use axum::extract::{Query, State};
-use axum::response::Html;
-use axum::routing::get;
-use axum_server::Handle;
-use axum_server::tls_rustls::RustlsConfig;
-use serde::Deserialize;
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::net::{Ipv4Addr, SocketAddr};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::RwLock;
 use tokio::sync::Mutex;
-use tracing::{info, warn};
+use tracing::{debug, info};
 use url::Url;
-use util::http::{HTTP_CLIENT, RequestBuilder, header};
+use util::time;
 
 #[derive(Debug, Deserialize)]
 pub struct TokenResponse {
@@ -26,136 +19,212 @@ pub struct TokenResponse {
     pub scope: String,
 }
 
-pub async fn init_auth() -> anyhow::Result<TokenResponse> {
-    let redirect_url = Url::parse(&APP_CONFIG.schwab_callback_url)
-        .with_context(|| format!("Couldn't parse {}", APP_CONFIG.schwab_callback_url))?;
-    info!("Initializing client with callback url: {redirect_url}");
+/// OAuth client of the app.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Whether the client may place orders, it then logs in with the `api` scope.
+    pub trading: bool,
+}
 
-    let auth_url = format!("{API_URL}/v1/oauth/authorize");
-    let mut auth_url =
-        Url::parse(&auth_url).with_context(|| format!("Couldn't parse {auth_url}"))?;
-    auth_url
-        .query_pairs_mut()
-        .append_pair("client_id", &APP_CONFIG.schwab_client_id)
-        .append_pair("redirect_uri", redirect_url.as_ref())
-        .append_pair("response_type", "code")
-        .append_pair("scope", "readonly");
-    info!("Auth url:\n{auth_url}\n");
+pub(crate) const READONLY_SCOPE: &str = "readonly";
+pub(crate) const TRADING_SCOPE: &str = "api";
 
-    let token_response = retrieve_auth_tokens(redirect_url).await?;
-    info!("Received exchange tokens from schwab server");
-    Ok(token_response)
+impl Credentials {
+    pub fn from_config() -> Self {
+        Self {
+            client_id: APP_CONFIG.schwab_client_id.clone(),
+            client_secret: APP_CONFIG.schwab_client_secret.clone(),
+            trading: cfg!(feature = "trading") && APP_CONFIG.enable_trading,
+        }
+    }
+
+    /// Trading needs the full `api` scope, everything else works with `readonly`.
+    pub fn scope(&self) -> &'static str {
+        if self.trading {
+            TRADING_SCOPE
+        } else {
+            READONLY_SCOPE
+        }
+    }
+}
+
+/// Refresh token persisted to the token file, Schwab lets it live for 7 days.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct RefreshToken {
+    pub refresh_token: String,
+    pub token_type: String,
+    pub scope: String,
+    pub expires_at: DateTime<Local>,
 }
 
-async fn retrieve_auth_tokens(redirect_url: Url) -> anyhow::Result<TokenResponse> {
-    #[derive(Debug, Deserialize)]
-    pub struct AuthCallback {
-        code: Option<String>,
-        error: Option<String>,
-        error_description: Option<String>,
-    }
-
-    #[derive(Debug, Clone)]
-    struct AuthState {
-        redirect_uri: String,
-        handle: Handle,
-        exchange_token: Arc<Mutex<Option<TokenResponse>>>,
-    }
-
-    async fn auth_handler(
-        Query(params): Query<AuthCallback>,
-        State(auth_state): State<AuthState>,
-    ) -> Result<Html<String>, Html<String>> {
-        if let Some(error) = params.error {
-            let error_desc = params.error_description.unwrap_or_default();
-            warn!("Auth failure: {error}/{error_desc}");
-            return Err(Html(format!(
-                r"
-                <h1>OAuth Error</h1>
-                <p>Error: {error}</p><p>Description: {error_desc}</p>
-                ",
-            )));
+impl RefreshToken {
+    pub fn issued(token: &TokenResponse) -> Self {
+        Self {
+            refresh_token: token.refresh_token.clone(),
+            token_type: token.token_type.clone(),
+            scope: token.scope.clone(),
+            expires_at: time::now() + ChronoDuration::days(7),
         }
-        let Some(code) = params.code else {
-            warn!("Didn't receive the auth code from Schwab");
-            return Err(Html(
-                r"
-                <h1>OAuth Error</h1>
-                <p>Didn't receive auth code from schwab in the response</p>
-                "
-                .to_owned(),
-            ));
+    }
+}
+
+#[derive(Debug)]
+struct AccessToken {
+    access_token: String,
+    expires_at: DateTime<Local>,
+}
+
+/// Access token shared by the REST calls and the streamer. It's refreshed off the refresh token
+/// ahead of its expiry, and right away when the API rejects it. A new login swaps both tokens in
+/// place, so that neither the client nor the streamer has to be recreated.
+#[derive(Debug)]
+pub(crate) struct Authenticator {
+    credentials: Credentials,
+    refresh_token: RwLock<RefreshToken>,
+    access_token: RwLock<AccessToken>,
+    refreshing: Mutex<()>,
+}
+
+impl Authenticator {
+    pub fn new(
+        credentials: Credentials,
+        refresh_token: RefreshToken,
+        access_token: impl Into<String>,
+        expires_in: i64,
+    ) -> Self {
+        Self {
+            credentials,
+            refresh_token: RwLock::new(refresh_token),
+            access_token: RwLock::new(AccessToken {
+                access_token: access_token.into(),
+                expires_at: time::now() + ChronoDuration::seconds(expires_in),
+            }),
+            refreshing: Mutex::new(()),
+        }
+    }
+
+    /// Fetches the first access token off the refresh token.
+    pub async fn login(
+        api: &ApiClient,
+        credentials: Credentials,
+        refresh_token: RefreshToken,
+    ) -> anyhow::Result<Self> {
+        let (access_token, expires_in) =
+            fetch_access_token(api, &credentials, &refresh_token.refresh_token).await?;
+        let auth = Self::new(credentials, refresh_token, access_token, expires_in);
+        info!("Fetched new access token, expires at {}", auth.expires_at());
+        Ok(auth)
+    }
+
+    pub fn access_token(&self) -> String {
+        self.access_token.read().unwrap().access_token.clone()
+    }
+
+    pub fn expires_at(&self) -> DateTime<Local> {
+        self.access_token.read().unwrap().expires_at
+    }
+
+    pub fn refresh_token_expires_at(&self) -> DateTime<Local> {
+        self.refresh_token.read().unwrap().expires_at
+    }
+
+    /// Fetches a new access token, unless the `rejected` one was already replaced by a
+    /// concurrent refresh.
+    pub async fn refresh(&self, api: &ApiClient, rejected: Option<&str>) -> anyhow::Result<()> {
+        let _guard = self.refreshing.lock().await;
+        if rejected.is_some_and(|rejected| rejected != self.access_token()) {
+            return Ok(());
+        }
+
+        let refresh_token = self.refresh_token.read().unwrap().refresh_token.clone();
+        let (access_token, expires_in) =
+            fetch_access_token(api, &self.credentials, &refresh_token).await?;
+        let expires_at = time::now() + ChronoDuration::seconds(expires_in);
+        *self.access_token.write().unwrap() = AccessToken {
+            access_token,
+            expires_at,
         };
-        let token_response = match exchange_tokens(&code, &auth_state.redirect_uri).await {
-            Ok(response) => response,
-            Err(e) => {
-                warn!("Failed to retrieved the exchange tokens: {e}");
-                return Err(Html(format!(
-                    r"
-                    <h1>OAuth Error</h1>
-                    <p>Failed to retrieve exchange token: {e:?}</p>
-                   ",
-                )));
-            }
+        debug!("Successfully refreshed access token, expires at {expires_at}");
+        Ok(())
+    }
+
+    /// Schwab's authorization page, it redirects back to `redirect_uri` with the code to
+    /// [Self::exchange_code].
+    pub fn authorize_url(
+        &self,
+        api: &ApiClient,
+        redirect_uri: &str,
+        state: Option<&str>,
+    ) -> anyhow::Result<Url> {
+        authorize_url(api, &self.credentials, redirect_uri, state)
+    }
+
+    #[cfg(feature = "trading")]
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    /// Exchanges the authorization code of a new login and swaps in the fresh tokens.
+    pub async fn exchange_code(
+        &self,
+        api: &ApiClient,
+        code: &str,
+        redirect_uri: &str,
+    ) -> anyhow::Result<RefreshToken> {
+        let _guard = self.refreshing.lock().await;
+        let token = exchange_tokens(api, &self.credentials, code, redirect_uri).await?;
+        let refresh_token = RefreshToken::issued(&token);
+        *self.refresh_token.write().unwrap() = refresh_token.clone();
+        *self.access_token.write().unwrap() = AccessToken {
+            access_token: token.access_token,
+            expires_at: time::now() + ChronoDuration::seconds(token.expires_in),
         };
-        auth_state
-            .exchange_token
-            .lock()
-            .await
-            .replace(token_response);
-        auth_state
-            .handle
-            .graceful_shutdown(Some(Duration::from_secs(5)));
-        Ok(Html(
-            r"
-            <h1>OAuth success!</h1>
-            <p>You may close this window now!</p>
-            "
-            .to_owned(),
-        ))
-    }
-
-    let auth_state = AuthState {
-        redirect_uri: redirect_url.to_string(),
-        handle: Handle::new(),
-        exchange_token: Arc::new(Mutex::new(None)),
-    };
-    let port = redirect_url.port().unwrap_or(443);
-    let router = Router::new()
-        .route(redirect_url.path(), get(auth_handler))
-        .with_state(auth_state.clone());
-    let rustls_config =
-        RustlsConfig::from_pem_file(&APP_CONFIG.openssl_cert_file, &APP_CONFIG.openssl_key_file)
-            .await?;
-    info!("Starting axum server and listening to {redirect_url}");
-    axum_server::bind_rustls(
-        SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)),
-        rustls_config,
-    )
-    .handle(auth_state.handle.clone())
-    .serve(router.into_make_service())
-    .await?;
-
-    info!("Axum server gracefully stopped, checking if exchange token was retrieved");
-    let token = auth_state
-        .exchange_token
-        .lock()
-        .await
-        .take()
-        .ok_or_else(|| anyhow::anyhow!("No exchange token found"))?;
-    info!("Exchange token is successfully retrieved");
-    Ok(token)
+        info!(
+            "Logged in again, refresh token expires at {}",
+            refresh_token.expires_at
+        );
+        Ok(refresh_token)
+    }
 }
 
-async fn exchange_tokens(code: &str, redirect_uri: &str) -> anyhow::Result<TokenResponse> {
+pub(crate) fn authorize_url(
+    api: &ApiClient,
+    credentials: &Credentials,
+    redirect_uri: &str,
+    state: Option<&str>,
+) -> anyhow::Result<Url> {
+    let auth_url = format!("{}/v1/oauth/authorize", api.url());
+    let mut auth_url =
+        Url::parse(&auth_url).with_context(|| format!("Couldn't parse {auth_url}"))?;
+    let mut query = auth_url.query_pairs_mut();
+    query
+        .append_pair("client_id", &credentials.client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("response_type", "code")
+        .append_pair("scope", credentials.scope());
+    if let Some(state) = state {
+        query.append_pair("state", state);
+    }
+    drop(query);
+    Ok(auth_url)
+}
+
+pub(crate) async fn exchange_tokens(
+    api: &ApiClient,
+    credentials: &Credentials,
+    code: &str,
+    redirect_uri: &str,
+) -> anyhow::Result<TokenResponse> {
     info!("Sending post request for token exchange");
     let params = json!({
         "grant_type": "authorization_code",
         "code": code,
         "redirect_uri": redirect_uri,
-        "client_id": &APP_CONFIG.schwab_client_id,
+        "client_id": &credentials.client_id,
     });
-    let response = prepare_post_request()
+    let response = prepare_post_request(api, credentials)
         .form(&params)
         .send()
         .await
@@ -172,13 +241,17 @@ async fn exchange_tokens(code: &str, redirect_uri: &str) -> anyhow::Result<Token
     Ok(token_response)
 }
 
-pub async fn fetch_access_token(refresh_token: &str) -> anyhow::Result<(String, i64)> {
+async fn fetch_access_token(
+    api: &ApiClient,
+    credentials: &Credentials,
+    refresh_token: &str,
+) -> anyhow::Result<(String, i64)> {
     info!("Sending post request for token exchange");
     let form_data = json!({
         "grant_type": "refresh_token",
         "refresh_token": refresh_token,
     });
-    let response = prepare_post_request()
+    let response = prepare_post_request(api, credentials)
         .form(&form_data)
         .send()
         .await
@@ -203,12 +276,45 @@ pub async fn fetch_access_token(refresh_token: &str) -> anyhow::Result<(String,
     Ok((response.access_token, response.expires_in))
 }
 
-fn prepare_post_request() -> RequestBuilder {
-    HTTP_CLIENT
-        .post(format!("{API_URL}/v1/oauth/token"))
-        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .basic_auth(
-            &APP_CONFIG.schwab_client_id,
-            Some(&APP_CONFIG.schwab_client_secret),
-        )
+fn prepare_post_request<'a>(api: &'a ApiClient, credentials: &Credentials) -> ApiRequest<'a> {
+    api.post("/v1/oauth/token")
+        .basic_auth(&credentials.client_id, Some(&credentials.client_secret))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Authenticator, Credentials, RefreshToken};
+    use crate::api_client::ApiClient;
+    use app_config::SchwabApiConfig;
+    use schwab_mock::{MockData, MockServer};
+
+    #[tokio::test]
+    async fn test_exchange_code() -> anyhow::Result<()> {
+        let server = MockServer::start(MockData::default()).await?;
+        let api = ApiClient::new(&SchwabApiConfig {
+            url: server.url(),
+            ..SchwabApiConfig::default()
+        })?;
+        let credentials = Credentials {
+            client_id: "client-id".to_owned(),
+            client_secret: "client-secret".to_owned(),
+            trading: false,
+        };
+        let refresh_token = RefreshToken {
+            refresh_token: "old-refresh-token".to_owned(),
+            token_type: "Bearer".to_owned(),
+            scope: super::READONLY_SCOPE.to_owned(),
+            expires_at: util::time::now() + chrono::Duration::days(1),
+        };
+        let auth = Authenticator::new(credentials, refresh_token, "old-token", 1800);
+
+        let refresh_token = auth
+            .exchange_code(&api, "auth-code", "https://127.0.0.1/api/auth/callback")
+            .await?;
+        assert_eq!(refresh_token.refresh_token, "mock-refresh-token");
+        assert_eq!(auth.access_token(), "mock-access-token-1");
+        assert!(auth.refresh_token_expires_at() > util::time::now() + chrono::Duration::days(6));
+        assert_eq!(server.tokens_issued(), 1);
+        Ok(())
+    }
 }
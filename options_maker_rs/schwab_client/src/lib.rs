@@ -1,22 +1,121 @@
-use chrono::{DateTime, Local};
+use app_config::APP_CONFIG;
+use chrono::{DateTime, Days, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
 use tokio_tungstenite::tungstenite;
+use util::time::MarketTime;
 
+mod account_activity;
+mod api_client;
 mod auth;
 mod candle;
+mod option_chain;
+mod order_book;
+#[cfg(feature = "trading")]
+mod orders;
 pub mod schwab_client;
 pub mod streaming_client;
+mod token_store;
 
+pub use account_activity::{ACCOUNT_ACTIVITY_KEY, AccountActivity};
+pub use auth::Credentials;
 pub use candle::Candle;
+pub use option_chain::{
+    ContractType, OptionChain, OptionChainParams, OptionContract, OptionStrategy, PutCall,
+    StrikeRange, Underlying,
+};
+pub use order_book::{BookEntry, BookLevel, OrderBook};
+#[cfg(feature = "trading")]
+pub use orders::{
+    ComplexOrderStrategyType, Instruction, Order, OrderAssetType, OrderBuilder, OrderDuration,
+    OrderInstrument, OrderLeg, OrderLegInstrument, OrderLegStatus, OrderRequest, OrderStrategyType,
+    OrderType, Session,
+};
 
 pub type SchwabResult<T> = Result<T, SchwabError>;
 
-pub const API_URL: &str = "https://api.schwabapi.com";
+/// Exchange the futures are listed on as far as the app is concerned.
+pub const FUTURES_EXCHANGE: &str = "CME";
+
+/// Schwab prefixes futures with a slash, e.g. `/ES` for the front month or `/ESZ25`.
+pub fn is_futures(symbol: &str) -> bool {
+    symbol.starts_with('/')
+}
+
+/// Dollar value of a point of the futures contract, e.g. 50 for `/ES` or `/ESZ25`.
+pub fn futures_multiplier(symbol: &str) -> Option<f64> {
+    let root = symbol.strip_prefix('/')?;
+    // The contract month and year follow the root, e.g. Z25 for December 2025
+    let root = match root.as_bytes() {
+        [.., month, y1, y2]
+            if root.len() > 3
+                && b"FGHJKMNQUVXZ".contains(month)
+                && y1.is_ascii_digit()
+                && y2.is_ascii_digit() =>
+        {
+            &root[..root.len() - 3]
+        }
+        _ => root,
+    };
+    let multiplier = match root {
+        "ES" | "RTY" => 50.0,
+        "MES" | "M2K" => 5.0,
+        "NQ" => 20.0,
+        "MNQ" => 2.0,
+        "YM" => 5.0,
+        "MYM" => 0.5,
+        "CL" => 1000.0,
+        "MCL" => 100.0,
+        "NG" => 10000.0,
+        "GC" => 100.0,
+        "MGC" => 10.0,
+        "SI" => 5000.0,
+        "ZB" | "ZN" | "ZF" => 1000.0,
+        "ZT" => 2000.0,
+        _ => return None,
+    };
+    Some(multiplier)
+}
+
+/// Trading date of `time` for `symbol`, the futures session opening in the evening trades the
+/// next day.
+pub fn trading_date(symbol: &str, time: DateTime<Local>) -> NaiveDate {
+    if is_futures(symbol) {
+        let tz = util::time::exchange_tz(FUTURES_EXCHANGE);
+        util::time::session_date(
+            time.with_timezone(&tz).naive_local(),
+            APP_CONFIG.trade_config.futures_hours,
+        )
+    } else {
+        time.market_date()
+    }
+}
+
+/// Opening of the futures session trading on `date`, the evening before unless the session
+/// doesn't wrap around midnight.
+pub fn futures_session_open(date: NaiveDate) -> DateTime<Local> {
+    let (start, end) = APP_CONFIG.trade_config.futures_hours;
+    let open_date = if start > end {
+        date - Days::new(1)
+    } else {
+        date
+    };
+    util::time::from_wall_clock(
+        util::time::exchange_tz(FUTURES_EXCHANGE),
+        open_date.and_time(start),
+    )
+}
+
+/// Option contracts use the 21 character OSI symbol, e.g. `AAPL  251219C00200000`.
+pub fn is_option(symbol: &str) -> bool {
+    symbol.len() == 21 && matches!(symbol.as_bytes()[12], b'C' | b'P')
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum SchwabError {
     #[error("Authentication failure: {0}")]
     AuthError(anyhow::Error),
+    #[error("No valid refresh token, log in first")]
+    LoginRequired,
     #[error("HttpError: {0}")]
     HttpError(#[from] util::http::Error),
     #[error("IO Error: {0}")]
@@ -44,6 +143,8 @@ pub struct Account {
     pub initial_balances: Balances,
     pub current_balances: Balances,
     pub projected_balances: Balances,
+    #[serde(default)]
+    pub positions: Vec<Position>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -55,6 +156,46 @@ pub struct Balances {
     pub cash_balance: f64,
     #[serde(default)]
     pub total_cash: f64,
+    #[serde(default)]
+    pub liquidation_value: f64,
+    #[serde(default)]
+    pub long_market_value: f64,
+    #[serde(default)]
+    pub short_market_value: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    pub short_quantity: f64,
+    pub long_quantity: f64,
+    pub average_price: f64,
+    pub market_value: f64,
+    #[serde(default)]
+    pub current_day_profit_loss: f64,
+    #[serde(default)]
+    pub long_open_profit_loss: Option<f64>,
+    #[serde(default)]
+    pub short_open_profit_loss: Option<f64>,
+    pub instrument: PositionInstrument,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionInstrument {
+    pub asset_type: String,
+    pub symbol: String,
+    pub cusip: Option<String>,
+    pub description: Option<String>,
+    pub put_call: Option<PutCall>,
+    pub underlying_symbol: Option<String>,
+}
+
+impl Position {
+    /// Net quantity of the position, negative for shorts.
+    pub fn quantity(&self) -> f64 {
+        self.long_quantity - self.short_quantity
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -88,6 +229,15 @@ pub struct Quote {
     pub quote_time: Option<DateTime<Local>>,
 }
 
+/// A single print of the time & sales stream.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Trade {
+    pub price: f64,
+    pub size: u64,
+    pub time: DateTime<Local>,
+    pub sequence: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Instrument {
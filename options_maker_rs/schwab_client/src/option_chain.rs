@@ -0,0 +1,448 @@
+use chrono::{DateTime, Local, Month, NaiveDate};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ContractType {
+    Call,
+    Put,
+    #[default]
+    All,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum StrikeRange {
+    InTheMoney,
+    NearTheMoney,
+    OutOfTheMoney,
+    StrikesAboveMarket,
+    StrikesBelowMarket,
+    StrikesNearMarket,
+    #[default]
+    All,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum OptionStrategy {
+    #[default]
+    Single,
+    /// Theoretical values and greeks computed by Schwab with the given overrides
+    Analytical {
+        volatility: Option<f64>,
+        underlying_price: Option<f64>,
+        interest_rate: Option<f64>,
+        days_to_expiration: Option<u32>,
+    },
+    Covered,
+    Vertical,
+    Calendar,
+    Strangle,
+    Straddle,
+    Butterfly,
+    Condor,
+    Diagonal,
+    Collar,
+    Roll,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OptionChainParams {
+    pub contract_type: ContractType,
+    pub strike_count: Option<u32>,
+    pub include_underlying_quote: bool,
+    pub strategy: OptionStrategy,
+    /// Strike interval for spread strategies
+    pub interval: Option<f64>,
+    pub strike: Option<f64>,
+    pub range: StrikeRange,
+    pub from_date: Option<NaiveDate>,
+    pub to_date: Option<NaiveDate>,
+    pub exp_month: Option<Month>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PutCall {
+    Put,
+    Call,
+}
+
+/// Option chain with contracts of every expiration flattened and sorted by expiration, then strike.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawOptionChain")]
+pub struct OptionChain {
+    pub symbol: String,
+    pub status: String,
+    pub underlying: Option<Underlying>,
+    pub strategy: String,
+    pub interval: f64,
+    pub is_delayed: bool,
+    pub is_index: bool,
+    pub interest_rate: f64,
+    pub underlying_price: f64,
+    pub volatility: f64,
+    pub days_to_expiration: f64,
+    pub calls: Vec<OptionContract>,
+    pub puts: Vec<OptionContract>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Underlying {
+    pub symbol: String,
+    pub description: Option<String>,
+    pub bid: f64,
+    pub ask: f64,
+    pub last: f64,
+    pub mark: Option<f64>,
+    pub change: Option<f64>,
+    pub percent_change: Option<f64>,
+    pub open_price: Option<f64>,
+    pub high_price: Option<f64>,
+    pub low_price: Option<f64>,
+    pub close: Option<f64>,
+    pub total_volume: Option<u64>,
+    #[serde(default, deserialize_with = "util::time::parse_timestamp_opt")]
+    pub quote_time: Option<DateTime<Local>>,
+    #[serde(default, deserialize_with = "util::time::parse_timestamp_opt")]
+    pub trade_time: Option<DateTime<Local>>,
+}
+
+/// A single option contract; greeks and volatilities which Schwab couldn't compute are `NaN`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionContract {
+    pub put_call: PutCall,
+    pub symbol: String,
+    pub description: String,
+    #[serde(skip_deserializing)]
+    pub expiration: NaiveDate,
+    pub strike_price: f64,
+    #[serde(deserialize_with = "parse_f64")]
+    pub bid: f64,
+    #[serde(deserialize_with = "parse_f64")]
+    pub ask: f64,
+    #[serde(deserialize_with = "parse_f64")]
+    pub last: f64,
+    #[serde(deserialize_with = "parse_f64")]
+    pub mark: f64,
+    #[serde(default)]
+    pub bid_size: u64,
+    #[serde(default)]
+    pub ask_size: u64,
+    #[serde(default)]
+    pub last_size: u64,
+    #[serde(default)]
+    pub total_volume: u64,
+    #[serde(default)]
+    pub open_interest: u64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub net_change: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub volatility: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub delta: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub gamma: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub theta: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub vega: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub rho: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub time_value: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub theoretical_option_value: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub theoretical_volatility: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub intrinsic_value: f64,
+    #[serde(default, deserialize_with = "parse_f64")]
+    pub extrinsic_value: f64,
+    pub days_to_expiration: i64,
+    pub expiration_type: Option<String>,
+    pub settlement_type: Option<String>,
+    #[serde(default)]
+    pub multiplier: f64,
+    #[serde(default)]
+    pub in_the_money: bool,
+    #[serde(default)]
+    pub non_standard: bool,
+    #[serde(
+        default,
+        rename = "quoteTimeInLong",
+        deserialize_with = "util::time::parse_timestamp_opt"
+    )]
+    pub quote_time: Option<DateTime<Local>>,
+    #[serde(
+        default,
+        rename = "tradeTimeInLong",
+        deserialize_with = "util::time::parse_timestamp_opt"
+    )]
+    pub trade_time: Option<DateTime<Local>>,
+}
+
+type ExpDateMap = HashMap<String, HashMap<String, Vec<OptionContract>>>;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawOptionChain {
+    symbol: String,
+    status: String,
+    underlying: Option<Underlying>,
+    strategy: String,
+    #[serde(default)]
+    interval: f64,
+    #[serde(default)]
+    is_delayed: bool,
+    #[serde(default)]
+    is_index: bool,
+    #[serde(default)]
+    interest_rate: f64,
+    #[serde(default)]
+    underlying_price: f64,
+    #[serde(default)]
+    volatility: f64,
+    #[serde(default)]
+    days_to_expiration: f64,
+    #[serde(default)]
+    call_exp_date_map: ExpDateMap,
+    #[serde(default)]
+    put_exp_date_map: ExpDateMap,
+}
+
+impl From<RawOptionChain> for OptionChain {
+    fn from(raw: RawOptionChain) -> Self {
+        // Maps are keyed by "yyyy-MM-dd:dte" and then by strike
+        fn _flatten(map: ExpDateMap) -> Vec<OptionContract> {
+            let mut contracts = Vec::new();
+            for (exp_key, strikes) in map {
+                let expiration = exp_key
+                    .split(':')
+                    .next()
+                    .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                    .unwrap_or_default();
+                for contract in strikes.into_values().flatten() {
+                    contracts.push(OptionContract {
+                        expiration,
+                        ..contract
+                    });
+                }
+            }
+            contracts.sort_by(|c1, c2| {
+                c1.expiration
+                    .cmp(&c2.expiration)
+                    .then(c1.strike_price.total_cmp(&c2.strike_price))
+            });
+            contracts
+        }
+
+        Self {
+            symbol: raw.symbol,
+            status: raw.status,
+            underlying: raw.underlying,
+            strategy: raw.strategy,
+            interval: raw.interval,
+            is_delayed: raw.is_delayed,
+            is_index: raw.is_index,
+            interest_rate: raw.interest_rate,
+            underlying_price: raw.underlying_price,
+            volatility: raw.volatility,
+            days_to_expiration: raw.days_to_expiration,
+            calls: _flatten(raw.call_exp_date_map),
+            puts: _flatten(raw.put_exp_date_map),
+        }
+    }
+}
+
+impl OptionChain {
+    pub fn expirations(&self) -> Vec<NaiveDate> {
+        let mut expirations = self
+            .calls
+            .iter()
+            .chain(&self.puts)
+            .map(|c| c.expiration)
+            .collect::<Vec<_>>();
+        expirations.sort();
+        expirations.dedup();
+        expirations
+    }
+
+    pub fn contracts(
+        &self,
+        expiration: NaiveDate,
+        put_call: PutCall,
+    ) -> impl Iterator<Item = &OptionContract> {
+        let contracts = match put_call {
+            PutCall::Call => &self.calls,
+            PutCall::Put => &self.puts,
+        };
+        contracts.iter().filter(move |c| c.expiration == expiration)
+    }
+}
+
+impl OptionChainParams {
+    pub(crate) fn to_params(&self, symbol: &str) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("symbol", symbol.to_uppercase()),
+            ("contractType", self.contract_type.to_string()),
+            (
+                "includeUnderlyingQuote",
+                self.include_underlying_quote.to_string(),
+            ),
+            ("strategy", self.strategy.to_string()),
+            ("range", self.range.to_string()),
+        ];
+        if let Some(strike_count) = self.strike_count {
+            params.push(("strikeCount", strike_count.to_string()));
+        }
+        if let Some(interval) = self.interval {
+            params.push(("interval", interval.to_string()));
+        }
+        if let Some(strike) = self.strike {
+            params.push(("strike", strike.to_string()));
+        }
+        if let Some(from_date) = self.from_date {
+            params.push(("fromDate", from_date.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(to_date) = self.to_date {
+            params.push(("toDate", to_date.format("%Y-%m-%d").to_string()));
+        }
+        if let Some(exp_month) = self.exp_month {
+            params.push(("expMonth", exp_month.name()[..3].to_uppercase()));
+        }
+        if let OptionStrategy::Analytical {
+            volatility,
+            underlying_price,
+            interest_rate,
+            days_to_expiration,
+        } = &self.strategy
+        {
+            let analytics = [
+                ("volatility", volatility.map(|v| v.to_string())),
+                ("underlyingPrice", underlying_price.map(|p| p.to_string())),
+                ("interestRate", interest_rate.map(|r| r.to_string())),
+                (
+                    "daysToExpiration",
+                    days_to_expiration.map(|d| d.to_string()),
+                ),
+            ];
+            for (name, value) in analytics {
+                if let Some(value) = value {
+                    params.push((name, value));
+                }
+            }
+        }
+        params
+    }
+}
+
+impl Display for ContractType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ContractType::Call => "CALL",
+            ContractType::Put => "PUT",
+            ContractType::All => "ALL",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Display for StrikeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            StrikeRange::InTheMoney => "ITM",
+            StrikeRange::NearTheMoney => "NTM",
+            StrikeRange::OutOfTheMoney => "OTM",
+            StrikeRange::StrikesAboveMarket => "SAK",
+            StrikeRange::StrikesBelowMarket => "SBK",
+            StrikeRange::StrikesNearMarket => "SNK",
+            StrikeRange::All => "ALL",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Display for OptionStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OptionStrategy::Single => "SINGLE",
+            OptionStrategy::Analytical { .. } => "ANALYTICAL",
+            OptionStrategy::Covered => "COVERED",
+            OptionStrategy::Vertical => "VERTICAL",
+            OptionStrategy::Calendar => "CALENDAR",
+            OptionStrategy::Strangle => "STRANGLE",
+            OptionStrategy::Straddle => "STRADDLE",
+            OptionStrategy::Butterfly => "BUTTERFLY",
+            OptionStrategy::Condor => "CONDOR",
+            OptionStrategy::Diagonal => "DIAGONAL",
+            OptionStrategy::Collar => "COLLAR",
+            OptionStrategy::Roll => "ROLL",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Schwab sends unavailable values either as "NaN" strings or as -999
+fn parse_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let value = match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+        Value::String(s) => s.parse().unwrap_or(f64::NAN),
+        _ => f64::NAN,
+    };
+    Ok(if value == -999.0 { f64::NAN } else { value })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OptionChain, PutCall};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse_option_chain() -> anyhow::Result<()> {
+        let contract = |put_call: &str, strike: f64, delta: &str| {
+            format!(
+                r#"[{{"putCall": "{put_call}", "symbol": "SPXW  250703{}{:08}", "description": "SPXW 07/03/2025 {strike} {put_call}", "strikePrice": {strike},
+                "bid": 1.2, "ask": 1.4, "last": 1.3, "mark": 1.3, "bidSize": 10, "askSize": 12, "lastSize": 1,
+                "totalVolume": 1500, "openInterest": 2300, "netChange": -0.5, "volatility": 14.2,
+                "delta": {delta}, "gamma": 0.01, "theta": -0.8, "vega": 0.1, "rho": 0.0,
+                "timeValue": 1.3, "theoreticalOptionValue": 1.31, "theoreticalVolatility": 29.0,
+                "intrinsicValue": 0.0, "extrinsicValue": 1.3, "daysToExpiration": 0,
+                "expirationType": "W", "settlementType": "P", "multiplier": 100.0, "inTheMoney": false,
+                "nonStandard": false, "quoteTimeInLong": 1751558400000, "tradeTimeInLong": 1751558400000}}]"#,
+                &put_call[..1],
+                (strike * 1000.0) as u64
+            )
+        };
+        let json = format!(
+            r#"{{"symbol": "$SPX", "status": "SUCCESS", "strategy": "SINGLE", "interval": 0.0,
+            "isDelayed": false, "isIndex": true, "interestRate": 4.5, "underlyingPrice": 6250.0,
+            "volatility": 29.0, "daysToExpiration": 0.0, "numberOfContracts": 3,
+            "callExpDateMap": {{"2025-07-03:0": {{"6300.0": {}, "6275.0": {}}}}},
+            "putExpDateMap": {{"2025-07-03:0": {{"6200.0": {}}}}}}}"#,
+            contract("CALL", 6300.0, "0.12"),
+            contract("CALL", 6275.0, "\"NaN\""),
+            contract("PUT", 6200.0, "-999.0"),
+        );
+
+        let chain = serde_json::from_str::<OptionChain>(&json)?;
+        let expiration = NaiveDate::from_ymd_opt(2025, 7, 3).unwrap();
+        assert_eq!(chain.expirations(), vec![expiration]);
+        assert_eq!(chain.calls.len(), 2);
+        assert_eq!(chain.calls[0].strike_price, 6275.0);
+        assert!(chain.calls[0].delta.is_nan());
+        assert_eq!(chain.calls[1].delta, 0.12);
+        assert_eq!(chain.calls[1].open_interest, 2300);
+
+        let puts = chain
+            .contracts(expiration, PutCall::Put)
+            .collect::<Vec<_>>();
+        assert_eq!(puts.len(), 1);
+        assert_eq!(puts[0].put_call, PutCall::Put);
+        assert!(puts[0].delta.is_nan());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Level 2 snapshot of a book service, both sides ordered from the best price outwards.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub venue: String,
+    pub time: DateTime<Local>,
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+}
+
+/// A price of the book with the size resting at it, aggregated across the market participants.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BookLevel {
+    pub price: f64,
+    pub size: u64,
+    pub entries: Vec<BookEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BookEntry {
+    pub mpid: String,
+    pub size: u64,
+}
+
+impl OrderBook {
+    /// Parses the content of NASDAQ_BOOK, NYSE_BOOK or OPTIONS_BOOK: "1" is the book time,
+    /// "2" and "3" the bid and ask levels, each with "0" price, "1" size and "3" participants.
+    pub(crate) fn from_stream(venue: &str, value: &Value) -> Option<Self> {
+        let time = value.get("1").and_then(Value::as_i64)?;
+        let mut bids = parse_levels(value.get("2"));
+        let mut asks = parse_levels(value.get("3"));
+        bids.sort_by(|l1, l2| l2.price.total_cmp(&l1.price));
+        asks.sort_by(|l1, l2| l1.price.total_cmp(&l2.price));
+        Some(Self {
+            venue: venue.to_owned(),
+            time: util::time::from_ts(time / 1000),
+            bids,
+            asks,
+        })
+    }
+
+    pub fn best_bid(&self) -> Option<&BookLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&BookLevel> {
+        self.asks.first()
+    }
+
+    /// Keeps `depth` levels on each side, the rest is rarely worth shipping to the UI.
+    pub fn truncated(&self, depth: usize) -> Self {
+        Self {
+            venue: self.venue.clone(),
+            time: self.time,
+            bids: self.bids.iter().take(depth).cloned().collect(),
+            asks: self.asks.iter().take(depth).cloned().collect(),
+        }
+    }
+}
+
+fn parse_levels(value: Option<&Value>) -> Vec<BookLevel> {
+    let Some(levels) = value.and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    levels
+        .iter()
+        .filter_map(|level| {
+            let entries = level
+                .get("3")
+                .and_then(Value::as_array)
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .filter_map(|entry| {
+                            Some(BookEntry {
+                                mpid: entry.get("0").and_then(Value::as_str)?.to_owned(),
+                                size: entry.get("1").and_then(Value::as_u64)?,
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(BookLevel {
+                price: level.get("0").and_then(Value::as_f64)?,
+                size: level.get("1").and_then(Value::as_u64)?,
+                entries,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::OrderBook;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_book() {
+        let value = json!({
+            "key": "NVDA",
+            "1": 1751378400000i64,
+            "2": [
+                {"0": 104.4, "1": 300, "2": 2, "3": [{"0": "NSDQ", "1": 200}, {"0": "ARCX", "1": 100}]},
+                {"0": 104.5, "1": 100, "2": 1, "3": [{"0": "NSDQ", "1": 100}]}
+            ],
+            "3": [
+                {"0": 104.7, "1": 500, "2": 1, "3": [{"0": "EDGX", "1": 500}]},
+                {"0": 104.6, "1": 200, "2": 1}
+            ]
+        });
+        let book = OrderBook::from_stream("NASDAQ_BOOK", &value).unwrap();
+        assert_eq!(book.best_bid().unwrap().price, 104.5);
+        assert_eq!(book.best_ask().unwrap().price, 104.6);
+        assert_eq!(book.bids[1].entries.len(), 2);
+        assert!(book.asks[0].entries.is_empty());
+        assert_eq!(book.truncated(1).asks.len(), 1);
+    }
+}
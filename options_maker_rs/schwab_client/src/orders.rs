@@ -0,0 +1,630 @@
+use crate::api_client::ApiClient;
+use crate::auth::Authenticator;
+use crate::{SchwabError, SchwabResult};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use util::http::{Response, header};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Session {
+    Normal,
+    Am,
+    Pm,
+    Seamless,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderDuration {
+    Day,
+    GoodTillCancel,
+    FillOrKill,
+    ImmediateOrCancel,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderType {
+    Market,
+    Limit,
+    Stop,
+    StopLimit,
+    NetDebit,
+    NetCredit,
+    NetZero,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ComplexOrderStrategyType {
+    None,
+    Vertical,
+    IronCondor,
+    Custom,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStrategyType {
+    Single,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Instruction {
+    Buy,
+    Sell,
+    SellShort,
+    BuyToCover,
+    BuyToOpen,
+    BuyToClose,
+    SellToOpen,
+    SellToClose,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderAssetType {
+    Equity,
+    Option,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRequest {
+    pub session: Session,
+    pub duration: OrderDuration,
+    pub order_type: OrderType,
+    pub complex_order_strategy_type: ComplexOrderStrategyType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_price: Option<f64>,
+    pub order_strategy_type: OrderStrategyType,
+    pub order_leg_collection: Vec<OrderLeg>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderLeg {
+    pub instruction: Instruction,
+    pub quantity: u32,
+    pub instrument: OrderInstrument,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderInstrument {
+    pub symbol: String,
+    pub asset_type: OrderAssetType,
+}
+
+/// Order as reported by Schwab, enums are kept as strings since the API returns far more
+/// variants than we ever place.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub order_id: i64,
+    pub account_number: Option<serde_json::Value>,
+    pub status: String,
+    pub session: String,
+    pub duration: String,
+    pub order_type: String,
+    pub complex_order_strategy_type: Option<String>,
+    pub quantity: f64,
+    #[serde(default)]
+    pub filled_quantity: f64,
+    #[serde(default)]
+    pub remaining_quantity: f64,
+    pub price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub entered_time: Option<String>,
+    pub close_time: Option<String>,
+    #[serde(default)]
+    pub cancelable: bool,
+    #[serde(default)]
+    pub editable: bool,
+    #[serde(default)]
+    pub order_leg_collection: Vec<OrderLegStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderLegStatus {
+    pub instruction: String,
+    pub quantity: f64,
+    pub instrument: OrderLegInstrument,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderLegInstrument {
+    pub symbol: String,
+    pub asset_type: String,
+}
+
+/// Builds single-leg equity/option orders and the multi-leg option spreads. Orders default to
+/// market orders for the day in the regular session.
+#[derive(Debug, Clone)]
+pub struct OrderBuilder {
+    request: OrderRequest,
+}
+
+impl OrderBuilder {
+    fn new(complex_type: ComplexOrderStrategyType, legs: Vec<OrderLeg>) -> Self {
+        Self {
+            request: OrderRequest {
+                session: Session::Normal,
+                duration: OrderDuration::Day,
+                order_type: OrderType::Market,
+                complex_order_strategy_type: complex_type,
+                price: None,
+                stop_price: None,
+                order_strategy_type: OrderStrategyType::Single,
+                order_leg_collection: legs,
+            },
+        }
+    }
+
+    pub fn equity(instruction: Instruction, symbol: impl Into<String>, quantity: u32) -> Self {
+        Self::new(
+            ComplexOrderStrategyType::None,
+            vec![OrderLeg::new(
+                instruction,
+                symbol,
+                OrderAssetType::Equity,
+                quantity,
+            )],
+        )
+    }
+
+    pub fn option(instruction: Instruction, contract: impl Into<String>, quantity: u32) -> Self {
+        Self::new(
+            ComplexOrderStrategyType::None,
+            vec![OrderLeg::new(
+                instruction,
+                contract,
+                OrderAssetType::Option,
+                quantity,
+            )],
+        )
+    }
+
+    /// Vertical spread buying `long_contract` and selling `short_contract`, opening or closing
+    /// the position. Needs a net debit or credit price.
+    pub fn vertical(
+        long_contract: impl Into<String>,
+        short_contract: impl Into<String>,
+        quantity: u32,
+        open: bool,
+    ) -> Self {
+        let (long, short) = leg_instructions(open);
+        Self::new(
+            ComplexOrderStrategyType::Vertical,
+            vec![
+                OrderLeg::new(long, long_contract, OrderAssetType::Option, quantity),
+                OrderLeg::new(short, short_contract, OrderAssetType::Option, quantity),
+            ],
+        )
+    }
+
+    /// Short iron condor: long put < short put < short call < long call. Closing the position
+    /// reverses every leg.
+    pub fn iron_condor(
+        long_put: impl Into<String>,
+        short_put: impl Into<String>,
+        short_call: impl Into<String>,
+        long_call: impl Into<String>,
+        quantity: u32,
+        open: bool,
+    ) -> Self {
+        let (long, short) = leg_instructions(open);
+        Self::new(
+            ComplexOrderStrategyType::IronCondor,
+            vec![
+                OrderLeg::new(long, long_put, OrderAssetType::Option, quantity),
+                OrderLeg::new(short, short_put, OrderAssetType::Option, quantity),
+                OrderLeg::new(short, short_call, OrderAssetType::Option, quantity),
+                OrderLeg::new(long, long_call, OrderAssetType::Option, quantity),
+            ],
+        )
+    }
+
+    pub fn limit(mut self, price: f64) -> Self {
+        self.request.order_type = OrderType::Limit;
+        self.request.price = Some(price);
+        self
+    }
+
+    pub fn stop(mut self, stop_price: f64) -> Self {
+        self.request.order_type = OrderType::Stop;
+        self.request.stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn stop_limit(mut self, stop_price: f64, price: f64) -> Self {
+        self.request.order_type = OrderType::StopLimit;
+        self.request.stop_price = Some(stop_price);
+        self.request.price = Some(price);
+        self
+    }
+
+    pub fn net_debit(mut self, price: f64) -> Self {
+        self.request.order_type = OrderType::NetDebit;
+        self.request.price = Some(price);
+        self
+    }
+
+    pub fn net_credit(mut self, price: f64) -> Self {
+        self.request.order_type = OrderType::NetCredit;
+        self.request.price = Some(price);
+        self
+    }
+
+    pub fn duration(mut self, duration: OrderDuration) -> Self {
+        self.request.duration = duration;
+        self
+    }
+
+    pub fn session(mut self, session: Session) -> Self {
+        self.request.session = session;
+        self
+    }
+
+    pub fn build(self) -> SchwabResult<OrderRequest> {
+        let request = self.request;
+        if request
+            .order_leg_collection
+            .iter()
+            .any(|leg| leg.quantity == 0)
+        {
+            return Err(SchwabError::Unexpected(
+                "Order quantity must be positive".to_owned(),
+            ));
+        }
+        if let Some(price) = request.price.or(request.stop_price)
+            && (!price.is_finite() || price < 0.0)
+        {
+            return Err(SchwabError::Unexpected(format!(
+                "Invalid order price {price}"
+            )));
+        }
+        let is_spread = request.order_leg_collection.len() > 1;
+        let is_net = matches!(
+            request.order_type,
+            OrderType::NetDebit | OrderType::NetCredit | OrderType::NetZero
+        );
+        if is_spread != is_net {
+            return Err(SchwabError::Unexpected(format!(
+                "{:?} order can't be placed with {} legs",
+                request.order_type,
+                request.order_leg_collection.len()
+            )));
+        }
+        Ok(request)
+    }
+}
+
+impl OrderLeg {
+    fn new(
+        instruction: Instruction,
+        symbol: impl Into<String>,
+        asset_type: OrderAssetType,
+        quantity: u32,
+    ) -> Self {
+        Self {
+            instruction,
+            quantity,
+            instrument: OrderInstrument {
+                symbol: symbol.into(),
+                asset_type,
+            },
+        }
+    }
+}
+
+/// Instructions of the long and the short legs of a spread.
+fn leg_instructions(open: bool) -> (Instruction, Instruction) {
+    if open {
+        (Instruction::BuyToOpen, Instruction::SellToOpen)
+    } else {
+        (Instruction::SellToClose, Instruction::BuyToClose)
+    }
+}
+
+pub(crate) async fn place_order(
+    api: &ApiClient,
+    auth: &Authenticator,
+    account_hash: &str,
+    order: &OrderRequest,
+) -> SchwabResult<i64> {
+    let response = api
+        .post(format!("/trader/v1/accounts/{account_hash}/orders"))
+        .authorized(auth)
+        .json(order)
+        .send()
+        .await?;
+    order_id_from_location(response, "place order").await
+}
+
+pub(crate) async fn replace_order(
+    api: &ApiClient,
+    auth: &Authenticator,
+    account_hash: &str,
+    order_id: i64,
+    order: &OrderRequest,
+) -> SchwabResult<i64> {
+    let response = api
+        .put(format!(
+            "/trader/v1/accounts/{account_hash}/orders/{order_id}"
+        ))
+        .authorized(auth)
+        .json(order)
+        .send()
+        .await?;
+    order_id_from_location(response, "replace order").await
+}
+
+pub(crate) async fn cancel_order(
+    api: &ApiClient,
+    auth: &Authenticator,
+    account_hash: &str,
+    order_id: i64,
+) -> SchwabResult<()> {
+    let response = api
+        .delete(format!(
+            "/trader/v1/accounts/{account_hash}/orders/{order_id}"
+        ))
+        .authorized(auth)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(SchwabError::ApiError(
+            response.status().as_u16(),
+            format!("Failed to cancel order: {}", response.text().await?),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) async fn get_orders(
+    api: &ApiClient,
+    auth: &Authenticator,
+    account_hash: &str,
+    (from, to): (DateTime<Local>, DateTime<Local>),
+    status: Option<&str>,
+) -> SchwabResult<Vec<Order>> {
+    let format_time = |time: DateTime<Local>| {
+        time.with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+    };
+    let mut params = vec![
+        ("fromEnteredTime", format_time(from)),
+        ("toEnteredTime", format_time(to)),
+    ];
+    if let Some(status) = status {
+        params.push(("status", status.to_owned()));
+    }
+    let response = api
+        .get(format!("/trader/v1/accounts/{account_hash}/orders"))
+        .authorized(auth)
+        .query(&params)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(SchwabError::ApiError(
+            response.status().as_u16(),
+            format!("Failed to get orders: {}", response.text().await?),
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+/// Schwab doesn't return a body for new orders, the order id is the tail of the `Location` header.
+async fn order_id_from_location(response: Response, action: &str) -> SchwabResult<i64> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(SchwabError::ApiError(
+            status.as_u16(),
+            format!("Failed to {action}: {}", response.text().await?),
+        ));
+    }
+    response
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .and_then(|location| location.rsplit('/').next())
+        .and_then(|order_id| order_id.parse().ok())
+        .ok_or_else(|| SchwabError::Unexpected(format!("No order id in the response to {action}")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use app_config::SchwabApiConfig;
+    use axum::Router;
+    use axum::extract::{Path, Query};
+    use axum::http::HeaderMap;
+    use axum::http::StatusCode;
+    use axum::routing::{delete, post, put};
+    use serde_json::{Value, json};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_build_orders() {
+        let order = OrderBuilder::iron_condor("P90", "P95", "C105", "C110", 2, true)
+            .net_credit(1.25)
+            .build()
+            .unwrap();
+        let value = serde_json::to_value(&order).unwrap();
+        assert_eq!(value["orderType"], "NET_CREDIT");
+        assert_eq!(value["complexOrderStrategyType"], "IRON_CONDOR");
+        assert_eq!(value["price"], 1.25);
+        assert_eq!(value["orderLegCollection"][0]["instruction"], "BUY_TO_OPEN");
+        assert_eq!(
+            value["orderLegCollection"][1]["instruction"],
+            "SELL_TO_OPEN"
+        );
+        assert_eq!(
+            value["orderLegCollection"][3]["instrument"]["symbol"],
+            "C110"
+        );
+        assert_eq!(
+            value["orderLegCollection"][3]["instrument"]["assetType"],
+            "OPTION"
+        );
+
+        let order = OrderBuilder::vertical("C100", "C105", 1, false)
+            .net_debit(0.5)
+            .build()
+            .unwrap();
+        assert_eq!(
+            order.order_leg_collection[0].instruction,
+            Instruction::SellToClose
+        );
+        assert_eq!(
+            order.order_leg_collection[1].instruction,
+            Instruction::BuyToClose
+        );
+
+        let value = serde_json::to_value(
+            OrderBuilder::equity(Instruction::Buy, "AAPL", 10)
+                .limit(190.5)
+                .duration(OrderDuration::GoodTillCancel)
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(value["orderType"], "LIMIT");
+        assert_eq!(value["duration"], "GOOD_TILL_CANCEL");
+        assert!(value.get("stopPrice").is_none());
+
+        assert!(
+            OrderBuilder::vertical("C100", "C105", 1, true)
+                .build()
+                .is_err()
+        );
+        assert!(
+            OrderBuilder::equity(Instruction::Buy, "AAPL", 1)
+                .net_credit(1.0)
+                .build()
+                .is_err()
+        );
+        assert!(
+            OrderBuilder::equity(Instruction::Buy, "AAPL", 0)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_order_endpoints() -> anyhow::Result<()> {
+        async fn place(
+            Path(hash): Path<String>,
+            headers: HeaderMap,
+            axum::Json(body): axum::Json<Value>,
+        ) -> (StatusCode, [(header::HeaderName, String); 1]) {
+            assert_eq!(headers[header::AUTHORIZATION], "Bearer token");
+            assert_eq!(body["orderLegCollection"][0]["instrument"]["symbol"], "SPY");
+            (
+                StatusCode::CREATED,
+                [(
+                    header::LOCATION,
+                    format!("https://localhost/trader/v1/accounts/{hash}/orders/1001"),
+                )],
+            )
+        }
+
+        async fn replace(
+            Path((hash, order_id)): Path<(String, i64)>,
+        ) -> (StatusCode, [(header::HeaderName, String); 1]) {
+            assert_eq!(order_id, 1001);
+            (
+                StatusCode::CREATED,
+                [(
+                    header::LOCATION,
+                    format!("https://localhost/trader/v1/accounts/{hash}/orders/1002"),
+                )],
+            )
+        }
+
+        async fn list(Query(params): Query<HashMap<String, String>>) -> axum::Json<Value> {
+            assert!(params["fromEnteredTime"].ends_with('Z'));
+            assert_eq!(params["status"], "WORKING");
+            axum::Json(json!([{
+                "orderId": 1002,
+                "accountNumber": 12345678,
+                "status": "WORKING",
+                "session": "NORMAL",
+                "duration": "DAY",
+                "orderType": "LIMIT",
+                "complexOrderStrategyType": "NONE",
+                "quantity": 5.0,
+                "filledQuantity": 0.0,
+                "remainingQuantity": 5.0,
+                "price": 400.0,
+                "enteredTime": "2025-01-02T15:00:00+0000",
+                "cancelable": true,
+                "editable": true,
+                "orderLegCollection": [{
+                    "orderLegType": "EQUITY",
+                    "legId": 1,
+                    "instruction": "BUY",
+                    "positionEffect": "OPENING",
+                    "quantity": 5.0,
+                    "instrument": { "assetType": "EQUITY", "cusip": "78462F103", "symbol": "SPY" }
+                }]
+            }]))
+        }
+
+        let router = Router::new()
+            .route("/trader/v1/accounts/{hash}/orders", post(place).get(list))
+            .route(
+                "/trader/v1/accounts/{hash}/orders/{order_id}",
+                put(replace).merge(delete(async || StatusCode::OK)),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let api = ApiClient::new(&SchwabApiConfig {
+            url: format!("http://{}", listener.local_addr()?),
+            ..SchwabApiConfig::default()
+        })?;
+        let credentials = crate::Credentials {
+            client_id: "client-id".to_owned(),
+            client_secret: "client-secret".to_owned(),
+            trading: true,
+        };
+        let refresh_token = crate::auth::RefreshToken {
+            refresh_token: "refresh-token".to_owned(),
+            token_type: "Bearer".to_owned(),
+            scope: "api".to_owned(),
+            expires_at: util::time::now() + chrono::Duration::days(7),
+        };
+        let auth = Authenticator::new(credentials, refresh_token, "token", 1800);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let order = OrderBuilder::equity(Instruction::Buy, "SPY", 5)
+            .limit(400.0)
+            .build()?;
+        let order_id = place_order(&api, &auth, "hash", &order).await?;
+        assert_eq!(order_id, 1001);
+        let order_id = replace_order(&api, &auth, "hash", order_id, &order).await?;
+        assert_eq!(order_id, 1002);
+
+        let now = util::time::now();
+        let orders = get_orders(
+            &api,
+            &auth,
+            "hash",
+            (now - chrono::Duration::days(1), now),
+            Some("WORKING"),
+        )
+        .await?;
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].order_id, 1002);
+        assert_eq!(orders[0].order_leg_collection[0].instrument.symbol, "SPY");
+
+        cancel_order(&api, &auth, "hash", order_id).await?;
+        Ok(())
+    }
+}
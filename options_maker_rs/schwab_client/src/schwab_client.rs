@@ -1,42 +1,31 @@
-use super::{API_URL, Account, Instrument, Quote, SchwabError};
+use super::{Account, Instrument, OptionChain, OptionChainParams, Quote, SchwabError};
 use super::{Candle, SchwabResult};
-use app_config::APP_CONFIG;
-use chrono::{DateTime, Duration, Local};
+use app_config::{APP_CONFIG, SchwabApiConfig};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveTime};
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
-use tokio::fs;
 use tracing::{debug, info, warn};
-use util::http::HTTP_CLIENT;
+use util::calendar::MarketDay;
 use util::time;
 
-use crate::auth;
+use crate::api_client::ApiClient;
+use crate::auth::{self, Authenticator, Credentials, RefreshToken};
 use crate::streaming_client::StreamingClient;
+use crate::token_store::TokenStore;
 
 pub struct SchwabClient {
-    refresh_token: RefreshToken,
-    access_token: Arc<RwLock<AccessToken>>,
+    api: ApiClient,
+    auth: Arc<Authenticator>,
+    /// Where a new login persists its refresh token, if anywhere.
+    token_store: Option<TokenStore>,
     is_active: Arc<AtomicBool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct RefreshToken {
-    refresh_token: String,
-    token_type: String,
-    scope: String,
-    expires_at: DateTime<Local>,
-}
-
-#[derive(Debug)]
-pub(crate) struct AccessToken {
-    pub access_token: String,
-    pub expires_at: DateTime<Local>,
-}
-
 #[derive(Debug, Clone)]
 pub enum Frequency {
     Minute(u32), // 1, 5, 10, 15, 30
@@ -64,69 +53,101 @@ pub enum SearchProjection {
 }
 
 impl SchwabClient {
+    /// Creates the client off the refresh token of the token file, without a valid one it fails
+    /// with [SchwabError::LoginRequired] and the first login goes through [Self::init_with_code].
     pub async fn init() -> SchwabResult<Self> {
-        let token_file = &APP_CONFIG.token_file;
-        if fs::try_exists(token_file).await? {
+        let api = ApiClient::new(&APP_CONFIG.schwab_api)?;
+        let credentials = Credentials::from_config();
+        let token_store =
+            TokenStore::init(&APP_CONFIG.token_file, APP_CONFIG.token_key_file.as_deref()).await?;
+        if let Some(refresh_token) = token_store.load().await? {
             info!("Refresh token exists, let's check if its not expired yet");
-            let refresh_token =
-                serde_json::from_str::<RefreshToken>(&fs::read_to_string(token_file).await?)
-                    .map_err(|e| SchwabError::IoError(e.into()))?;
-            if time::now() + Duration::days(1) <= refresh_token.expires_at {
+            if time::now() + Duration::minutes(5) <= refresh_token.expires_at {
                 info!(
                     "Refresh token will expire on {}, fetching new access token",
                     refresh_token.expires_at
                 );
-                let (access_token, expires_in) =
-                    auth::fetch_access_token(&refresh_token.refresh_token)
-                        .await
-                        .map_err(SchwabError::AuthError)?;
-                let expires_at = time::now() + Duration::seconds(expires_in);
-                info!("Fetched new access token, expires at {expires_at}");
-                let client = SchwabClient {
-                    refresh_token,
-                    access_token: Arc::new(RwLock::new(AccessToken {
-                        access_token,
-                        expires_at,
-                    })),
-                    is_active: Arc::new(AtomicBool::new(true)),
-                };
-                client.schedule_token_refresh();
+                let mut client = Self::with_refresh_token(api, credentials, refresh_token).await?;
+                client.token_store = Some(token_store);
                 return Ok(client);
             } else {
                 warn!(
-                    "Refresh token is close to expiry ({}), requires reauth",
+                    "Refresh token has expired ({}), requires reauth",
                     refresh_token.expires_at
                 );
             }
         } else {
             info!("Refresh token doesn't exist, requires auth");
         };
+        Err(SchwabError::LoginRequired)
+    }
+
+    /// Schwab's login page for when [Self::init] finds no valid refresh token, it redirects to
+    /// `redirect_uri` with the code for [Self::init_with_code] and the given `state`.
+    pub fn first_login_url(redirect_uri: &str, state: &str) -> SchwabResult<String> {
+        let api = ApiClient::new(&APP_CONFIG.schwab_api)?;
+        auth::authorize_url(&api, &Credentials::from_config(), redirect_uri, Some(state))
+            .map(String::from)
+            .map_err(SchwabError::AuthError)
+    }
 
-        let token = auth::init_auth().await.map_err(SchwabError::AuthError)?;
+    /// Creates the client off the first login, the refresh token is persisted to the token file.
+    pub async fn init_with_code(code: &str, redirect_uri: &str) -> SchwabResult<Self> {
+        let api = ApiClient::new(&APP_CONFIG.schwab_api)?;
+        let credentials = Credentials::from_config();
+        let token_store =
+            TokenStore::init(&APP_CONFIG.token_file, APP_CONFIG.token_key_file.as_deref()).await?;
+        let token = auth::exchange_tokens(&api, &credentials, code, redirect_uri)
+            .await
+            .map_err(SchwabError::AuthError)?;
         info!("Authenticated Successfully");
+        let refresh_token = RefreshToken::issued(&token);
+        token_store.save(&refresh_token).await?;
+
+        let auth = Authenticator::new(
+            credentials,
+            refresh_token,
+            token.access_token,
+            token.expires_in,
+        );
+        let client = SchwabClient {
+            api,
+            auth: Arc::new(auth),
+            token_store: Some(token_store),
+            is_active: Arc::new(AtomicBool::new(true)),
+        };
+        client.schedule_token_refresh();
+        Ok(client)
+    }
+
+    /// Creates a client off an existing refresh token without touching the token file, e.g. to
+    /// run against `schwab_mock`.
+    pub async fn from_refresh_token(
+        config: &SchwabApiConfig,
+        credentials: Credentials,
+        refresh_token: impl Into<String>,
+    ) -> SchwabResult<Self> {
         let refresh_token = RefreshToken {
-            refresh_token: token.refresh_token,
-            token_type: token.token_type,
-            scope: token.scope,
+            refresh_token: refresh_token.into(),
+            token_type: String::from("Bearer"),
+            scope: credentials.scope().to_owned(),
             expires_at: time::now() + Duration::days(7),
         };
-        fs::write(
-            token_file,
-            serde_json::to_string_pretty(&refresh_token)
-                .map_err(|e| SchwabError::IoError(e.into()))?,
-        )
-        .await?;
-        info!(
-            "Refresh token saved to {:?}",
-            fs::canonicalize(token_file).await?
-        );
+        Self::with_refresh_token(ApiClient::new(config)?, credentials, refresh_token).await
+    }
 
+    async fn with_refresh_token(
+        api: ApiClient,
+        credentials: Credentials,
+        refresh_token: RefreshToken,
+    ) -> SchwabResult<Self> {
+        let auth = Authenticator::login(&api, credentials, refresh_token)
+            .await
+            .map_err(SchwabError::AuthError)?;
         let client = SchwabClient {
-            refresh_token,
-            access_token: Arc::new(RwLock::new(AccessToken {
-                access_token: token.access_token,
-                expires_at: time::now() + Duration::seconds(token.expires_in),
-            })),
+            api,
+            auth: Arc::new(auth),
+            token_store: None,
             is_active: Arc::new(AtomicBool::new(true)),
         };
         client.schedule_token_refresh();
@@ -134,47 +155,60 @@ impl SchwabClient {
     }
 
     fn schedule_token_refresh(&self) {
-        let refresh_token = self.refresh_token.clone();
-        let access_token = self.access_token.clone();
+        let api = self.api.clone();
+        let auth = self.auth.clone();
         let is_active = self.is_active.clone();
         tokio::spawn(async move {
             while is_active.load(Ordering::Relaxed) {
                 let one_min = std::time::Duration::from_secs(60);
                 tokio::time::sleep(one_min).await;
 
-                if access_token.read().unwrap().expires_at >= time::now() + (5 * one_min) {
+                if auth.expires_at() >= time::now() + (5 * one_min) {
                     continue;
                 }
-                if refresh_token.expires_at <= time::now() {
-                    panic!("Refresh token has expired, app needs to restart");
+                if auth.refresh_token_expires_at() <= time::now() {
+                    debug!("Refresh token has expired, waiting for a new login");
+                    continue;
                 }
 
                 debug!("Access token is about to expire, let's refresh it");
-                let (new_token, expires_in) =
-                    match auth::fetch_access_token(&refresh_token.refresh_token).await {
-                        Ok(a) => a,
-                        Err(e) => {
-                            warn!("Failed to refresh access token: {e}");
-                            continue;
-                        }
-                    };
-                let expires_at = time::now() + Duration::seconds(expires_in);
-                let mut lock = access_token.write().unwrap();
-                *lock = AccessToken {
-                    access_token: new_token,
-                    expires_at,
-                };
-                debug!("Successfully refreshed access token, expires at {expires_at}");
+                if let Err(e) = auth.refresh(&api, None).await {
+                    warn!("Failed to refresh access token: {e}");
+                }
             }
         });
     }
 
-    fn bearer_token(&self) -> String {
-        self.access_token.read().unwrap().access_token.clone()
+    /// Expiry of the refresh token, the user has to log in again before it.
+    pub fn refresh_token_expires_at(&self) -> DateTime<Local> {
+        self.auth.refresh_token_expires_at()
+    }
+
+    /// Schwab's login page, it redirects to `redirect_uri` with the code for [Self::login] and
+    /// the given `state`.
+    pub fn authorize_url(&self, redirect_uri: &str, state: &str) -> SchwabResult<String> {
+        self.auth
+            .authorize_url(&self.api, redirect_uri, Some(state))
+            .map(String::from)
+            .map_err(SchwabError::AuthError)
+    }
+
+    /// Completes a login started off [Self::authorize_url]. The new tokens are swapped in under
+    /// the running client and streamer, and persisted to the token file.
+    pub async fn login(&self, code: &str, redirect_uri: &str) -> SchwabResult<()> {
+        let refresh_token = self
+            .auth
+            .exchange_code(&self.api, code, redirect_uri)
+            .await
+            .map_err(SchwabError::AuthError)?;
+        if let Some(token_store) = &self.token_store {
+            token_store.save(&refresh_token).await?;
+        }
+        Ok(())
     }
 
     pub async fn create_streaming_client(&self) -> SchwabResult<StreamingClient> {
-        StreamingClient::init(self.access_token.clone(), self.is_active.clone()).await
+        StreamingClient::init(self.api.clone(), self.auth.clone(), self.is_active.clone()).await
     }
 
     pub async fn get_accounts(&self) -> SchwabResult<Vec<Account>> {
@@ -189,9 +223,10 @@ impl SchwabClient {
             securities_account: super::Account,
         }
 
-        let response = HTTP_CLIENT
-            .get(format!("{API_URL}/trader/v1/accounts/accountNumbers"))
-            .bearer_auth(self.bearer_token())
+        let response = self
+            .api
+            .get("/trader/v1/accounts/accountNumbers")
+            .authorized(&self.auth)
             .send()
             .await?;
         if !response.status().is_success() {
@@ -204,12 +239,11 @@ impl SchwabClient {
         let accounts = response.json::<Vec<AccountHash>>().await?;
         let mut result = Vec::with_capacity(accounts.len());
         for account in accounts {
-            let response = HTTP_CLIENT
-                .get(format!(
-                    "{}/trader/v1/accounts/{}",
-                    API_URL, account.hash_value
-                ))
-                .bearer_auth(self.bearer_token())
+            let response = self
+                .api
+                .get(format!("/trader/v1/accounts/{}", account.hash_value))
+                .query(&[("fields", "positions")])
+                .authorized(&self.auth)
                 .send()
                 .await?;
             if !response.status().is_success() {
@@ -233,7 +267,6 @@ impl SchwabClient {
         period: Option<Period>,
         need_extended_hours_data: bool,
     ) -> SchwabResult<Vec<Candle>> {
-        let url = format!("{API_URL}/marketdata/v1/pricehistory");
         let mut query_params = vec![
             ("symbol", symbol.to_uppercase()),
             (
@@ -252,9 +285,10 @@ impl SchwabClient {
             query_params.push(("periodType", period_type));
             query_params.push(("period", period_val));
         }
-        let response = HTTP_CLIENT
-            .get(&url)
-            .bearer_auth(self.bearer_token())
+        let response = self
+            .api
+            .get("/marketdata/v1/pricehistory")
+            .authorized(&self.auth)
             .query(&query_params)
             .send()
             .await?;
@@ -313,14 +347,11 @@ impl SchwabClient {
         symbols: impl IntoIterator<Item = impl Into<String>>,
     ) -> SchwabResult<HashMap<String, Quote>> {
         let symbols_param = symbols.into_iter().map(Into::into).join(",");
-        let url = format!(
-            "{}/marketdata/v1/quotes?symbols={}&fields=quote",
-            API_URL,
-            urlencoding::encode(&symbols_param)
-        );
-        let response = HTTP_CLIENT
-            .get(url)
-            .bearer_auth(self.bearer_token())
+        let response = self
+            .api
+            .get("/marketdata/v1/quotes")
+            .query(&[("symbols", symbols_param.as_str()), ("fields", "quote")])
+            .authorized(&self.auth)
             .send()
             .await?;
         if !response.status().is_success() {
@@ -350,17 +381,51 @@ impl SchwabClient {
         Ok(response)
     }
 
+    pub async fn get_option_chain(
+        &self,
+        symbol: &str,
+        params: &OptionChainParams,
+    ) -> SchwabResult<OptionChain> {
+        let response = self
+            .api
+            .get("/marketdata/v1/chains")
+            .authorized(&self.auth)
+            .query(&params.to_params(symbol))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(SchwabError::ApiError(
+                response.status().as_u16(),
+                format!("Failed to get option chain: {}", response.text().await?),
+            ));
+        }
+
+        let chain = response.json::<OptionChain>().await?;
+        if chain.status != "SUCCESS" {
+            return Err(SchwabError::ApiError(
+                404,
+                format!("Option chain of {symbol} returned {}", chain.status),
+            ));
+        }
+        info!(
+            "Fetched {} calls and {} puts for {symbol}",
+            chain.calls.len(),
+            chain.puts.len()
+        );
+        Ok(chain)
+    }
+
     pub async fn search(
         &self,
         symbol: impl AsRef<str>,
         projection: SearchProjection,
     ) -> SchwabResult<Instrument> {
         let symbol = symbol.as_ref();
-        let url = format!("{API_URL}/marketdata/v1/instruments");
-        let response = HTTP_CLIENT
-            .get(url)
+        let response = self
+            .api
+            .get("/marketdata/v1/instruments")
             .query(&[("symbol", symbol), ("projection", &projection.to_string())])
-            .bearer_auth(self.bearer_token())
+            .authorized(&self.auth)
             .send()
             .await?;
         if !response.status().is_success() {
@@ -384,6 +449,115 @@ impl SchwabClient {
             "Didn't find {symbol} in {value}"
         )))
     }
+
+    /// Equity market sessions of `date`, times are those of the exchange.
+    pub async fn get_market_hours(&self, date: NaiveDate) -> SchwabResult<MarketDay> {
+        let response = self
+            .api
+            .get("/marketdata/v1/markets")
+            .query(&[
+                ("markets", "equity".to_owned()),
+                ("date", date.format("%Y-%m-%d").to_string()),
+            ])
+            .authorized(&self.auth)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(SchwabError::ApiError(
+                response.status().as_u16(),
+                format!("Failed to get market hours: {}", response.text().await?),
+            ));
+        }
+        let value = response.json::<Value>().await?;
+        parse_market_day(date, &value)
+            .ok_or_else(|| SchwabError::ApiError(444, format!("Invalid response: {value}")))
+    }
+}
+
+/// The product of the equity market is keyed "EQ" on open days and "equity" on closed ones.
+fn parse_market_day(date: NaiveDate, value: &Value) -> Option<MarketDay> {
+    let market = value.get("equity")?.as_object()?.values().next()?;
+    if !market.get("isOpen")?.as_bool()? {
+        return Some(MarketDay::closed(date));
+    }
+    let hours = market.get("sessionHours")?;
+    let session = |name: &str| -> Option<(NaiveTime, NaiveTime)> {
+        let session = hours.get(name)?.as_array()?.first()?;
+        let time = |key: &str| {
+            let time = session.get(key)?.as_str()?;
+            DateTime::<FixedOffset>::parse_from_rfc3339(time)
+                .ok()
+                .map(|time| time.naive_local().time())
+        };
+        Some((time("start")?, time("end")?))
+    };
+    let regular = session("regularMarket")?;
+    let extended = (
+        session("preMarket").map_or(regular.0, |(start, _)| start),
+        session("postMarket").map_or(regular.1, |(_, end)| end),
+    );
+    Some(MarketDay {
+        date,
+        regular: Some(regular),
+        extended: Some(extended),
+    })
+}
+
+#[cfg(feature = "trading")]
+impl SchwabClient {
+    pub async fn place_order(
+        &self,
+        account_hash: &str,
+        order: &crate::OrderRequest,
+    ) -> SchwabResult<i64> {
+        self.ensure_trading()?;
+        let order_id =
+            crate::orders::place_order(&self.api, &self.auth, account_hash, order).await?;
+        info!("Placed order #{order_id}");
+        Ok(order_id)
+    }
+
+    /// Replaces the working order, Schwab cancels it and returns the id of the new order.
+    pub async fn replace_order(
+        &self,
+        account_hash: &str,
+        order_id: i64,
+        order: &crate::OrderRequest,
+    ) -> SchwabResult<i64> {
+        self.ensure_trading()?;
+        let new_order_id =
+            crate::orders::replace_order(&self.api, &self.auth, account_hash, order_id, order)
+                .await?;
+        info!("Replaced order #{order_id} with #{new_order_id}");
+        Ok(new_order_id)
+    }
+
+    pub async fn cancel_order(&self, account_hash: &str, order_id: i64) -> SchwabResult<()> {
+        self.ensure_trading()?;
+        crate::orders::cancel_order(&self.api, &self.auth, account_hash, order_id).await?;
+        info!("Cancelled order #{order_id}");
+        Ok(())
+    }
+
+    pub async fn get_orders(
+        &self,
+        account_hash: &str,
+        entered_between: (DateTime<Local>, DateTime<Local>),
+        status: Option<&str>,
+    ) -> SchwabResult<Vec<crate::Order>> {
+        crate::orders::get_orders(&self.api, &self.auth, account_hash, entered_between, status)
+            .await
+    }
+
+    fn ensure_trading(&self) -> SchwabResult<()> {
+        if self.auth.credentials().trading {
+            Ok(())
+        } else {
+            Err(SchwabError::Unexpected(
+                "Trading is disabled, set enable_trading in the config".to_owned(),
+            ))
+        }
+    }
 }
 
 impl Drop for SchwabClient {
@@ -440,15 +614,265 @@ impl Display for SearchProjection {
 
 #[cfg(test)]
 mod test {
-    use crate::schwab_client::{SchwabClient, SearchProjection};
+    use crate::schwab_client::{Frequency, SchwabClient, SearchProjection};
+    use crate::streaming_client::{StreamResponse, Subscription};
+    use crate::{ACCOUNT_ACTIVITY_KEY, Credentials};
+    use app_config::SchwabApiConfig;
+    use schwab_mock::{MockCandle, MockData, MockServer};
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    fn mock_data() -> MockData {
+        let now = util::time::now().timestamp_millis();
+        MockData {
+            candles: [(
+                "NVDA".to_owned(),
+                (0..5)
+                    .map(|i| MockCandle {
+                        open: 100.0 + i as f64,
+                        high: 101.0 + i as f64,
+                        low: 99.0 + i as f64,
+                        close: 100.5 + i as f64,
+                        volume: 1000,
+                        datetime: now - (5 - i) * 60_000,
+                    })
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+            quotes: [(
+                "NVDA".to_owned(),
+                json!({
+                    "bidPrice": 104.4, "bidSize": 3, "askPrice": 104.6, "askSize": 5,
+                    "lastPrice": 104.5, "lastSize": 100, "totalVolume": 5000,
+                    "quoteTime": now, "tradeTime": now
+                }),
+            )]
+            .into_iter()
+            .collect(),
+            instruments: vec![json!({
+                "symbol": "NVDA",
+                "exchange": "NASDAQ",
+                "assetType": "EQUITY",
+                "cusip": "67066G104",
+                "description": "NVIDIA CORP"
+            })],
+            closed_dates: vec!["2025-12-25".to_owned()],
+            heartbeat_ms: 500,
+            ..MockData::default()
+        }
+    }
+
+    async fn mock_client(server: &MockServer) -> anyhow::Result<SchwabClient> {
+        let config = SchwabApiConfig {
+            url: server.url(),
+            ..SchwabApiConfig::default()
+        };
+        let credentials = Credentials {
+            client_id: "client-id".to_owned(),
+            client_secret: "client-secret".to_owned(),
+            trading: false,
+        };
+        Ok(SchwabClient::from_refresh_token(&config, credentials, "refresh-token").await?)
+    }
+
+    async fn eventually(what: &str, check: impl Fn() -> bool) {
+        for _ in 0..400 {
+            if check() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("Timed out waiting for {what}");
+    }
+
+    async fn next(receiver: &mut mpsc::UnboundedReceiver<StreamResponse>) -> StreamResponse {
+        tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("Timed out waiting for stream response")
+            .expect("Stream closed")
+    }
 
     #[tokio::test]
     async fn test_schwab_client() -> anyhow::Result<()> {
-        util::init::init_test();
+        let server = MockServer::start(mock_data()).await?;
+        let client = mock_client(&server).await?;
+        assert_eq!(server.tokens_issued(), 1);
 
-        let client = SchwabClient::init().await?;
         let search_res = client.search("nvda", SearchProjection::DescSearch).await?;
-        println!("{:?}", search_res);
+        assert_eq!(search_res.symbol, "NVDA");
+        assert_eq!(search_res.description, "NVIDIA CORP");
+
+        let quote = client.get_quote("NVDA").await?;
+        assert_eq!(quote.bid_price, 104.4);
+        assert_eq!(quote.ask_size, 5);
+
+        // A rejected access token gets refreshed and the request retried
+        server.expire_access_tokens();
+        let quote = client.get_quote("NVDA").await?;
+        assert_eq!(quote.last_price, 104.5);
+        assert_eq!(server.tokens_issued(), 2);
+
+        // A new login swaps in both tokens under the running client
+        let redirect_uri = "https://127.0.0.1/api/auth/callback";
+        let url = client.authorize_url(redirect_uri, "login-state")?;
+        assert!(url.starts_with(&format!("{}/v1/oauth/authorize?", server.url())));
+        assert!(url.contains("state=login-state"));
+        server.expire_access_tokens();
+        client.login("auth-code", redirect_uri).await?;
+        assert_eq!(server.tokens_issued(), 3);
+        assert!(client.refresh_token_expires_at() > util::time::now() + chrono::Duration::days(6));
+        client.get_quote("NVDA").await?;
+        assert_eq!(server.tokens_issued(), 3);
+
+        let now = util::time::now();
+        let candles = client
+            .get_price_history(
+                "NVDA",
+                Frequency::Minute(1),
+                Some((now - chrono::Duration::seconds(210), now)),
+                None,
+                false,
+            )
+            .await?;
+        assert_eq!(candles.len(), 3);
+        assert_eq!(candles[0].open, 102.0);
+        assert_eq!(candles[0].duration, 60);
+
+        let date = |m, d| chrono::NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let day = client.get_market_hours(date(12, 24)).await?;
+        let time = |h, m| chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(day.regular, Some((time(9, 30), time(16, 0))));
+        assert_eq!(day.extended, Some((time(7, 0), time(20, 0))));
+        assert!(!client.get_market_hours(date(12, 25)).await?.is_open());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_streaming_client() -> anyhow::Result<()> {
+        let server = MockServer::start(mock_data()).await?;
+        let client = mock_client(&server).await?;
+        let streaming_client = client.create_streaming_client().await?;
+        let mut receiver = streaming_client.create_subscription();
+        assert_eq!(server.logins(), 1);
+
+        streaming_client.subscribe(Subscription::EquityChart, ["NVDA"]);
+        streaming_client.subscribe(Subscription::EquityLevelOne, ["NVDA"]);
+        streaming_client.subscribe(Subscription::EquityLevelOne, ["AAPL"]);
+        eventually("subscriptions", || {
+            server.subscriptions("CHART_EQUITY") == ["NVDA"]
+                && server.subscriptions("LEVELONE_EQUITIES") == ["AAPL", "NVDA"]
+        })
+        .await;
+
+        let time = util::time::now().timestamp() * 1000;
+        server.push(
+            "CHART_EQUITY",
+            vec![json!({
+                "key": "NVDA", "1": 1, "2": 100.0, "3": 101.0, "4": 99.5, "5": 100.5,
+                "6": 1200.0, "7": time
+            })],
+        );
+        let StreamResponse::Equity { symbol, candle } = next(&mut receiver).await else {
+            panic!("Expected a chart candle");
+        };
+        assert_eq!(symbol, "NVDA");
+        assert_eq!(candle.close, 100.5);
+        assert_eq!(candle.volume, 1200);
+        assert_eq!(candle.time.timestamp() * 1000, time);
+
+        // Level one updates only carry the changed fields
+        server.push(
+            "LEVELONE_EQUITIES",
+            vec![json!({ "key": "AAPL", "1": 189.9, "2": 190.1, "3": 190.0 })],
+        );
+        server.push(
+            "LEVELONE_EQUITIES",
+            vec![json!({ "key": "AAPL", "3": 190.5 })],
+        );
+        next(&mut receiver).await;
+        let StreamResponse::EquityLevelOne { symbol, quote } = next(&mut receiver).await else {
+            panic!("Expected a level one quote");
+        };
+        assert_eq!(symbol, "AAPL");
+        assert_eq!(quote.bid_price, 189.9);
+        assert_eq!(quote.last_price, 190.5);
+
+        // Futures carry the chart time in field 1 and their own level one layout
+        streaming_client.subscribe(Subscription::FuturesChart, ["/ES"]);
+        streaming_client.subscribe(Subscription::FuturesLevelOne, ["/ES"]);
+        eventually("futures subscriptions", || {
+            server.subscriptions("CHART_FUTURES") == ["/ES"]
+                && server.subscriptions("LEVELONE_FUTURES") == ["/ES"]
+        })
+        .await;
+        server.push(
+            "CHART_FUTURES",
+            vec![json!({
+                "key": "/ES", "1": time, "2": 6000.0, "3": 6002.25, "4": 5999.5, "5": 6001.75,
+                "6": 850.0
+            })],
+        );
+        let StreamResponse::Futures { symbol, candle } = next(&mut receiver).await else {
+            panic!("Expected a futures candle");
+        };
+        assert_eq!(symbol, "/ES");
+        assert_eq!(candle.high, 6002.25);
+        assert_eq!(candle.time.timestamp() * 1000, time);
+        server.push(
+            "LEVELONE_FUTURES",
+            vec![json!({ "key": "/ES", "1": 6001.5, "2": 6001.75, "3": 6001.75, "8": 120000 })],
+        );
+        let StreamResponse::FuturesLevelOne { symbol, quote } = next(&mut receiver).await else {
+            panic!("Expected a futures quote");
+        };
+        assert_eq!(symbol, "/ES");
+        assert_eq!(quote.ask_price, 6001.75);
+        assert_eq!(quote.total_volume, 120000);
+
+        streaming_client.subscribe(Subscription::AccountActivity, [ACCOUNT_ACTIVITY_KEY]);
+        eventually("account activity subscription", || {
+            server.subscriptions("ACCT_ACTIVITY") == [ACCOUNT_ACTIVITY_KEY]
+        })
+        .await;
+        let fill = json!({ "SchwabOrderID": "1001", "Symbol": "AAPL", "LastFillQuantity": 5 });
+        server.push(
+            "ACCT_ACTIVITY",
+            vec![json!({
+                "key": ACCOUNT_ACTIVITY_KEY, "1": "12345678", "2": "OrderFillCompleted",
+                "3": fill.to_string()
+            })],
+        );
+        let StreamResponse::OrderFill { activity } = next(&mut receiver).await else {
+            panic!("Expected an order fill");
+        };
+        assert_eq!(activity.order_id, Some(1001));
+        assert_eq!(activity.quantity, Some(5.0));
+
+        streaming_client.unsubscribe(Subscription::EquityLevelOne, ["AAPL"]);
+        eventually("unsubscription", || {
+            server.subscriptions("LEVELONE_EQUITIES") == ["NVDA"]
+        })
+        .await;
+
+        // The client logs in again and restores its subscriptions once the stream drops
+        server.drop_streams();
+        eventually("resubscription", || {
+            server.logins() == 2
+                && server.subscriptions("CHART_EQUITY") == ["NVDA"]
+                && server.subscriptions("LEVELONE_EQUITIES") == ["NVDA"]
+        })
+        .await;
+        server.push(
+            "LEVELONE_EQUITIES",
+            vec![json!({ "key": "NVDA", "3": 105.0 })],
+        );
+        let StreamResponse::EquityLevelOne { symbol, quote } = next(&mut receiver).await else {
+            panic!("Expected a level one quote");
+        };
+        assert_eq!(symbol, "NVDA");
+        assert_eq!(quote.last_price, 105.0);
         Ok(())
     }
 }
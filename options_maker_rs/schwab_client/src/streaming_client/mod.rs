@@ -1,11 +1,12 @@
-use crate::schwab_client::AccessToken;
-use crate::{Candle, Quote, SchwabResult};
+use crate::api_client::ApiClient;
+use crate::auth::Authenticator;
+use crate::{AccountActivity, Candle, OrderBook, Quote, SchwabResult, Trade};
 use futures::{SinkExt, StreamExt};
 
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde_json::Value;
+use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use streamer::Streamer;
 
@@ -27,14 +28,29 @@ pub struct StreamingClient {
 pub enum Subscription {
     EquityChart,
     EquityLevelOne,
+    EquityTimeSale,
     OptionsLevelOne,
+    FuturesChart,
+    FuturesLevelOne,
+    NasdaqBook,
+    NyseBook,
+    OptionsBook,
+    AccountActivity,
 }
 
 #[derive(Debug, Clone)]
 pub enum StreamResponse {
     Equity { symbol: String, candle: Candle },
     EquityLevelOne { symbol: String, quote: Quote },
+    TimeSale { symbol: String, trade: Trade },
     OptionsLevelOne { symbol: String, quote: Quote },
+    Futures { symbol: String, candle: Candle },
+    FuturesLevelOne { symbol: String, quote: Quote },
+    Book { symbol: String, book: OrderBook },
+    OrderFill { activity: AccountActivity },
+    OrderCancel { activity: AccountActivity },
+    PositionChange { activity: AccountActivity },
+    OrderStatus { activity: AccountActivity },
 }
 
 #[derive(Debug, Clone)]
@@ -46,12 +62,12 @@ enum StreamCommand {
 
 impl StreamingClient {
     pub(crate) async fn init(
-        access_token: Arc<RwLock<AccessToken>>,
+        api: ApiClient,
+        auth: Arc<Authenticator>,
         main_client_alive: Arc<AtomicBool>,
     ) -> SchwabResult<Self> {
         let is_alive = Arc::new(AtomicBool::new(true));
-        let cmd_sender =
-            Self::init_inner(access_token, is_alive.clone(), main_client_alive).await?;
+        let cmd_sender = Self::init_inner(api, auth, is_alive.clone(), main_client_alive).await?;
 
         Ok(Self {
             cmd_sender,
@@ -89,12 +105,13 @@ impl StreamingClient {
     }
 
     async fn init_inner(
-        access_token: Arc<RwLock<AccessToken>>,
+        api: ApiClient,
+        auth: Arc<Authenticator>,
         streaming_client_alive: Arc<AtomicBool>,
         main_client_alive: Arc<AtomicBool>,
     ) -> SchwabResult<mpsc::UnboundedSender<StreamCommand>> {
         let (cmd_sender, mut cmd_receiver) = mpsc::unbounded_channel::<StreamCommand>();
-        let (mut config, mut ws_stream) = Streamer::connect(access_token.clone()).await?;
+        let (mut config, mut ws_stream) = Streamer::connect(&api, &auth).await?;
 
         tokio::spawn(async move {
             let clients_alive = || {
@@ -222,7 +239,7 @@ impl StreamingClient {
                         continue;
                     }
 
-                    match Streamer::connect(access_token.clone()).await {
+                    match Streamer::connect(&api, &auth).await {
                         Ok(conn_result) => break conn_result,
                         Err(e) => {
                             warn!("Error while re-connecting to websocket: {e}");
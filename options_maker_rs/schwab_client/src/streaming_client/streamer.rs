@@ -1,19 +1,19 @@
-use crate::schwab_client::AccessToken;
+use crate::account_activity::ActivityKind;
+use crate::api_client::ApiClient;
+use crate::auth::Authenticator;
 use crate::streaming_client::{StreamResponse, Subscription};
-use crate::{API_URL, Candle, Quote, SchwabError, SchwabResult};
+use crate::{AccountActivity, Candle, OrderBook, Quote, SchwabError, SchwabResult, Trade};
 use futures::{SinkExt, StreamExt};
 use itertools::Itertools;
 use rustc_hash::FxHashMap;
 use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, RwLock};
 use std::time::Instant;
 use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
 use tracing::{debug, info, warn};
-use util::http::HTTP_CLIENT;
 
 static REQUEST_ID: AtomicU32 = AtomicU32::new(1);
 
@@ -34,11 +34,11 @@ pub struct Streamer {
 
 impl Streamer {
     pub async fn connect(
-        access_token: Arc<RwLock<AccessToken>>,
+        api: &ApiClient,
+        auth: &Authenticator,
     ) -> SchwabResult<(Self, WebSocketStream<MaybeTlsStream<TcpStream>>)> {
         let start = Instant::now();
-        let token = access_token.read().unwrap().access_token.clone();
-        let streamer_info = Self::fetch_streamer_info(&token).await?;
+        let streamer_info = Self::fetch_streamer_info(api, auth).await?;
 
         info!(
             "Connecting to websocket at {}",
@@ -55,7 +55,7 @@ impl Streamer {
             "SchwabClientCustomerId": streamer_info.schwab_client_customer_id,
             "SchwabClientCorrelId": streamer_info.schwab_client_correl_id,
             "parameters": {
-                "Authorization": token,
+                "Authorization": auth.access_token(),
                 "SchwabClientChannel": streamer_info.schwab_client_channel,
                 "SchwabClientFunctionId": streamer_info.schwab_client_function_id,
             },
@@ -121,7 +121,10 @@ impl Streamer {
         None
     }
 
-    async fn fetch_streamer_info(access_token: &str) -> SchwabResult<StreamerInfo> {
+    async fn fetch_streamer_info(
+        api: &ApiClient,
+        auth: &Authenticator,
+    ) -> SchwabResult<StreamerInfo> {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "camelCase")]
         pub struct UserPreferences {
@@ -129,9 +132,9 @@ impl Streamer {
         }
 
         info!("Fetching StreamerInfo");
-        let response = HTTP_CLIENT
-            .get(format!("{API_URL}/trader/v1/userPreference"))
-            .bearer_auth(access_token)
+        let response = api
+            .get("/trader/v1/userPreference")
+            .authorized(auth)
             .send()
             .await?;
         if !response.status().is_success() {
@@ -202,7 +205,14 @@ impl Subscription {
         let subscriptions = [
             Subscription::EquityChart,
             Subscription::EquityLevelOne,
+            Subscription::EquityTimeSale,
             Subscription::OptionsLevelOne,
+            Subscription::FuturesChart,
+            Subscription::FuturesLevelOne,
+            Subscription::NasdaqBook,
+            Subscription::NyseBook,
+            Subscription::OptionsBook,
+            Subscription::AccountActivity,
         ];
         subscriptions
             .iter()
@@ -215,6 +225,13 @@ impl Subscription {
             Subscription::EquityChart => "CHART_EQUITY",
             Subscription::EquityLevelOne => "LEVELONE_EQUITIES",
             Subscription::OptionsLevelOne => "LEVELONE_OPTIONS",
+            Subscription::FuturesChart => "CHART_FUTURES",
+            Subscription::FuturesLevelOne => "LEVELONE_FUTURES",
+            Subscription::EquityTimeSale => "TIMESALE_EQUITY",
+            Subscription::NasdaqBook => "NASDAQ_BOOK",
+            Subscription::NyseBook => "NYSE_BOOK",
+            Subscription::OptionsBook => "OPTIONS_BOOK",
+            Subscription::AccountActivity => "ACCT_ACTIVITY",
         }
     }
 
@@ -225,6 +242,13 @@ impl Subscription {
             Subscription::OptionsLevelOne => {
                 "0,2,3,4,5,6,7,8,9,10,15,16,17,18,19,28,29,30,31,32,38,39"
             }
+            Subscription::FuturesChart => "0,1,2,3,4,5,6",
+            Subscription::EquityTimeSale => "0,1,2,3,4",
+            Subscription::FuturesLevelOne => "0,1,2,3,4,5,8,9,10,11,12,13,14,18,19,23",
+            Subscription::NasdaqBook
+            | Subscription::NyseBook
+            | Subscription::OptionsBook
+            | Subscription::AccountActivity => "0,1,2,3",
         }
     }
 
@@ -283,6 +307,88 @@ impl Subscription {
                     quote: quote.clone(),
                 }
             }
+            Subscription::FuturesChart => {
+                // Unlike the equities, futures carry the chart time in field 1
+                #[derive(Debug, Deserialize)]
+                struct ChartFutures {
+                    key: String,
+                    #[serde(rename = "1")]
+                    time: i64,
+                    #[serde(rename = "2")]
+                    open: f64,
+                    #[serde(rename = "3")]
+                    high: f64,
+                    #[serde(rename = "4")]
+                    low: f64,
+                    #[serde(rename = "5")]
+                    close: f64,
+                    #[serde(rename = "6")]
+                    volume: f64,
+                }
+                let cf = serde_json::from_value::<ChartFutures>(value.clone()).ok()?;
+                StreamResponse::Futures {
+                    symbol: cf.key,
+                    candle: Candle {
+                        open: cf.open,
+                        low: cf.low,
+                        high: cf.high,
+                        close: cf.close,
+                        volume: cf.volume as u64,
+                        time: util::time::from_ts(cf.time / 1000),
+                        duration: 60,
+                    },
+                }
+            }
+            Subscription::FuturesLevelOne => {
+                let key = value.get("key").and_then(Value::as_str)?;
+                let quote = cache.entry(key.to_owned()).or_default();
+                Self::fill_futures_quote(quote, value);
+                StreamResponse::FuturesLevelOne {
+                    symbol: key.to_owned(),
+                    quote: quote.clone(),
+                }
+            }
+            Subscription::EquityTimeSale => {
+                #[derive(Debug, Deserialize)]
+                struct TimeSale {
+                    key: String,
+                    #[serde(rename = "1")]
+                    time: i64,
+                    #[serde(rename = "2")]
+                    price: f64,
+                    #[serde(rename = "3")]
+                    size: f64,
+                    #[serde(rename = "4")]
+                    sequence: u64,
+                }
+                let ts = serde_json::from_value::<TimeSale>(value.clone()).ok()?;
+                StreamResponse::TimeSale {
+                    symbol: ts.key,
+                    trade: Trade {
+                        price: ts.price,
+                        size: ts.size as u64,
+                        time: util::time::from_ts_millis(ts.time),
+                        sequence: ts.sequence,
+                    },
+                }
+            }
+            Subscription::NasdaqBook | Subscription::NyseBook | Subscription::OptionsBook => {
+                // Every book message is a full snapshot, nothing to merge with the previous one
+                let key = value.get("key").and_then(Value::as_str)?;
+                StreamResponse::Book {
+                    symbol: key.to_owned(),
+                    book: OrderBook::from_stream(self.service(), value)?,
+                }
+            }
+            Subscription::AccountActivity => {
+                let (kind, activity) = AccountActivity::from_stream(value)?;
+                match kind {
+                    ActivityKind::Fill => StreamResponse::OrderFill { activity },
+                    ActivityKind::Cancel => StreamResponse::OrderCancel { activity },
+                    ActivityKind::PositionChange => StreamResponse::PositionChange { activity },
+                    ActivityKind::OrderStatus => StreamResponse::OrderStatus { activity },
+                }
+            }
         };
         Some(response)
     }
@@ -339,6 +445,55 @@ impl Subscription {
         }
     }
 
+    fn fill_futures_quote(quote: &mut Quote, value: &Value) {
+        // "0,1,2,3,4,5,8,9,10,11,12,13,14,18,19,23"
+        if let Some(bid_price) = value.get("1").and_then(Value::as_f64) {
+            quote.bid_price = bid_price;
+        }
+        if let Some(ask_price) = value.get("2").and_then(Value::as_f64) {
+            quote.ask_price = ask_price;
+        }
+        if let Some(last_price) = value.get("3").and_then(Value::as_f64) {
+            quote.last_price = last_price;
+        }
+        if let Some(bid_size) = value.get("4").and_then(Value::as_u64) {
+            quote.bid_size = bid_size;
+        }
+        if let Some(ask_size) = value.get("5").and_then(Value::as_u64) {
+            quote.ask_size = ask_size;
+        }
+        if let Some(total_volume) = value.get("8").and_then(Value::as_u64) {
+            quote.total_volume = total_volume;
+        }
+        if let Some(last_size) = value.get("9").and_then(Value::as_u64) {
+            quote.last_size = last_size;
+        }
+        if let Some(quote_time) = value.get("10").and_then(Value::as_i64) {
+            quote.quote_time = Some(util::time::from_ts(quote_time / 1000));
+        }
+        if let Some(trade_time) = value.get("11").and_then(Value::as_i64) {
+            quote.trade_time = Some(util::time::from_ts(trade_time / 1000));
+        }
+        if let Some(high_price) = value.get("12").and_then(Value::as_f64) {
+            quote.high_price = Some(high_price);
+        }
+        if let Some(low_price) = value.get("13").and_then(Value::as_f64) {
+            quote.low_price = Some(low_price);
+        }
+        if let Some(close_price) = value.get("14").and_then(Value::as_f64) {
+            quote.close_price = Some(close_price);
+        }
+        if let Some(open_price) = value.get("18").and_then(Value::as_f64) {
+            quote.open_price = Some(open_price);
+        }
+        if let Some(net_change) = value.get("19").and_then(Value::as_f64) {
+            quote.net_change = Some(net_change);
+        }
+        if let Some(open_interest) = value.get("23").and_then(Value::as_u64) {
+            quote.open_interest = Some(open_interest);
+        }
+    }
+
     fn fill_options_quote(quote: &mut Quote, value: &Value) {
         // "0,2,3,4,5,6,7,8,9,10,15,16,17,18,19,28,29,30,31,32,38,39"
         if let Some(bid_price) = value.get("2").and_then(Value::as_f64) {
@@ -0,0 +1,207 @@
+use crate::auth::RefreshToken;
+use crate::{SchwabError, SchwabResult};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::{info, warn};
+
+/// Base64 encoded 256-bit key of the token file, it takes precedence over `token_key_file`.
+pub const TOKEN_KEY_ENV: &str = "OPTIONS_MAKER_TOKEN_KEY";
+
+const AAD: &[u8] = b"options_maker refresh token";
+
+/// Token file holding the refresh token, sealed with AES-256-GCM when a key is configured.
+/// A plaintext file of an older version is still read, and encrypted in place on load.
+pub(crate) struct TokenStore {
+    path: PathBuf,
+    key: Option<LessSafeKey>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    version: u32,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl TokenStore {
+    /// Reads the key off [TOKEN_KEY_ENV], or off `key_file` which gets a fresh key if it doesn't
+    /// exist yet.
+    pub async fn init(path: impl Into<PathBuf>, key_file: Option<&str>) -> SchwabResult<Self> {
+        let path = path.into();
+        let key = if let Ok(key) = std::env::var(TOKEN_KEY_ENV) {
+            Some(parse_key(&key)?)
+        } else if let Some(key_file) = key_file {
+            Some(load_or_create_key(Path::new(key_file)).await?)
+        } else {
+            warn!(
+                "Neither {TOKEN_KEY_ENV} nor token_key_file is set, token file is kept in plaintext"
+            );
+            None
+        };
+        Ok(Self { path, key })
+    }
+
+    pub async fn load(&self) -> SchwabResult<Option<RefreshToken>> {
+        if !fs::try_exists(&self.path).await? {
+            return Ok(None);
+        }
+        let content = fs::read(&self.path).await?;
+        if let Ok(sealed) = serde_json::from_slice::<Sealed>(&content) {
+            let key = self.key.as_ref().ok_or_else(|| {
+                SchwabError::Unexpected(format!(
+                    "{:?} is encrypted, set {TOKEN_KEY_ENV} or token_key_file",
+                    self.path
+                ))
+            })?;
+            return Ok(Some(open(key, &sealed)?));
+        }
+
+        let refresh_token = serde_json::from_slice::<RefreshToken>(&content)
+            .map_err(|e| SchwabError::IoError(e.into()))?;
+        if self.key.is_some() {
+            info!("Encrypting the plaintext token file {:?}", self.path);
+            self.save(&refresh_token).await?;
+        }
+        Ok(Some(refresh_token))
+    }
+
+    pub async fn save(&self, refresh_token: &RefreshToken) -> SchwabResult<()> {
+        let content = match &self.key {
+            Some(key) => serde_json::to_vec_pretty(&seal(key, refresh_token)?),
+            None => serde_json::to_vec_pretty(refresh_token),
+        }
+        .map_err(|e| SchwabError::IoError(e.into()))?;
+        write_private(&self.path, &content).await?;
+        info!(
+            "Refresh token saved to {:?}",
+            fs::canonicalize(&self.path).await?
+        );
+        Ok(())
+    }
+}
+
+fn seal(key: &LessSafeKey, refresh_token: &RefreshToken) -> SchwabResult<Sealed> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| crypto_error("generate a nonce"))?;
+    let mut in_out =
+        serde_json::to_vec(refresh_token).map_err(|e| SchwabError::IoError(e.into()))?;
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(AAD),
+        &mut in_out,
+    )
+    .map_err(|_| crypto_error("encrypt the token"))?;
+    Ok(Sealed {
+        version: 1,
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(in_out),
+    })
+}
+
+fn open(key: &LessSafeKey, sealed: &Sealed) -> SchwabResult<RefreshToken> {
+    if sealed.version != 1 {
+        return Err(SchwabError::Unexpected(format!(
+            "Unsupported token file version {}",
+            sealed.version
+        )));
+    }
+    let decode = |value: &str| {
+        BASE64
+            .decode(value)
+            .map_err(|e| crypto_error(&e.to_string()))
+    };
+    let nonce = Nonce::try_assume_unique_for_key(&decode(&sealed.nonce)?)
+        .map_err(|_| crypto_error("read the nonce"))?;
+    let mut in_out = decode(&sealed.ciphertext)?;
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(AAD), &mut in_out)
+        .map_err(|_| crypto_error("decrypt the token file, is it the right key?"))?;
+    serde_json::from_slice(plaintext).map_err(|e| SchwabError::IoError(e.into()))
+}
+
+fn parse_key(key: &str) -> SchwabResult<LessSafeKey> {
+    let bytes = BASE64
+        .decode(key.trim())
+        .map_err(|e| crypto_error(&format!("decode the key: {e}")))?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes)
+        .map_err(|_| crypto_error("use the key, it must be 32 bytes"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+async fn load_or_create_key(key_file: &Path) -> SchwabResult<LessSafeKey> {
+    if fs::try_exists(key_file).await? {
+        return parse_key(&fs::read_to_string(key_file).await?);
+    }
+    let mut key = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| crypto_error("generate a key"))?;
+    let encoded = BASE64.encode(key);
+    write_private(key_file, encoded.as_bytes()).await?;
+    warn!("Generated a new token key at {key_file:?}, keep it off the shared box");
+    parse_key(&encoded)
+}
+
+/// Writes the file readable by the owner only.
+async fn write_private(path: &Path, content: &[u8]) -> SchwabResult<()> {
+    fs::write(path, content).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    Ok(())
+}
+
+fn crypto_error(action: &str) -> SchwabError {
+    SchwabError::Unexpected(format!("Failed to {action}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn refresh_token() -> RefreshToken {
+        RefreshToken {
+            refresh_token: "refresh-token".to_owned(),
+            token_type: "Bearer".to_owned(),
+            scope: crate::auth::READONLY_SCOPE.to_owned(),
+            expires_at: util::time::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_store() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("token_store_{}", std::process::id()));
+        fs::create_dir_all(&dir).await?;
+        let token_file = dir.join("token.json");
+        let key_file = dir.join("token.key");
+        let key_file = key_file.to_str();
+
+        // An existing plaintext file gets encrypted on load
+        let plaintext = TokenStore::init(&token_file, None).await?;
+        plaintext.save(&refresh_token()).await?;
+        let store = TokenStore::init(&token_file, key_file).await?;
+        let token = store.load().await?.unwrap();
+        assert_eq!(token.refresh_token, "refresh-token");
+        let content = fs::read_to_string(&token_file).await?;
+        assert!(!content.contains("refresh-token"));
+        assert!(plaintext.load().await.is_err());
+
+        let store = TokenStore::init(&token_file, key_file).await?;
+        assert_eq!(store.load().await?.unwrap().expires_at, token.expires_at);
+
+        let other_key = TokenStore::init(&token_file, dir.join("other.key").to_str()).await?;
+        assert!(other_key.load().await.is_err());
+
+        fs::remove_dir_all(&dir).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+//! Mock of the Schwab API serving scripted data: OAuth tokens, price history, quotes, instruments,
+//! market hours, user preferences and the streamer websocket. Used to test the client, the streamer and the
+//! providers without a brokerage account.
+
+mod streamer;
+
+use axum::extract::ws::{Message, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use streamer::StreamHandle;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+/// Scripted data served by the mock, it can be loaded from a json file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MockData {
+    /// Candles served by `pricehistory`, keyed by symbol.
+    pub candles: FxHashMap<String, Vec<MockCandle>>,
+    /// Raw quote objects served by `quotes`, keyed by symbol.
+    pub quotes: FxHashMap<String, Value>,
+    /// Raw instruments served by `instruments`.
+    pub instruments: Vec<Value>,
+    /// Dates (YYYY-MM-DD) `markets` reports closed, every other date is a regular session.
+    pub closed_dates: Vec<String>,
+    /// Data frames replayed in a loop to every streamer, filtered by its subscriptions.
+    pub stream: Vec<StreamFrame>,
+    pub stream_interval_ms: u64,
+    pub heartbeat_ms: u64,
+    pub access_token_expires_in: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockCandle {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: u64,
+    /// Epoch millis.
+    pub datetime: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamFrame {
+    pub service: String,
+    pub content: Vec<Value>,
+}
+
+impl Default for MockData {
+    fn default() -> Self {
+        Self {
+            candles: FxHashMap::default(),
+            quotes: FxHashMap::default(),
+            instruments: Vec::new(),
+            closed_dates: Vec::new(),
+            stream: Vec::new(),
+            stream_interval_ms: 1000,
+            heartbeat_ms: 5000,
+            access_token_expires_in: 1800,
+        }
+    }
+}
+
+pub(crate) struct MockState {
+    data: MockData,
+    ws_url: String,
+    tokens: Mutex<FxHashSet<String>>,
+    tokens_issued: AtomicUsize,
+    logins: AtomicUsize,
+    streams: Mutex<Vec<Arc<StreamHandle>>>,
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+    server: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts the mock at a random local port.
+    pub async fn start(data: MockData) -> anyhow::Result<Self> {
+        Self::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)), data).await
+    }
+
+    pub async fn bind(addr: SocketAddr, data: MockData) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(MockState {
+            data,
+            ws_url: if addr.ip().is_unspecified() {
+                format!("ws://localhost:{}/ws", addr.port())
+            } else {
+                format!("ws://{addr}/ws")
+            },
+            tokens: Mutex::new(FxHashSet::default()),
+            tokens_issued: AtomicUsize::new(0),
+            logins: AtomicUsize::new(0),
+            streams: Mutex::new(Vec::new()),
+        });
+        let router = Router::new()
+            .route("/v1/oauth/token", post(token))
+            .route("/marketdata/v1/pricehistory", get(price_history))
+            .route("/marketdata/v1/quotes", get(quotes))
+            .route("/marketdata/v1/instruments", get(instruments))
+            .route("/marketdata/v1/markets", get(markets))
+            .route("/trader/v1/userPreference", get(user_preference))
+            .route("/ws", get(websocket))
+            .with_state(state.clone());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, router).await.ok();
+        });
+        info!("Mock Schwab API is listening at http://{addr}");
+        Ok(Self {
+            addr,
+            state,
+            server,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Number of access tokens handed out, by either grant type.
+    pub fn tokens_issued(&self) -> usize {
+        self.state.tokens_issued.load(Ordering::Relaxed)
+    }
+
+    /// Number of successful streamer LOGINs.
+    pub fn logins(&self) -> usize {
+        self.state.logins.load(Ordering::Relaxed)
+    }
+
+    /// Keys currently subscribed to the service across the connected streamers.
+    pub fn subscriptions(&self, service: &str) -> Vec<String> {
+        let mut keys = self
+            .state
+            .streams
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|stream| stream.subscriptions(service))
+            .collect::<FxHashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    /// Sends a data frame to every streamer subscribed to any of its keys.
+    pub fn push(&self, service: &str, content: Vec<Value>) {
+        let frame = StreamFrame {
+            service: service.to_owned(),
+            content,
+        };
+        for stream in self.state.streams.lock().unwrap().iter() {
+            stream.send_data(&frame);
+        }
+    }
+
+    /// Closes every streamer connection, the clients are expected to reconnect.
+    pub fn drop_streams(&self) {
+        for stream in self.state.streams.lock().unwrap().drain(..) {
+            stream.close();
+        }
+    }
+
+    /// Invalidates every access token handed out so far.
+    pub fn expire_access_tokens(&self) {
+        self.state.tokens.lock().unwrap().clear();
+    }
+
+    /// Runs until the server stops.
+    pub async fn wait(mut self) {
+        (&mut self.server).await.ok();
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.abort();
+        self.drop_streams();
+    }
+}
+
+impl MockState {
+    fn is_valid_token(&self, token: &str) -> bool {
+        self.tokens.lock().unwrap().contains(token)
+    }
+
+    fn is_authorized(&self, headers: &HeaderMap) -> bool {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        let is_valid = self.is_valid_token(token);
+        if !is_valid {
+            debug!("Rejecting request with invalid token {token:?}");
+        }
+        is_valid
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenRequest {
+    grant_type: String,
+    refresh_token: Option<String>,
+    code: Option<String>,
+}
+
+async fn token(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Form(request): Form<TokenRequest>,
+) -> Response {
+    if !headers.contains_key(header::AUTHORIZATION) {
+        return error(StatusCode::UNAUTHORIZED, "Missing client credentials");
+    }
+    let refresh_token = match request.grant_type.as_str() {
+        "authorization_code" if request.code.is_some() => "mock-refresh-token".to_owned(),
+        "refresh_token" if request.refresh_token.is_some() => request.refresh_token.unwrap(),
+        _ => return error(StatusCode::BAD_REQUEST, "Unsupported grant"),
+    };
+
+    let issued = state.tokens_issued.fetch_add(1, Ordering::Relaxed) + 1;
+    let access_token = format!("mock-access-token-{issued}");
+    state.tokens.lock().unwrap().insert(access_token.clone());
+    Json(json!({
+        "access_token": access_token,
+        "refresh_token": refresh_token,
+        "expires_in": state.data.access_token_expires_in,
+        "token_type": "Bearer",
+        "scope": "api",
+        "id_token": "mock-id-token",
+    }))
+    .into_response()
+}
+
+async fn price_history(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if !state.is_authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Client not authorized");
+    }
+    let symbol = params.get("symbol").cloned().unwrap_or_default();
+    let param = |name: &str| params.get(name).and_then(|v| v.parse::<i64>().ok());
+    let start = param("startDate").unwrap_or(i64::MIN);
+    let end = param("endDate").unwrap_or(i64::MAX);
+    let candles = state
+        .data
+        .candles
+        .get(&symbol)
+        .map(|candles| {
+            candles
+                .iter()
+                .filter(|candle| start <= candle.datetime && candle.datetime <= end)
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    Json(json!({
+        "symbol": symbol,
+        "empty": candles.is_empty(),
+        "candles": candles,
+    }))
+    .into_response()
+}
+
+async fn quotes(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if !state.is_authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Client not authorized");
+    }
+    let quotes = params
+        .get("symbols")
+        .map(String::as_str)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|symbol| {
+            let quote = state.data.quotes.get(symbol)?;
+            Some((
+                symbol.to_owned(),
+                json!({ "symbol": symbol, "quote": quote }),
+            ))
+        })
+        .collect::<serde_json::Map<_, _>>();
+    Json(Value::Object(quotes)).into_response()
+}
+
+async fn instruments(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if !state.is_authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Client not authorized");
+    }
+    let symbol = params.get("symbol").cloned().unwrap_or_default();
+    let instruments = state
+        .data
+        .instruments
+        .iter()
+        .filter(|instrument| {
+            instrument["symbol"]
+                .as_str()
+                .is_some_and(|s| s.eq_ignore_ascii_case(&symbol))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    Json(json!({ "instruments": instruments })).into_response()
+}
+
+async fn markets(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if !state.is_authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Client not authorized");
+    }
+    let Some(date) = params.get("date") else {
+        return error(StatusCode::BAD_REQUEST, "Missing date");
+    };
+    if state.data.closed_dates.contains(date) {
+        return Json(json!({
+            "equity": {
+                "equity": { "date": date, "marketType": "EQUITY", "product": "equity", "isOpen": false }
+            }
+        }))
+        .into_response();
+    }
+    let session = |start: &str, end: &str| json!([{ "start": format!("{date}T{start}:00-04:00"), "end": format!("{date}T{end}:00-04:00") }]);
+    Json(json!({
+        "equity": {
+            "EQ": {
+                "date": date,
+                "marketType": "EQUITY",
+                "product": "EQ",
+                "isOpen": true,
+                "sessionHours": {
+                    "preMarket": session("07:00", "09:30"),
+                    "regularMarket": session("09:30", "16:00"),
+                    "postMarket": session("16:00", "20:00")
+                }
+            }
+        }
+    }))
+    .into_response()
+}
+
+async fn user_preference(State(state): State<Arc<MockState>>, headers: HeaderMap) -> Response {
+    if !state.is_authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "Client not authorized");
+    }
+    Json(json!({
+        "accounts": [],
+        "streamerInfo": [{
+            "streamerSocketUrl": state.ws_url,
+            "schwabClientCustomerId": "mock-customer-id",
+            "schwabClientCorrelId": "mock-correl-id",
+            "schwabClientChannel": "N9",
+            "schwabClientFunctionId": "APIAPP",
+        }],
+        "offers": [],
+    }))
+    .into_response()
+}
+
+async fn websocket(State(state): State<Arc<MockState>>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| streamer::handle_socket(socket, state))
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "errors": [{ "status": status.as_u16(), "title": message }],
+        })),
+    )
+        .into_response()
+}
+
+fn timestamp() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+fn text(value: Value) -> Message {
+    Message::text(value.to_string())
+}
@@ -0,0 +1,22 @@
+use schwab_mock::{MockData, MockServer};
+use std::net::{Ipv4Addr, SocketAddr};
+use tracing_subscriber::EnvFilter;
+
+/// Usage: schwab_mock [port] [data.json]
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive("info".parse()?))
+        .init();
+
+    let mut args = std::env::args().skip(1);
+    let port = args.next().map(|p| p.parse()).transpose()?.unwrap_or(8182);
+    let data = match args.next() {
+        Some(data_file) => serde_json::from_str::<MockData>(&std::fs::read_to_string(data_file)?)?,
+        None => MockData::default(),
+    };
+
+    let server = MockServer::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)), data).await?;
+    server.wait().await;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use crate::{MockState, StreamFrame, text, timestamp};
+use axum::extract::ws::{Message, WebSocket};
+use futures::{SinkExt, StreamExt};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+/// A connected streamer along with the keys it subscribed to per service.
+pub(crate) struct StreamHandle {
+    sender: mpsc::UnboundedSender<Message>,
+    logged_in: AtomicBool,
+    subscriptions: Mutex<FxHashMap<String, FxHashSet<String>>>,
+}
+
+impl StreamHandle {
+    pub(crate) fn subscriptions(&self, service: &str) -> Vec<String> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .get(service)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn send_data(&self, frame: &StreamFrame) {
+        if !self.logged_in.load(Ordering::Relaxed) {
+            return;
+        }
+        let content = {
+            let subscriptions = self.subscriptions.lock().unwrap();
+            let Some(keys) = subscriptions.get(&frame.service) else {
+                return;
+            };
+            frame
+                .content
+                .iter()
+                .filter(|item| item["key"].as_str().is_some_and(|key| keys.contains(key)))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        if content.is_empty() {
+            return;
+        }
+        self.sender
+            .send(text(json!({
+                "data": [{
+                    "service": frame.service,
+                    "timestamp": timestamp(),
+                    "command": "SUBS",
+                    "content": content,
+                }],
+            })))
+            .ok();
+    }
+
+    pub(crate) fn close(&self) {
+        self.sender.send(Message::Close(None)).ok();
+    }
+
+    fn handle_request(&self, state: &MockState, request: &Value) {
+        let service = request["service"].as_str().unwrap_or_default();
+        let command = request["command"].as_str().unwrap_or_default();
+        let request_id = match &request["requestid"] {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        };
+        let params = &request["parameters"];
+        debug!("Streamer request #{request_id}: {service}/{command}");
+
+        let (code, msg) = match (service, command) {
+            ("ADMIN", "LOGIN") => {
+                let token = params["Authorization"].as_str().unwrap_or_default();
+                if state.is_valid_token(token) {
+                    self.logged_in.store(true, Ordering::Relaxed);
+                    state.logins.fetch_add(1, Ordering::Relaxed);
+                    (0, "server=mock;status=PN".to_owned())
+                } else {
+                    (3, "Login denied".to_owned())
+                }
+            }
+            ("ADMIN", "LOGOUT") => {
+                self.logged_in.store(false, Ordering::Relaxed);
+                self.close();
+                (0, "LOGOUT command succeeded".to_owned())
+            }
+            _ if !self.logged_in.load(Ordering::Relaxed) => (3, "Not logged in".to_owned()),
+            (_, "SUBS" | "ADD" | "UNSUBS" | "VIEW") => {
+                let keys = params["keys"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|key| !key.is_empty())
+                    .map(str::to_owned);
+                let mut subscriptions = self.subscriptions.lock().unwrap();
+                let subscribed = subscriptions.entry(service.to_owned()).or_default();
+                match command {
+                    "SUBS" => *subscribed = keys.collect(),
+                    "ADD" => subscribed.extend(keys),
+                    "UNSUBS" => {
+                        for key in keys {
+                            subscribed.remove(&key);
+                        }
+                    }
+                    _ => {}
+                }
+                (0, format!("{command} command succeeded"))
+            }
+            _ => (22, format!("Unsupported command {service}/{command}")),
+        };
+        self.sender
+            .send(text(json!({
+                "response": [{
+                    "service": service,
+                    "command": command,
+                    "requestid": request_id,
+                    "SchwabClientCorrelId": request["SchwabClientCorrelId"],
+                    "timestamp": timestamp(),
+                    "content": { "code": code, "msg": msg },
+                }],
+            })))
+            .ok();
+    }
+}
+
+pub(crate) async fn handle_socket(socket: WebSocket, state: Arc<MockState>) {
+    let (mut ws_writer, mut ws_reader) = socket.split();
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let stream = Arc::new(StreamHandle {
+        sender,
+        logged_in: AtomicBool::new(false),
+        subscriptions: Mutex::new(FxHashMap::default()),
+    });
+    state.streams.lock().unwrap().push(stream.clone());
+    info!("Streamer connected");
+
+    let mut heartbeat = tokio::time::interval(Duration::from_millis(state.data.heartbeat_ms));
+    let mut replay_timer =
+        tokio::time::interval(Duration::from_millis(state.data.stream_interval_ms));
+    let mut next_frame = 0;
+    loop {
+        tokio::select! {
+            Some(message) = receiver.recv() => {
+                let is_close = matches!(message, Message::Close(_));
+                if ws_writer.send(message).await.is_err() || is_close {
+                    break;
+                }
+            }
+            message = ws_reader.next() => match message {
+                Some(Ok(Message::Text(message))) => {
+                    let Ok(request) = serde_json::from_str::<Value>(&message) else {
+                        continue;
+                    };
+                    match request.get("requests").and_then(Value::as_array) {
+                        Some(requests) => requests
+                            .iter()
+                            .for_each(|request| stream.handle_request(&state, request)),
+                        None => stream.handle_request(&state, &request),
+                    }
+                }
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                _ => {}
+            },
+            _ = heartbeat.tick() => {
+                if stream.logged_in.load(Ordering::Relaxed) {
+                    stream.sender
+                        .send(text(json!({ "notify": [{ "heartbeat": timestamp().to_string() }] })))
+                        .ok();
+                }
+            }
+            _ = replay_timer.tick(), if !state.data.stream.is_empty() => {
+                stream.send_data(&state.data.stream[next_frame % state.data.stream.len()]);
+                next_frame += 1;
+            }
+        }
+    }
+
+    state
+        .streams
+        .lock()
+        .unwrap()
+        .retain(|s| !Arc::ptr_eq(s, &stream));
+    info!("Streamer disconnected");
+}
@@ -11,6 +11,7 @@ use std::time::Instant;
 use ta_lib::volatility;
 use tracing::info;
 use util::format_big_num;
+use util::time::MarketTime;
 
 pub struct Chart {
     config: &'static ChartConfig,
@@ -51,7 +52,8 @@ impl Chart {
         Ok(())
     }
 
-    pub fn update(&mut self, candles: &[Candle], trend: Trend) {
+    /// Recomputes the chart and returns the divergence if a new one was found on this update.
+    pub fn update(&mut self, candles: &[Candle], trend: Trend) -> Option<Divergence> {
         self.aggregated = utils::aggregate(candles, self.config.timeframe);
         self.dataframe = DataFrame::from_candles(&self.aggregated);
 
@@ -61,8 +63,10 @@ impl Chart {
 
         self.dataframe = self.dataframe.trim_working_days(self.config.days);
 
-        if self.config.use_divergence {
-            self.compute_divergence(trend);
+        if self.config.use_divergence && self.compute_divergence(trend) {
+            self.divergences.last().cloned()
+        } else {
+            None
         }
     }
 
@@ -151,20 +155,24 @@ impl Chart {
         self.messages.push(prediction_msg);
     }
 
-    fn compute_divergence(&mut self, trend: Trend) {
+    fn compute_divergence(&mut self, trend: Trend) -> bool {
         if let Some(div) = find_divergence(trend, &self.dataframe, "rsi") {
+            let mut is_new = true;
             while let Some(last_div) = self.divergences.last()
                 && last_div.end > div.start
             {
+                is_new &= !(last_div.start == div.start && last_div.end == div.end);
                 self.divergences.pop();
             }
             self.divergences.push(div);
+            return is_new;
         } else if let Some(last_div) = self.divergences.last()
             && let Some(&last_idx) = self.dataframe.index().last()
             && last_div.end == last_idx
         {
             self.divergences.pop();
         }
+        false
     }
 
     pub fn atr(&self) -> Option<f64> {
@@ -195,6 +203,10 @@ impl Chart {
         Some(current_price - trade_start_price)
     }
 
+    pub fn timeframe(&self) -> i64 {
+        self.config.timeframe.num_seconds()
+    }
+
     pub fn rvol(&self) -> f64 {
         self.rvol
     }
@@ -230,7 +242,7 @@ impl Chart {
             .aggregated
             .iter()
             .enumerate()
-            .rfind(|(_idx, candle)| candle.time.date_naive() < last.time.date_naive())?;
+            .rfind(|(_idx, candle)| candle.time.market_date() < last.time.market_date())?;
         Some(end + 1)
     }
 }
@@ -1,25 +1,43 @@
 use super::chart::Chart;
 use super::dataframe::DataFrame;
+use super::order_flow::OrderFlow;
+use super::outcome::OutcomeTracker;
+use super::signal::{DivergenceSignal, LiquiditySignal, RejectionSignal, Signal};
 use super::support_resistance::{PriceRejection, check_resistance, check_support, threshold};
 use super::utils;
 
 use crate::analyzer::gap_fill::GapFill;
 use crate::websocket;
 use app_config::APP_CONFIG;
-use chrono::{DateTime, Duration, Local, NaiveDateTime};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
 use itertools::Itertools;
+use persist::outcome::SignalOutcome;
 use rand::{Rng, rng};
-use schwab_client::{Candle, Quote};
+use rustc_hash::FxHashMap;
+use schwab_client::{BookLevel, Candle, OrderBook, Quote, Trade};
 use serde::Serialize;
 use serde_json::json;
 use tracing::{debug, warn};
+use util::calendar::MarketCalendar;
+use util::time::MarketTime;
 
 pub struct Controller {
     symbol: String,
+    /// Hours rejections are looked for in, the nearly-24h session for the futures.
+    session: (NaiveTime, NaiveTime),
+    /// Timezone of `session`, that of the exchange the symbol trades on.
+    tz: Tz,
+    /// Futures trade the `session` around midnight, the equities close early on the half days.
+    is_futures: bool,
+    /// Whether the time & sales prints build the tick candles instead of the level one quotes.
+    use_time_sales: bool,
     candles: Vec<Candle>,
     charts: Vec<Chart>,
     trend: Trend,
     tick: Option<Candle>,
+    quote: Option<Quote>,
+    order_flow: OrderFlow,
     tick_published: DateTime<Local>,
     tick_publish_delay: Duration,
     price_levels_overriden: bool,
@@ -28,6 +46,12 @@ pub struct Controller {
     gap_fill: GapFill,
     rejection_msg: RejectionMessage,
     is_favorite: bool,
+    signals: Vec<Signal>,
+    outcome_tracker: OutcomeTracker,
+    outcomes: Vec<SignalOutcome>,
+    book_published: DateTime<Local>,
+    /// Large orders of the previous snapshot of each venue, the books of the venues alternate.
+    liquidity: FxHashMap<String, Vec<LiquiditySignal>>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
@@ -71,6 +95,7 @@ impl Controller {
         candles: Vec<Candle>,
         price_levels: Vec<PriceLevel>,
         is_favorite: bool,
+        use_time_sales: bool,
     ) -> Self {
         let charts = APP_CONFIG
             .trade_config
@@ -79,12 +104,30 @@ impl Controller {
             .map(|cf| Chart::new(&candles, cf))
             .collect::<Vec<_>>();
         let tick_publish_delay_ms = rng().random_range(5_000..15_000);
+        let is_futures = schwab_client::is_futures(&symbol);
+        let (session, tz) = if is_futures {
+            (
+                APP_CONFIG.trade_config.futures_hours,
+                util::time::exchange_tz(schwab_client::FUTURES_EXCHANGE),
+            )
+        } else {
+            (
+                APP_CONFIG.trade_config.trading_hours,
+                util::time::market_tz(),
+            )
+        };
         Self {
             symbol,
+            session,
+            tz,
+            is_futures,
+            use_time_sales,
             candles,
             charts,
             trend: Trend::None,
             tick: None,
+            quote: None,
+            order_flow: OrderFlow::default(),
             tick_published: util::time::now(),
             tick_publish_delay: Duration::milliseconds(tick_publish_delay_ms),
             price_levels_overriden: !price_levels.is_empty(),
@@ -100,6 +143,11 @@ impl Controller {
                 points: Vec::new(),
             },
             is_favorite,
+            signals: Vec::new(),
+            outcome_tracker: OutcomeTracker::new(session.1, tz, !is_futures),
+            outcomes: Vec::new(),
+            book_published: DateTime::default(),
+            liquidity: FxHashMap::default(),
         }
     }
 
@@ -107,6 +155,22 @@ impl Controller {
         &self.symbol
     }
 
+    /// Signals emitted by the most recent candle or tick update.
+    pub fn signals(&self) -> &[Signal] {
+        &self.signals
+    }
+
+    /// Outcomes of earlier rejection signals which got resolved by the most recent candle.
+    pub fn outcomes(&self) -> &[SignalOutcome] {
+        &self.outcomes
+    }
+
+    /// Resolves the signals still being followed, when the candles run out before their session
+    /// end.
+    pub fn close_outcomes(&mut self) -> Vec<SignalOutcome> {
+        self.outcome_tracker.close_all()
+    }
+
     pub fn train(&mut self) {
         for chart in &mut self.charts {
             if let Err(e) = chart.train() {
@@ -117,19 +181,44 @@ impl Controller {
 
     pub fn on_new_candle(&mut self, candle: Candle, publish: bool) {
         self.candles.push(candle);
+        self.outcomes = self.outcome_tracker.on_candle(&candle);
 
         self.update_charts(publish);
 
         self.tick = None; // Clear the temporary tick candles
         self.tick_published = util::time::now();
+        self.order_flow.reset();
     }
 
     pub fn on_tick(&mut self, quote: Quote) {
+        // Only what this quote produces, not the signals of the last candle again
+        self.signals.clear();
+        if self.use_time_sales {
+            // The prints build the tick candle, the quote only tells their aggressor side
+            self.quote = Some(quote);
+            return;
+        }
         let (last, volume, Some(time)) = (quote.last_price, quote.last_size, quote.trade_time)
         else {
             return;
         };
+        self.update_tick(last, volume, time);
+    }
 
+    /// Adds a time & sales print to the tick candle, unlike the level one quotes every trade is
+    /// counted exactly once.
+    pub fn on_trade(&mut self, trade: Trade) {
+        self.signals.clear();
+        let session = util::time::session_date(
+            trade.time.with_timezone(&self.tz).naive_local(),
+            self.session,
+        );
+        if self.order_flow.add(&trade, self.quote.as_ref(), session) {
+            self.update_tick(trade.price, trade.size, trade.time);
+        }
+    }
+
+    fn update_tick(&mut self, last: f64, volume: u64, time: DateTime<Local>) {
         let now = util::time::now();
         if let Some(last_tick) = &mut self.tick {
             last_tick.low = last_tick.low.min(last);
@@ -159,6 +248,67 @@ impl Controller {
         }
     }
 
+    /// Flags large size resting near the active price levels, only the orders which weren't in
+    /// the previous snapshot of the same venue become signals.
+    pub fn on_book(&mut self, book: OrderBook) {
+        self.signals.clear();
+        let config = &APP_CONFIG.trade_config.order_book;
+        let mut liquidity = Vec::new();
+        for level in self.price_levels.iter().filter(|level| level.is_active) {
+            let band = threshold(level.price);
+            for (trend, side) in [(Trend::Bullish, &book.bids), (Trend::Bearish, &book.asks)] {
+                let largest = large_orders(side, config.large_size_ratio)
+                    .filter(|order| (order.price - level.price).abs() <= band)
+                    .max_by_key(|order| order.size);
+                if let Some(order) = largest {
+                    liquidity.push(LiquiditySignal {
+                        symbol: self.symbol.clone(),
+                        trend,
+                        price_level: level.price,
+                        price: order.price,
+                        size: order.size,
+                        venue: book.venue.clone(),
+                        found_at: book.time,
+                    });
+                }
+            }
+        }
+        let previous = self.liquidity.entry(book.venue.clone()).or_default();
+        for signal in &liquidity {
+            let is_new = !previous.iter().any(|prev| {
+                (prev.trend, prev.price_level, prev.price)
+                    == (signal.trend, signal.price_level, signal.price)
+            });
+            if is_new {
+                debug!(
+                    "{}: {} resting at {:.2} near the price level {:.2}",
+                    self.symbol, signal.size, signal.price, signal.price_level
+                );
+                self.signals.push(Signal::Liquidity(signal.clone()));
+            }
+        }
+        *previous = liquidity;
+
+        let now = util::time::now();
+        if now - self.book_published >= Duration::seconds(1) {
+            self.book_published = now;
+            let data = json!({
+                "symbol": self.symbol,
+                "book": book.truncated(config.depth),
+                "liquidity": previous,
+            });
+            tokio::task::spawn_blocking(|| websocket::publish("ORDER_BOOK", data));
+        }
+    }
+
+    pub fn price_levels(&self) -> &[PriceLevel] {
+        &self.price_levels
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.is_favorite
+    }
+
     pub fn set_favorite(&mut self, favorite: bool) {
         self.is_favorite = favorite;
         self.publish();
@@ -182,15 +332,26 @@ impl Controller {
             "priceLevelsOverridden": self.price_levels_overriden,
             "rejection": self.rejection_msg,
             "isFavorite": self.is_favorite,
+            "orderFlow": self.order_flow,
             "charts": charts,
         });
         tokio::task::spawn_blocking(|| websocket::publish("UPDATE_CHART", data));
     }
 
     fn update_charts(&mut self, publish: bool) {
+        self.signals.clear();
         self.trend = utils::check_trend(&self.candles);
         for chart in &mut self.charts {
-            chart.update(&self.candles, self.trend);
+            if let Some(divergence) = chart.update(&self.candles, self.trend)
+                && let Some(last) = self.candles.last()
+            {
+                self.signals.push(Signal::Divergence(DivergenceSignal {
+                    symbol: self.symbol.clone(),
+                    timeframe: chart.timeframe(),
+                    found_at: last.time + Duration::seconds(last.duration),
+                    divergence,
+                }));
+            }
         }
 
         self.update_price_levels();
@@ -207,24 +368,27 @@ impl Controller {
         }
 
         const MIN_30: Duration = Duration::minutes(30);
-        let (th_start, th_end) = APP_CONFIG.trade_config.trading_hours;
-
+        let (th_start, _) = self.session;
         let last = self.candles.last().unwrap();
-        let candle_time = last.time.time() + Duration::seconds(last.duration);
-        if self.price_levels.is_empty()
-            || ((th_start - MIN_30) <= candle_time && candle_time < th_start)
-        {
+        let candle_time = (last.time + Duration::seconds(last.duration))
+            .with_timezone(&self.tz)
+            .time();
+        // Nothing trades in the break ahead of the futures session, its first half hour shows
+        // the gap instead of the pre-market
+        let refresh = if self.is_futures {
+            th_start <= candle_time && candle_time < th_start + MIN_30
+        } else {
+            (th_start - MIN_30) <= candle_time && candle_time < th_start
+        };
+        if self.price_levels.is_empty() || refresh {
             let candles = utils::aggregate(&self.candles, MIN_30);
 
             let data_frame = DataFrame::from_candles(&candles);
-            let df = data_frame.trim_working_days(1);
-            let regular_hours = df.filtered(|_, idx| {
-                idx.date() < last.time.date_naive() && idx.time() >= th_start && idx.time() < th_end
-            });
-            let extended_hours = df.filtered(|_, idx| {
-                (idx.date() < last.time.date_naive() && idx.time() >= th_end)
-                    || (idx.date() == last.time.date_naive() && idx.time() < th_start)
-            });
+            let (regular_hours, extended_hours) = if self.is_futures {
+                self.futures_sessions(&candles, &data_frame)
+            } else {
+                self.equity_sessions(&data_frame)
+            };
 
             if !self.price_levels_overriden {
                 let mut levels = Vec::new();
@@ -239,6 +403,50 @@ impl Controller {
         }
     }
 
+    /// Regular hours of the last trading day and the extended hours since.
+    fn equity_sessions(&self, data_frame: &DataFrame) -> (DataFrame, DataFrame) {
+        let (th_start, th_end) = self.session;
+        let th_end = |date| MarketCalendar::day(date).adjusted_close(th_end);
+        let df = data_frame.trim_working_days(1);
+        let today = self.candles.last().unwrap().time.market_date();
+        let regular_hours = df.filtered(|_, idx| {
+            idx.date() < today && idx.time() >= th_start && idx.time() < th_end(idx.date())
+        });
+        let extended_hours = df.filtered(|_, idx| {
+            (idx.date() < today && idx.time() >= th_end(idx.date()))
+                || (idx.date() == today && idx.time() < th_start)
+        });
+        (regular_hours, extended_hours)
+    }
+
+    /// The previous futures session and the current one so far, `candles` are the rows of
+    /// `data_frame`.
+    fn futures_sessions(
+        &self,
+        candles: &[Candle],
+        data_frame: &DataFrame,
+    ) -> (DataFrame, DataFrame) {
+        let trading_date = |time: DateTime<Local>| {
+            util::time::session_date(time.with_timezone(&self.tz).naive_local(), self.session)
+        };
+        let dates = candles.iter().map(|c| trading_date(c.time)).collect_vec();
+        let today = trading_date(self.candles.last().unwrap().time);
+        let prev_day = dates.iter().copied().filter(|d| *d < today).max();
+        let previous_session = data_frame.filtered(|i, _| Some(dates[i]) == prev_day);
+        let current_session = data_frame.filtered(|i, _| dates[i] == today);
+        (previous_session, current_session)
+    }
+
+    /// `session` on `date`, shortened on the half days for the equities.
+    fn session(&self, date: NaiveDate) -> (NaiveTime, NaiveTime) {
+        let (start, end) = self.session;
+        if !self.is_futures {
+            (start, MarketCalendar::day(date).adjusted_close(end))
+        } else {
+            (start, end)
+        }
+    }
+
     fn find_support_resistance(&mut self, trend: Trend) -> Option<()> {
         self.price_levels.iter_mut().for_each(|level| {
             level.is_active = false;
@@ -250,14 +458,22 @@ impl Controller {
         let prev_rej = self.rejection.take();
 
         let last = self.candles.last()?;
-        if last.time.date_naive() != self.rejection_msg.found_at.date_naive() {
+        if last.time.market_date() != self.rejection_msg.found_at.market_date() {
             self.rejection_msg.trend = Trend::None;
             self.rejection_msg.points.clear();
         }
 
         let cur_time = last.time + Duration::seconds(last.duration);
-        let (th_start, th_end) = APP_CONFIG.trade_config.trading_hours;
-        if cur_time.time() < th_start || cur_time.time() > th_end {
+        let exchange_time = cur_time.with_timezone(&self.tz);
+        let (th_start, th_end) = self.session(exchange_time.date_naive());
+        let time = exchange_time.time();
+        let in_session = if th_start <= th_end {
+            th_start <= time && time <= th_end
+        } else {
+            // Wraps around midnight
+            th_start <= time || time <= th_end
+        };
+        if !in_session {
             return None;
         }
 
@@ -297,23 +513,21 @@ impl Controller {
                 }
             })?;
 
-            let found_at = if let Some(prev_rej) = prev_rej {
-                if prev_rej.rejected_at.time == rejection.rejected_at.time {
-                    self.rejection_msg.found_at
-                } else {
-                    cur_time
-                }
-            } else {
+            let is_new = prev_rej
+                .is_none_or(|prev_rej| prev_rej.rejected_at.time != rejection.rejected_at.time);
+            let found_at = if is_new {
                 cur_time
+            } else {
+                self.rejection_msg.found_at
             };
             debug!(
                 "{}: {:?} support at price level {:.2}, low at: {}, imminent: {}, found at: {}",
                 self.symbol,
                 rejection.trend,
                 rejection.price_level,
-                rejection.rejected_at.time.time(),
+                rejection.rejected_at.time.market_time(),
                 rejection.is_imminent,
-                found_at.naive_local(),
+                found_at.market_naive(),
             );
             self.rejection_msg = RejectionMessage {
                 trend: rejection.trend,
@@ -323,6 +537,22 @@ impl Controller {
                 found_at,
                 points: Self::create_chart_points(&rejection, found_at),
             };
+            if is_new {
+                let signal = RejectionSignal {
+                    symbol: self.symbol.clone(),
+                    trend: rejection.trend,
+                    price_level: rejection.price_level,
+                    is_imminent: rejection.is_imminent,
+                    is_gap_fill,
+                    found_at,
+                    rejected_at: rejection.rejected_at,
+                    close: rejection.now.close,
+                    atr,
+                    points: self.rejection_msg.points.clone(),
+                };
+                self.outcome_tracker.track(&signal);
+                self.signals.push(Signal::Rejection(signal));
+            }
             self.rejection = Some(rejection);
         }
         Some(())
@@ -354,3 +584,15 @@ impl Controller {
         ]
     }
 }
+
+/// Book levels holding `ratio` times the median size of their side.
+fn large_orders(side: &[BookLevel], ratio: f64) -> impl Iterator<Item = &BookLevel> {
+    let sizes = side
+        .iter()
+        .map(|level| level.size)
+        .sorted()
+        .collect::<Vec<_>>();
+    let median = sizes.get(sizes.len() / 2).copied().unwrap_or_default();
+    side.iter()
+        .filter(move |level| median > 0 && level.size as f64 >= median as f64 * ratio)
+}
@@ -6,7 +6,7 @@ use std::fmt::{Debug, Display, Write};
 
 use rustc_hash::FxHashMap;
 use std::ops::Index;
-use util::time::TradingDay;
+use util::time::{MarketTime, TradingDay};
 
 #[derive(Clone)]
 pub struct DataFrame {
@@ -38,7 +38,7 @@ impl DataFrame {
         let mut closes = Vec::with_capacity(candles.len());
         let mut volumes = Vec::with_capacity(candles.len());
         for candle in candles {
-            index.push(candle.time.naive_local());
+            index.push(candle.time.market_naive());
             opens.push(candle.open);
             lows.push(candle.low);
             highs.push(candle.high);
@@ -91,7 +91,6 @@ impl DataFrame {
     }
 
     pub fn trim_working_days(&self, days: usize) -> Self {
-        let min_working_hours = util::time::regular_trading_hours();
         let work_days = self
             .index
             .iter()
@@ -107,7 +106,7 @@ impl DataFrame {
             .into_iter()
             .filter(|(key, ..)| key.is_trading_day())
             .map(|(key, (min, max))| (key, max - min))
-            .filter(|(_, diff)| *diff >= min_working_hours)
+            .filter(|(key, diff)| *diff >= util::time::regular_trading_hours(*key))
             .map(|(key, _)| key)
             .sorted()
             .collect::<Vec<_>>();
@@ -3,8 +3,9 @@ use crate::analyzer::dataframe::DataFrame;
 use crate::analyzer::utils;
 
 use chrono::NaiveDateTime;
+use serde::Serialize;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Divergence {
     pub trend: Trend,
     pub start: NaiveDateTime,
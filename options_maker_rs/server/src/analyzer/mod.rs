@@ -3,23 +3,33 @@ mod controller;
 mod dataframe;
 mod divergence;
 mod gap_fill;
+mod order_flow;
+mod outcome;
+mod signal;
+mod spreads;
 mod support_resistance;
 mod utils;
 mod volume;
 
-use crate::websocket;
+use crate::{notifier, options, websocket};
 use app_config::APP_CONFIG;
-use controller::{Controller, PriceLevel};
+pub use controller::{Controller, PriceLevel, Trend};
 use data_provider::provider;
+pub use outcome::{OutcomeStats, OutcomeSummary};
+use persist::outcome::SignalOutcome;
+use persist::signal::SignalRecord;
 use rustc_hash::FxHashMap;
-use schwab_client::Instrument;
 use schwab_client::streaming_client::StreamResponse;
+use schwab_client::{Instrument, Trade};
+pub use signal::{DivergenceSignal, LiquiditySignal, RejectionSignal, Signal};
+pub use spreads::{SpreadBuilder, SpreadCandidate, SpreadKind, SpreadLeg, StrikeTarget};
 
 use futures::{StreamExt, stream};
 use std::sync::OnceLock;
 use std::time::Instant;
 use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, info, warn};
+use util::time::MarketTime;
 
 static CMD_SENDER: OnceLock<mpsc::UnboundedSender<AnalyzerCmd>> = OnceLock::new();
 
@@ -27,6 +37,7 @@ pub enum AnalyzerCmd {
     Publish,
     ReInitialize(Box<Controller>),
     IsTickerPresent(String, oneshot::Sender<bool>),
+    PriceLevels(String, oneshot::Sender<Option<Vec<PriceLevel>>>),
     Remove(String),
     SetFavorite(String, bool),
 }
@@ -56,35 +67,90 @@ pub async fn start_analysis() -> anyhow::Result<()> {
         .await;
     provider().sub_charts(controllers.keys().cloned().collect());
 
-    let use_tick_data = APP_CONFIG.trade_config.use_tick_data;
+    let use_time_sales = APP_CONFIG.trade_config.use_time_sales;
+    // The prints take their aggressor side from the level one quotes
+    let use_tick_data = APP_CONFIG.trade_config.use_tick_data || use_time_sales;
     if use_tick_data {
         info!("Subscribing to tick data for all the equities");
-        provider().sub_tick(controllers.keys().cloned().collect());
+        data_provider::sub_tick(controllers.keys().cloned().collect());
+    }
+    if use_time_sales {
+        info!("Subscribing to time & sales for all the equities");
+        provider().sub_trades(controllers.keys().cloned().collect());
+    }
+    let save_trades = APP_CONFIG.trade_config.save_trades;
+    let trades_writer = start_trades_writer();
+    let use_order_book = APP_CONFIG.trade_config.order_book.enabled;
+    if use_order_book {
+        info!("Subscribing to the order books of all the symbols");
+        provider().sub_book(controllers.keys().cloned().collect());
     }
 
     tokio::spawn(async move {
         loop {
             tokio::select! {
                 Some(stream_res) = stream_listener.recv() => match stream_res {
-                    StreamResponse::Equity { symbol,candle } => {
+                    StreamResponse::Equity { symbol, candle }
+                    | StreamResponse::Futures { symbol, candle } => {
                         if let Some(controller) = controllers.get_mut(&symbol) {
                             let start = Instant::now();
                             controller.on_new_candle(candle, true);
                             debug!("Processed new candle for {} in {:.2?}", symbol, start.elapsed());
+                            save_signals(controller.signals());
+                            save_outcomes(controller.outcomes().to_vec());
+                            if controller.is_favorite() {
+                                notifier::notify(controller.signals());
+                            }
                         } else {
                             warn!("Unexpected chart candle received for {symbol}");
                         }
                     }
-                    StreamResponse::EquityLevelOne { symbol,quote } => {
+                    StreamResponse::EquityLevelOne { symbol, quote }
+                    | StreamResponse::FuturesLevelOne { symbol, quote } => {
                         if let Some(controller) = controllers.get_mut(&symbol) {
                             let start = Instant::now();
                             controller.on_tick(quote);
+                            save_signals(controller.signals());
+                            if controller.is_favorite() {
+                                notifier::notify(controller.signals());
+                            }
                             debug!("Processed new tick for {} in {:.2?}", symbol, start.elapsed());
                         } else {
-                            warn!("Unexpected tick received for {symbol}");
+                            debug!("Ignoring tick of untracked symbol {symbol}");
+                        }
+                    }
+                    StreamResponse::TimeSale { symbol, trade } => {
+                        if let Some(controller) = controllers.get_mut(&symbol) {
+                            controller.on_trade(trade);
+                            save_signals(controller.signals());
+                            if controller.is_favorite() {
+                                notifier::notify(controller.signals());
+                            }
+                            if save_trades {
+                                trades_writer.send((symbol, trade)).ok();
+                            }
+                        } else {
+                            debug!("Ignoring print of untracked symbol {symbol}");
+                        }
+                    }
+                    StreamResponse::OptionsLevelOne { symbol, quote } => {
+                        options::on_quote(symbol, quote);
+                    }
+                    StreamResponse::OrderFill { .. }
+                    | StreamResponse::OrderCancel { .. }
+                    | StreamResponse::PositionChange { .. }
+                    | StreamResponse::OrderStatus { .. } => {
+                        // Account activity is followed by the brokerage
+                    }
+                    StreamResponse::Book { symbol, book } => {
+                        if let Some(controller) = controllers.get_mut(&symbol) {
+                            controller.on_book(book);
+                            save_signals(controller.signals());
+                            if controller.is_favorite() {
+                                notifier::notify(controller.signals());
+                            }
                         }
                     }
-                    _ => { }
                 },
                 Some(cmd) = cmd_recv.recv() => match cmd {
                     AnalyzerCmd::Publish => {
@@ -97,20 +163,37 @@ pub async fn start_analysis() -> anyhow::Result<()> {
                         let symbol = ctr.symbol().to_owned();
                         info!("Resetting the controller of {symbol}");
                         ctr.publish();
-                        controllers.insert(symbol.to_owned(), *ctr);
-                        if use_tick_data {
-                            provider().sub_tick(vec![symbol.clone()]);
+                        // Reloading a tracked symbol doesn't add another interest in its quotes
+                        let is_new = controllers.insert(symbol.to_owned(), *ctr).is_none();
+                        if use_tick_data && is_new {
+                            data_provider::sub_tick(vec![symbol.clone()]);
+                        }
+                        if use_time_sales {
+                            provider().sub_trades(vec![symbol.clone()]);
+                        }
+                        if use_order_book {
+                            provider().sub_book(vec![symbol.clone()]);
                         }
                         provider().sub_charts(vec![symbol]);
                     }
                     AnalyzerCmd::IsTickerPresent(ticker, sender) => {
                         sender.send(controllers.contains_key(&ticker)).ok();
                     }
+                    AnalyzerCmd::PriceLevels(ticker, sender) => {
+                        let levels = controllers.get(&ticker).map(|c| c.price_levels().to_vec());
+                        sender.send(levels).ok();
+                    }
                     AnalyzerCmd::Remove(symbol) => {
                         if let Some(_ctr) = controllers.remove(&symbol) {
                             info!("Removing controller for {symbol}");
                             if use_tick_data {
-                                provider().unsub_tick(vec![symbol.clone()]);
+                                data_provider::unsub_tick(vec![symbol.clone()]);
+                            }
+                            if use_time_sales {
+                                provider().unsub_trades(vec![symbol.clone()]);
+                            }
+                            if use_order_book {
+                                provider().unsub_book(vec![symbol.clone()]);
                             }
                             provider().unsub_charts(vec![symbol]);
                         } else {
@@ -153,7 +236,7 @@ pub async fn init_controller(instrument: &Instrument) -> anyhow::Result<Controll
     let price_levels = persist::price_level::fetch_price_levels(&instrument.symbol)
         .await?
         .into_iter()
-        .map(|(price, at)| PriceLevel::new(price, at))
+        .map(|(price, at)| PriceLevel::new(price, at.market_naive()))
         .collect::<Vec<_>>();
     if !price_levels.is_empty() {
         info!(
@@ -163,9 +246,18 @@ pub async fn init_controller(instrument: &Instrument) -> anyhow::Result<Controll
         );
     }
     let is_favorite = persist::groups::is_favorite(&instrument.symbol).await?;
+    let use_time_sales =
+        APP_CONFIG.trade_config.use_time_sales && provider().streams_trades(&instrument.symbol);
     let symbol = instrument.symbol.clone();
+    // The warm-up replays history, only the signals of the live updates are saved
     let controller = tokio::task::spawn_blocking(move || {
-        let mut controller = Controller::new(symbol, base_candles, price_levels, is_favorite);
+        let mut controller = Controller::new(
+            symbol,
+            base_candles,
+            price_levels,
+            is_favorite,
+            use_time_sales,
+        );
         controller.train();
         for candle in update_candles {
             controller.on_new_candle(candle, false);
@@ -181,6 +273,48 @@ pub async fn init_controller(instrument: &Instrument) -> anyhow::Result<Controll
     Ok(controller)
 }
 
+/// Signals and outcomes are an audit of what happened live, nothing replayed is saved.
+fn save_signals(signals: &[Signal]) {
+    if signals.is_empty() || APP_CONFIG.replay_mode {
+        return;
+    }
+
+    let records = signals.iter().map(SignalRecord::from).collect::<Vec<_>>();
+    tokio::spawn(async move {
+        if let Err(e) = persist::signal::save_signals(&records).await {
+            warn!("Failed to save {} signals: {e}", records.len());
+        }
+    });
+}
+
+/// Prints arrive far too often to be saved one by one, the writer saves whatever queued up
+/// while the previous batch was being written.
+fn start_trades_writer() -> mpsc::UnboundedSender<(String, Trade)> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut batch = Vec::new();
+        while receiver.recv_many(&mut batch, 10_000).await > 0 {
+            if let Err(e) = persist::trades::save_trades(&batch).await {
+                warn!("Failed to save {} trades: {e}", batch.len());
+            }
+            batch.clear();
+        }
+    });
+    sender
+}
+
+fn save_outcomes(outcomes: Vec<SignalOutcome>) {
+    if outcomes.is_empty() || APP_CONFIG.replay_mode {
+        return;
+    }
+
+    tokio::spawn(async move {
+        if let Err(e) = persist::outcome::save_outcomes(&outcomes).await {
+            warn!("Failed to save {} signal outcomes: {e}", outcomes.len());
+        }
+    });
+}
+
 pub fn send_analyzer_cmd(cmd: AnalyzerCmd) {
     if let Some(sender) = CMD_SENDER.get() {
         sender.send(cmd).ok();
@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use schwab_client::{Quote, Trade};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum Aggressor {
+    Buyer,
+    Seller,
+}
+
+/// Volume of the current candle split by the side which crossed the spread.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderFlow {
+    pub buy_volume: u64,
+    pub sell_volume: u64,
+    #[serde(skip)]
+    last_trade: Option<(f64, Aggressor)>,
+    /// The sequence numbers restart with every session.
+    #[serde(skip)]
+    last_sequence: (NaiveDate, u64),
+}
+
+impl OrderFlow {
+    /// Adds the print of the `session` date unless it was already seen, returns whether it was
+    /// added.
+    pub fn add(&mut self, trade: &Trade, quote: Option<&Quote>, session: NaiveDate) -> bool {
+        let sequence = (session, trade.sequence);
+        if trade.sequence != 0 && sequence <= self.last_sequence {
+            return false;
+        }
+        self.last_sequence = sequence;
+
+        let aggressor = classify(trade.price, quote, self.last_trade);
+        match aggressor {
+            Aggressor::Buyer => self.buy_volume += trade.size,
+            Aggressor::Seller => self.sell_volume += trade.size,
+        }
+        self.last_trade = Some((trade.price, aggressor));
+        true
+    }
+
+    /// Starts over with the next candle, keeping what's needed to classify its first print.
+    pub fn reset(&mut self) {
+        self.buy_volume = 0;
+        self.sell_volume = 0;
+    }
+}
+
+/// Quote rule first: a print at or through the ask was bought, at or through the bid sold.
+/// Inside the spread, or without a quote, the tick rule compares it with the previous print.
+fn classify(price: f64, quote: Option<&Quote>, last_trade: Option<(f64, Aggressor)>) -> Aggressor {
+    if let Some(quote) = quote.filter(|q| q.bid_price > 0.0 && q.ask_price >= q.bid_price) {
+        if price >= quote.ask_price {
+            return Aggressor::Buyer;
+        }
+        if price <= quote.bid_price {
+            return Aggressor::Seller;
+        }
+    }
+    match last_trade {
+        Some((last_price, _)) if price > last_price => Aggressor::Buyer,
+        Some((last_price, _)) if price < last_price => Aggressor::Seller,
+        Some((_, aggressor)) => aggressor,
+        None => Aggressor::Buyer,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OrderFlow;
+    use chrono::NaiveDate;
+    use schwab_client::{Quote, Trade};
+
+    fn trade(price: f64, size: u64, sequence: u64) -> Trade {
+        Trade {
+            price,
+            size,
+            time: util::time::now(),
+            sequence,
+        }
+    }
+
+    #[test]
+    fn test_order_flow() {
+        let quote = Quote {
+            bid_price: 100.0,
+            ask_price: 100.1,
+            ..Quote::default()
+        };
+        let day = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        let mut flow = OrderFlow::default();
+        assert!(flow.add(&trade(100.1, 300, 1), Some(&quote), day));
+        assert!(flow.add(&trade(100.0, 200, 2), Some(&quote), day));
+        // Inside the spread it's an uptick from the last print
+        assert!(flow.add(&trade(100.05, 100, 3), Some(&quote), day));
+        // Zero tick keeps the side of the previous print
+        assert!(flow.add(&trade(100.05, 50, 4), None, day));
+        assert!(!flow.add(&trade(100.05, 50, 4), None, day));
+        assert_eq!((flow.buy_volume, flow.sell_volume), (450, 200));
+
+        flow.reset();
+        assert!(flow.add(&trade(100.0, 10, 5), None, day));
+        assert_eq!((flow.buy_volume, flow.sell_volume), (0, 10));
+    }
+
+    #[test]
+    fn test_sequence_restarts_next_day() {
+        let day = NaiveDate::from_ymd_opt(2025, 7, 1).unwrap();
+        let next_day = day.succ_opt().unwrap();
+        let mut flow = OrderFlow::default();
+        assert!(flow.add(&trade(100.0, 10, 900), None, day));
+        assert!(flow.add(&trade(100.0, 10, 901), None, day));
+
+        flow.reset();
+        assert!(flow.add(&trade(100.0, 20, 1), None, next_day));
+        assert!(flow.add(&trade(100.0, 30, 2), None, next_day));
+        assert!(!flow.add(&trade(100.0, 30, 2), None, next_day));
+        // A late print of the previous session is a duplicate
+        assert!(!flow.add(&trade(100.0, 10, 902), None, day));
+        assert_eq!(flow.buy_volume, 50);
+    }
+}
@@ -0,0 +1,394 @@
+use super::controller::Trend;
+use super::signal::RejectionSignal;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use persist::outcome::SignalOutcome;
+use schwab_client::Candle;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use util::calendar::MarketCalendar;
+
+/// Follows rejection signals forward, candle by candle, until they hit the stop at the rejected
+/// candle's extreme, reach the 2R target or run out of session.
+pub struct OutcomeTracker {
+    session_end: NaiveTime,
+    tz: Tz,
+    early_closes: bool,
+    open: Vec<OpenSignal>,
+}
+
+struct OpenSignal {
+    signal: RejectionSignal,
+    stop: f64,
+    risk: f64,
+    mfe: f64,
+    mae: f64,
+    last_close: f64,
+    last_time: DateTime<Local>,
+    session_end: DateTime<Local>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct OutcomeStats {
+    pub count: usize,
+    pub win_rate: f64,
+    pub hit_1r_rate: f64,
+    pub hit_2r_rate: f64,
+    pub stop_rate: f64,
+    pub expectancy: f64,
+    pub avg_mfe_atr: f64,
+    pub avg_mae_atr: f64,
+    pub avg_minutes: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutcomeSummary {
+    pub overall: OutcomeStats,
+    pub by_symbol: BTreeMap<String, OutcomeStats>,
+    pub by_trend: BTreeMap<String, OutcomeStats>,
+    pub by_level: BTreeMap<String, OutcomeStats>,
+}
+
+impl OutcomeTracker {
+    /// Signals are followed until `session_end`, a wall clock time of `tz`. With `early_closes`
+    /// it's moved up on the half days of the market calendar.
+    pub fn new(session_end: NaiveTime, tz: Tz, early_closes: bool) -> Self {
+        Self {
+            session_end,
+            tz,
+            early_closes,
+            open: Vec::new(),
+        }
+    }
+
+    pub fn track(&mut self, signal: &RejectionSignal) {
+        let stop = if signal.trend == Trend::Bullish {
+            signal.rejected_at.low
+        } else {
+            signal.rejected_at.high
+        };
+        let risk = (signal.close - stop).abs();
+        if risk <= 0.0 || signal.atr <= 0.0 {
+            return;
+        }
+
+        self.open.push(OpenSignal {
+            signal: signal.clone(),
+            stop,
+            risk,
+            mfe: 0.0,
+            mae: 0.0,
+            last_close: signal.close,
+            session_end: self.next_session_end(signal.found_at),
+            last_time: signal.found_at,
+        });
+    }
+
+    /// Feeds a new candle to every open signal and returns the ones which got resolved by it.
+    pub fn on_candle(&mut self, candle: &Candle) -> Vec<SignalOutcome> {
+        let candle_end = candle.time + Duration::seconds(candle.duration);
+        let mut resolved = Vec::new();
+        self.open.retain_mut(|open| {
+            if candle_end <= open.signal.found_at {
+                return true;
+            }
+            if candle.time >= open.session_end {
+                let (close, time) = (open.last_close, open.last_time);
+                resolved.push(open.resolve(close, time, false));
+                return false;
+            }
+
+            let is_bullish = open.signal.trend == Trend::Bullish;
+            let entry = open.signal.close;
+            let (favorable, adverse) = if is_bullish {
+                (candle.high - entry, entry - candle.low)
+            } else {
+                (entry - candle.low, candle.high - entry)
+            };
+            open.mae = open.mae.max(adverse);
+            open.last_close = candle.close;
+            open.last_time = candle_end;
+            if adverse >= open.risk {
+                // When a candle spans both stop and target, the stop is assumed to be hit first
+                resolved.push(open.resolve(open.stop, candle_end, true));
+                return false;
+            }
+
+            open.mfe = open.mfe.max(favorable);
+            if open.mfe >= 2.0 * open.risk {
+                let target = if is_bullish {
+                    entry + 2.0 * open.risk
+                } else {
+                    entry - 2.0 * open.risk
+                };
+                resolved.push(open.resolve(target, candle_end, false));
+                false
+            } else if candle_end >= open.session_end {
+                resolved.push(open.resolve(candle.close, candle_end, false));
+                false
+            } else {
+                true
+            }
+        });
+        resolved
+    }
+
+    /// Resolves every open signal at its last close, for when no more candles are coming.
+    pub fn close_all(&mut self) -> Vec<SignalOutcome> {
+        self.open
+            .drain(..)
+            .map(|open| open.resolve(open.last_close, open.last_time, false))
+            .collect()
+    }
+}
+
+impl OutcomeTracker {
+    /// First session end in `tz` after `found_at`, the next day's for sessions which wrap around
+    /// midnight. The next day is taken by the calendar, it isn't always 24 hours away.
+    fn next_session_end(&self, found_at: DateTime<Local>) -> DateTime<Local> {
+        let session_end = |date: NaiveDate| {
+            let end = if self.early_closes {
+                MarketCalendar::day(date).adjusted_close(self.session_end)
+            } else {
+                self.session_end
+            };
+            util::time::from_wall_clock(self.tz, date.and_time(end))
+        };
+        let date = found_at.with_timezone(&self.tz).date_naive();
+        let end = session_end(date);
+        if end > found_at {
+            return end;
+        }
+        session_end(date.succ_opt().expect("date out of range"))
+    }
+}
+
+impl OpenSignal {
+    fn resolve(&self, exit: f64, resolved_at: DateTime<Local>, stopped: bool) -> SignalOutcome {
+        let signal = &self.signal;
+        let pnl = if signal.trend == Trend::Bullish {
+            exit - signal.close
+        } else {
+            signal.close - exit
+        };
+        SignalOutcome {
+            symbol: signal.symbol.clone(),
+            trend: format!("{:?}", signal.trend),
+            is_gap_fill: signal.is_gap_fill,
+            price_level: signal.price_level,
+            found_at: signal.found_at,
+            entry: signal.close,
+            stop: self.stop,
+            atr: signal.atr,
+            mfe_atr: self.mfe / signal.atr,
+            mae_atr: self.mae / signal.atr,
+            hit_1r: self.mfe >= self.risk,
+            hit_2r: self.mfe >= 2.0 * self.risk,
+            stopped,
+            r_multiple: pnl / self.risk,
+            resolved_at,
+        }
+    }
+}
+
+impl OutcomeStats {
+    pub fn new<'a>(outcomes: impl IntoIterator<Item = &'a SignalOutcome>) -> Self {
+        let mut stats = Self::default();
+        for outcome in outcomes {
+            stats.count += 1;
+            stats.win_rate += (outcome.r_multiple > 0.0) as u8 as f64;
+            stats.hit_1r_rate += outcome.hit_1r as u8 as f64;
+            stats.hit_2r_rate += outcome.hit_2r as u8 as f64;
+            stats.stop_rate += outcome.stopped as u8 as f64;
+            stats.expectancy += outcome.r_multiple;
+            stats.avg_mfe_atr += outcome.mfe_atr;
+            stats.avg_mae_atr += outcome.mae_atr;
+            stats.avg_minutes +=
+                (outcome.resolved_at - outcome.found_at).num_seconds() as f64 / 60.0;
+        }
+        if stats.count > 0 {
+            let count = stats.count as f64;
+            stats.win_rate /= count;
+            stats.hit_1r_rate /= count;
+            stats.hit_2r_rate /= count;
+            stats.stop_rate /= count;
+            stats.expectancy /= count;
+            stats.avg_mfe_atr /= count;
+            stats.avg_mae_atr /= count;
+            stats.avg_minutes /= count;
+        }
+        stats
+    }
+}
+
+impl OutcomeSummary {
+    pub fn new(outcomes: &[SignalOutcome]) -> Self {
+        fn _group_by(
+            outcomes: &[SignalOutcome],
+            key: impl Fn(&SignalOutcome) -> String,
+        ) -> BTreeMap<String, OutcomeStats> {
+            let mut groups = BTreeMap::<String, Vec<&SignalOutcome>>::new();
+            for outcome in outcomes {
+                groups.entry(key(outcome)).or_default().push(outcome);
+            }
+            groups
+                .into_iter()
+                .map(|(key, group)| (key, OutcomeStats::new(group)))
+                .collect()
+        }
+
+        Self {
+            overall: OutcomeStats::new(outcomes),
+            by_symbol: _group_by(outcomes, |o| o.symbol.clone()),
+            by_trend: _group_by(outcomes, |o| o.trend.clone()),
+            by_level: _group_by(outcomes, |o| {
+                if o.is_gap_fill { "GapFill" } else { "Regular" }.to_owned()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OutcomeStats, OutcomeTracker};
+    use crate::analyzer::controller::Trend;
+    use crate::analyzer::signal::RejectionSignal;
+    use chrono::{DateTime, Local, NaiveTime, TimeZone};
+    use chrono_tz::America::New_York;
+    use schwab_client::Candle;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        New_York
+            .with_ymd_and_hms(2025, 7, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    fn candle(minute: u32, open: f64, low: f64, high: f64, close: f64) -> Candle {
+        Candle {
+            open,
+            low,
+            high,
+            close,
+            volume: 1000,
+            time: at(1, 10, minute),
+            duration: 60,
+        }
+    }
+
+    fn bullish_signal() -> RejectionSignal {
+        RejectionSignal {
+            symbol: "SPY".to_owned(),
+            trend: Trend::Bullish,
+            price_level: 100.0,
+            is_imminent: true,
+            is_gap_fill: false,
+            found_at: at(1, 10, 1),
+            rejected_at: candle(0, 100.5, 99.0, 101.0, 100.8),
+            close: 101.0,
+            atr: 1.0,
+            points: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_target_hit() {
+        let mut tracker =
+            OutcomeTracker::new(NaiveTime::from_hms_opt(16, 0, 0).unwrap(), New_York, true);
+        tracker.track(&bullish_signal());
+
+        assert!(
+            tracker
+                .on_candle(&candle(0, 100.5, 99.0, 101.0, 100.8))
+                .is_empty()
+        );
+        assert!(
+            tracker
+                .on_candle(&candle(1, 101.0, 100.5, 103.5, 103.0))
+                .is_empty()
+        );
+        let outcomes = tracker.on_candle(&candle(2, 103.0, 102.5, 105.2, 105.0));
+        assert_eq!(outcomes.len(), 1);
+        let outcome = &outcomes[0];
+        assert!(outcome.hit_1r && outcome.hit_2r && !outcome.stopped);
+        assert_eq!(outcome.r_multiple, 2.0);
+        assert_eq!(outcome.mae_atr, 0.5);
+    }
+
+    #[test]
+    fn test_stop_hit() {
+        let mut tracker =
+            OutcomeTracker::new(NaiveTime::from_hms_opt(16, 0, 0).unwrap(), New_York, true);
+        tracker.track(&bullish_signal());
+
+        let outcomes = tracker.on_candle(&candle(1, 101.0, 98.5, 105.5, 99.0));
+        assert_eq!(outcomes.len(), 1);
+        let outcome = &outcomes[0];
+        assert!(outcome.stopped && !outcome.hit_1r);
+        assert_eq!(outcome.r_multiple, -1.0);
+
+        let stats = OutcomeStats::new(&outcomes);
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.expectancy, -1.0);
+        assert_eq!(stats.stop_rate, 1.0);
+    }
+
+    #[test]
+    fn test_overnight_session() {
+        // A futures session closing at 09:00 keeps the signal open past midnight
+        let mut tracker =
+            OutcomeTracker::new(NaiveTime::from_hms_opt(9, 0, 0).unwrap(), New_York, false);
+        tracker.track(&bullish_signal());
+
+        let mut late = candle(30, 101.0, 100.5, 101.5, 101.2);
+        late.time = at(1, 23, 59);
+        assert!(tracker.on_candle(&late).is_empty());
+
+        let mut close = candle(0, 101.2, 100.8, 101.6, 101.5);
+        close.time = at(2, 8, 59);
+        let outcomes = tracker.on_candle(&close);
+        assert_eq!(outcomes.len(), 1);
+        assert!(!outcomes[0].stopped && !outcomes[0].hit_1r);
+        assert_eq!(outcomes[0].r_multiple, 0.25);
+    }
+
+    #[test]
+    fn test_close_all() {
+        let mut tracker =
+            OutcomeTracker::new(NaiveTime::from_hms_opt(16, 0, 0).unwrap(), New_York, true);
+        tracker.track(&bullish_signal());
+        assert!(
+            tracker
+                .on_candle(&candle(1, 101.0, 100.5, 101.5, 101.5))
+                .is_empty()
+        );
+
+        let outcomes = tracker.close_all();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].r_multiple, 0.25);
+        assert_eq!(outcomes[0].resolved_at, at(1, 10, 2));
+        assert!(tracker.close_all().is_empty());
+    }
+
+    #[test]
+    fn test_half_day_close() {
+        // The day after Thanksgiving closes at 13:00
+        let mut tracker =
+            OutcomeTracker::new(NaiveTime::from_hms_opt(16, 0, 0).unwrap(), New_York, true);
+        let half_day = |hour, minute| {
+            New_York
+                .with_ymd_and_hms(2025, 11, 28, hour, minute, 0)
+                .unwrap()
+                .with_timezone(&Local)
+        };
+        let mut signal = bullish_signal();
+        signal.found_at = half_day(12, 30);
+        tracker.track(&signal);
+
+        let mut last = candle(0, 101.0, 100.5, 101.5, 101.2);
+        last.time = half_day(12, 59);
+        let outcomes = tracker.on_candle(&last);
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].resolved_at, half_day(13, 0));
+    }
+}
@@ -0,0 +1,201 @@
+use super::controller::Trend;
+use super::divergence::Divergence;
+use super::utils;
+use chrono::{DateTime, Local};
+use persist::signal::SignalRecord;
+use schwab_client::Candle;
+use serde::Serialize;
+
+/// A trade signal emitted by a [`super::Controller`] while processing a candle or tick.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind")]
+pub enum Signal {
+    Rejection(RejectionSignal),
+    Divergence(DivergenceSignal),
+    Liquidity(LiquiditySignal),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RejectionSignal {
+    pub symbol: String,
+    pub trend: Trend,
+    pub price_level: f64,
+    pub is_imminent: bool,
+    pub is_gap_fill: bool,
+    pub found_at: DateTime<Local>,
+    pub rejected_at: Candle,
+    pub close: f64,
+    pub atr: f64,
+    pub points: Vec<(i64, f64)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DivergenceSignal {
+    pub symbol: String,
+    pub timeframe: i64,
+    pub found_at: DateTime<Local>,
+    #[serde(flatten)]
+    pub divergence: Divergence,
+}
+
+/// Unusually large size resting in the book near an active price level, bids backing a support
+/// are bullish and asks capping a resistance bearish.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LiquiditySignal {
+    pub symbol: String,
+    pub trend: Trend,
+    pub price_level: f64,
+    pub price: f64,
+    pub size: u64,
+    pub venue: String,
+    pub found_at: DateTime<Local>,
+}
+
+impl Signal {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Signal::Rejection(_) => "Rejection",
+            Signal::Divergence(_) => "Divergence",
+            Signal::Liquidity(_) => "Liquidity",
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            Signal::Rejection(rejection) => &rejection.symbol,
+            Signal::Divergence(divergence) => &divergence.symbol,
+            Signal::Liquidity(liquidity) => &liquidity.symbol,
+        }
+    }
+
+    pub fn found_at(&self) -> DateTime<Local> {
+        match self {
+            Signal::Rejection(rejection) => rejection.found_at,
+            Signal::Divergence(divergence) => divergence.found_at,
+            Signal::Liquidity(liquidity) => liquidity.found_at,
+        }
+    }
+}
+
+impl From<&Signal> for SignalRecord {
+    fn from(signal: &Signal) -> Self {
+        match signal {
+            Signal::Rejection(rejection) => SignalRecord {
+                symbol: rejection.symbol.clone(),
+                kind: signal.kind().to_owned(),
+                trend: format!("{:?}", rejection.trend),
+                price_level: rejection.price_level,
+                found_at: rejection.found_at,
+                points: rejection.points.clone(),
+                is_gap_fill: rejection.is_gap_fill,
+                is_imminent: rejection.is_imminent,
+            },
+            Signal::Divergence(div) => SignalRecord {
+                symbol: div.symbol.clone(),
+                kind: signal.kind().to_owned(),
+                trend: format!("{:?}", div.divergence.trend),
+                price_level: div.divergence.end_price,
+                found_at: div.found_at,
+                points: vec![
+                    (
+                        div.divergence.start.and_utc().timestamp(),
+                        div.divergence.start_price,
+                    ),
+                    (
+                        div.divergence.end.and_utc().timestamp(),
+                        div.divergence.end_price,
+                    ),
+                ],
+                is_gap_fill: false,
+                is_imminent: false,
+            },
+            Signal::Liquidity(liquidity) => SignalRecord {
+                symbol: liquidity.symbol.clone(),
+                kind: signal.kind().to_owned(),
+                trend: format!("{:?}", liquidity.trend),
+                price_level: liquidity.price_level,
+                found_at: liquidity.found_at,
+                points: vec![(utils::naive_ts(liquidity.found_at), liquidity.price)],
+                is_gap_fill: false,
+                is_imminent: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DivergenceSignal, RejectionSignal, Signal};
+    use crate::analyzer::Trend;
+    use crate::analyzer::divergence::Divergence;
+    use chrono::{Local, NaiveDate, TimeZone};
+    use persist::signal::SignalRecord;
+    use schwab_client::Candle;
+
+    #[test]
+    fn test_signal_record() {
+        let found_at = Local.with_ymd_and_hms(2025, 7, 1, 10, 15, 0).unwrap();
+        let candle = Candle {
+            open: 100.5,
+            low: 99.9,
+            high: 100.6,
+            close: 100.4,
+            volume: 1000,
+            time: found_at,
+            duration: 300,
+        };
+        let rejection = Signal::Rejection(RejectionSignal {
+            symbol: "SPY".to_owned(),
+            trend: Trend::Bullish,
+            price_level: 100.0,
+            is_imminent: true,
+            is_gap_fill: false,
+            found_at,
+            rejected_at: candle,
+            close: 100.4,
+            atr: 0.8,
+            points: vec![(1751364900, 100.0)],
+        });
+        let record = SignalRecord::from(&rejection);
+        assert_eq!(record.symbol, "SPY");
+        assert_eq!(record.kind, "Rejection");
+        assert_eq!(record.trend, "Bullish");
+        assert_eq!(record.price_level, 100.0);
+        assert_eq!(record.found_at, found_at);
+        assert_eq!(record.points, [(1751364900, 100.0)]);
+        assert!(record.is_imminent && !record.is_gap_fill);
+
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2025, 7, 1)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+        };
+        let divergence = Signal::Divergence(DivergenceSignal {
+            symbol: "QQQ".to_owned(),
+            timeframe: 300,
+            found_at,
+            divergence: Divergence {
+                trend: Trend::Bearish,
+                start: at(9, 45),
+                start_price: 550.0,
+                start_indicator: 72.0,
+                end: at(10, 10),
+                end_price: 552.0,
+                end_indicator: 65.0,
+            },
+        });
+        let record = SignalRecord::from(&divergence);
+        assert_eq!(record.kind, "Divergence");
+        assert_eq!(record.trend, "Bearish");
+        // Ends at the second peak, the points are the chart's naive timestamps
+        assert_eq!(record.price_level, 552.0);
+        assert_eq!(
+            record.points,
+            [
+                (at(9, 45).and_utc().timestamp(), 550.0),
+                (at(10, 10).and_utc().timestamp(), 552.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use super::controller::PriceLevel;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use schwab_client::{OptionChain, OptionContract, PutCall};
+use serde::{Deserialize, Serialize};
+
+const MINUTES_PER_YEAR: f64 = 365.0 * 24.0 * 60.0;
+
+/// How to pick the short strike of a credit spread.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum StrikeTarget {
+    /// Short strike with the absolute delta closest to the given one
+    ShortDelta(f64),
+    /// Short strike at least this many points beyond the nearest support (puts) or resistance (calls)
+    LevelDistance(f64),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum SpreadKind {
+    PutVertical,
+    CallVertical,
+    IronCondor,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpreadLeg {
+    pub symbol: String,
+    pub put_call: PutCall,
+    pub strike: f64,
+    pub quantity: i32,
+    pub bid: f64,
+    pub ask: f64,
+    pub delta: f64,
+    pub volatility: f64,
+}
+
+/// A credit spread candidate, all prices are per share.
+#[derive(Clone, Debug, Serialize)]
+pub struct SpreadCandidate {
+    pub kind: SpreadKind,
+    pub symbol: String,
+    pub expiration: NaiveDate,
+    pub underlying_price: f64,
+    pub legs: Vec<SpreadLeg>,
+    pub anchor_levels: Vec<f64>,
+    /// Natural credit, selling at bid and buying at ask
+    pub credit: f64,
+    pub mid_credit: f64,
+    pub max_loss: f64,
+    pub breakevens: Vec<f64>,
+    pub pop: f64,
+}
+
+/// Picks credit spread strikes out of an option chain for a single expiration, optionally
+/// anchoring the short strikes to the support/resistance levels found by the [`super::Controller`].
+pub struct SpreadBuilder<'a> {
+    chain: &'a OptionChain,
+    expiration: NaiveDate,
+    price_levels: &'a [PriceLevel],
+    years_to_expiry: f64,
+}
+
+struct Vertical<'a> {
+    short: &'a OptionContract,
+    long: &'a OptionContract,
+    anchor_level: Option<f64>,
+}
+
+impl<'a> SpreadBuilder<'a> {
+    pub fn new(
+        chain: &'a OptionChain,
+        expiration: NaiveDate,
+        price_levels: &'a [PriceLevel],
+        now: NaiveDateTime,
+        session_end: NaiveTime,
+    ) -> Self {
+        // 0DTE spreads still have a few minutes to go, so never let the time collapse to zero
+        let minutes_left = (expiration.and_time(session_end) - now)
+            .num_minutes()
+            .max(5);
+        Self {
+            chain,
+            expiration,
+            price_levels,
+            years_to_expiry: minutes_left as f64 / MINUTES_PER_YEAR,
+        }
+    }
+
+    pub fn candidates(&self, target: StrikeTarget, widths: &[f64]) -> Vec<SpreadCandidate> {
+        let mut candidates = Vec::new();
+        for &width in widths {
+            let put = self.find_vertical(PutCall::Put, target, width);
+            let call = self.find_vertical(PutCall::Call, target, width);
+            if let (Some(put), Some(call)) = (&put, &call) {
+                candidates.extend(self.iron_condor(put, call));
+            }
+            candidates.extend(put.and_then(|put| self.vertical(&put)));
+            candidates.extend(call.and_then(|call| self.vertical(&call)));
+        }
+        candidates
+    }
+
+    pub fn build_vertical(
+        &self,
+        put_call: PutCall,
+        target: StrikeTarget,
+        width: f64,
+    ) -> Option<SpreadCandidate> {
+        self.vertical(&self.find_vertical(put_call, target, width)?)
+    }
+
+    pub fn build_iron_condor(&self, target: StrikeTarget, width: f64) -> Option<SpreadCandidate> {
+        let put = self.find_vertical(PutCall::Put, target, width)?;
+        let call = self.find_vertical(PutCall::Call, target, width)?;
+        self.iron_condor(&put, &call)
+    }
+
+    fn find_vertical(
+        &self,
+        put_call: PutCall,
+        target: StrikeTarget,
+        width: f64,
+    ) -> Option<Vertical<'a>> {
+        let spot = self.chain.underlying_price;
+        let is_put = put_call == PutCall::Put;
+        // Out of the money contracts, ordered from the money outwards
+        let mut otm = self
+            .chain
+            .contracts(self.expiration, put_call)
+            .filter(|c| {
+                if is_put {
+                    c.strike_price < spot
+                } else {
+                    c.strike_price > spot
+                }
+            })
+            .collect::<Vec<_>>();
+        if is_put {
+            otm.reverse();
+        }
+
+        let (short, anchor_level) = match target {
+            StrikeTarget::ShortDelta(delta) => {
+                let short = otm.iter().filter(|c| !c.delta.is_nan()).min_by(|c1, c2| {
+                    (c1.delta.abs() - delta)
+                        .abs()
+                        .total_cmp(&(c2.delta.abs() - delta).abs())
+                })?;
+                (*short, None)
+            }
+            StrikeTarget::LevelDistance(distance) => {
+                let levels = self.price_levels.iter().map(|l| l.price);
+                let level = if is_put {
+                    levels.filter(|&p| p < spot).max_by(f64::total_cmp)?
+                } else {
+                    levels.filter(|&p| p > spot).min_by(f64::total_cmp)?
+                };
+                let short = otm.iter().find(|c| {
+                    if is_put {
+                        c.strike_price <= level - distance
+                    } else {
+                        c.strike_price >= level + distance
+                    }
+                })?;
+                (*short, Some(level))
+            }
+        };
+        let long = otm.into_iter().find(|c| {
+            if is_put {
+                c.strike_price <= short.strike_price - width
+            } else {
+                c.strike_price >= short.strike_price + width
+            }
+        })?;
+        Some(Vertical {
+            short,
+            long,
+            anchor_level,
+        })
+    }
+
+    fn vertical(&self, vertical: &Vertical) -> Option<SpreadCandidate> {
+        let credit = vertical.credit();
+        if credit <= 0.0 {
+            return None;
+        }
+
+        let (kind, breakeven, pop) = if vertical.short.put_call == PutCall::Put {
+            let breakeven = vertical.short.strike_price - credit;
+            let pop = self.prob_above(breakeven, vertical.short);
+            (SpreadKind::PutVertical, breakeven, pop)
+        } else {
+            let breakeven = vertical.short.strike_price + credit;
+            let pop = 1.0 - self.prob_above(breakeven, vertical.short);
+            (SpreadKind::CallVertical, breakeven, pop)
+        };
+        Some(SpreadCandidate {
+            kind,
+            symbol: self.chain.symbol.clone(),
+            expiration: self.expiration,
+            underlying_price: self.chain.underlying_price,
+            legs: vertical.legs(),
+            anchor_levels: vertical.anchor_level.into_iter().collect(),
+            credit,
+            mid_credit: vertical.mid_credit(),
+            max_loss: vertical.width() - credit,
+            breakevens: vec![breakeven],
+            pop,
+        })
+    }
+
+    fn iron_condor(&self, put: &Vertical, call: &Vertical) -> Option<SpreadCandidate> {
+        if put.short.strike_price >= call.short.strike_price {
+            return None;
+        }
+        let credit = put.credit() + call.credit();
+        if credit <= 0.0 {
+            return None;
+        }
+
+        let lower = put.short.strike_price - credit;
+        let upper = call.short.strike_price + credit;
+        let pop = self.prob_above(lower, put.short) - self.prob_above(upper, call.short);
+        Some(SpreadCandidate {
+            kind: SpreadKind::IronCondor,
+            symbol: self.chain.symbol.clone(),
+            expiration: self.expiration,
+            underlying_price: self.chain.underlying_price,
+            legs: put.legs().into_iter().chain(call.legs()).collect(),
+            anchor_levels: put
+                .anchor_level
+                .into_iter()
+                .chain(call.anchor_level)
+                .collect(),
+            credit,
+            mid_credit: put.mid_credit() + call.mid_credit(),
+            // Only one side can be breached at expiration
+            max_loss: put.width().max(call.width()) - credit,
+            breakevens: vec![lower, upper],
+            pop: pop.max(0.0),
+        })
+    }
+
+    /// Probability of the underlying expiring above `price`, assuming log-normal returns with the
+    /// implied volatility of the given contract; falls back to its delta if IV isn't available.
+    fn prob_above(&self, price: f64, contract: &OptionContract) -> f64 {
+        let volatility = [contract.volatility, self.chain.volatility]
+            .into_iter()
+            .find(|v| v.is_finite() && *v > 0.0)
+            .map(|v| v / 100.0);
+        let Some(volatility) = volatility else {
+            let delta = contract.delta.abs();
+            return if contract.put_call == PutCall::Put {
+                1.0 - delta
+            } else {
+                delta
+            };
+        };
+
+        let time = self.years_to_expiry;
+        let rate = self.chain.interest_rate / 100.0;
+        let d2 = ((self.chain.underlying_price / price).ln()
+            + (rate - volatility * volatility / 2.0) * time)
+            / (volatility * time.sqrt());
+        norm_cdf(d2)
+    }
+}
+
+impl Vertical<'_> {
+    fn credit(&self) -> f64 {
+        self.short.bid - self.long.ask
+    }
+
+    fn mid_credit(&self) -> f64 {
+        (self.short.bid + self.short.ask - self.long.bid - self.long.ask) / 2.0
+    }
+
+    fn width(&self) -> f64 {
+        (self.short.strike_price - self.long.strike_price).abs()
+    }
+
+    fn legs(&self) -> Vec<SpreadLeg> {
+        [(self.short, -1), (self.long, 1)]
+            .into_iter()
+            .map(|(contract, quantity)| SpreadLeg {
+                symbol: contract.symbol.clone(),
+                put_call: contract.put_call,
+                strike: contract.strike_price,
+                quantity,
+                bid: contract.bid,
+                ask: contract.ask,
+                delta: contract.delta,
+                volatility: contract.volatility,
+            })
+            .collect()
+    }
+}
+
+/// Standard normal CDF using the Abramowitz-Stegun approximation of erf (error < 1.5e-7)
+fn norm_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SpreadBuilder, SpreadKind, StrikeTarget, norm_cdf};
+    use crate::analyzer::PriceLevel;
+    use chrono::{Days, NaiveDate, NaiveTime};
+    use schwab_client::{OptionChain, OptionContract, PutCall};
+    use serde_json::json;
+
+    fn contract(
+        put_call: PutCall,
+        strike: f64,
+        spot: f64,
+        expiration: NaiveDate,
+    ) -> OptionContract {
+        // Crude but monotonic pricing, good enough to exercise strike selection
+        let distance = if put_call == PutCall::Put {
+            spot - strike
+        } else {
+            strike - spot
+        };
+        let mark = (2.0 - distance * 0.15).max(0.05);
+        let delta = (0.5 - distance * 0.04).max(0.01);
+        let mut contract = serde_json::from_value::<OptionContract>(json!({
+            "putCall": put_call,
+            "symbol": format!("SPY {put_call:?} {strike}"),
+            "description": "",
+            "strikePrice": strike,
+            "bid": mark - 0.05,
+            "ask": mark + 0.05,
+            "last": mark,
+            "mark": mark,
+            "delta": if put_call == PutCall::Put { -delta } else { delta },
+            "volatility": 15.0,
+            "daysToExpiration": 0,
+        }))
+        .unwrap();
+        contract.expiration = expiration;
+        contract
+    }
+
+    #[test]
+    fn test_level_anchored_condor() {
+        let expiration = NaiveDate::from_ymd_opt(2025, 7, 3).unwrap();
+        let spot = 100.0;
+        let strikes = (85..=115).map(f64::from);
+        let chain = OptionChain {
+            symbol: "SPY".to_owned(),
+            status: "SUCCESS".to_owned(),
+            underlying: None,
+            strategy: "SINGLE".to_owned(),
+            interval: 0.0,
+            is_delayed: false,
+            is_index: false,
+            interest_rate: 4.0,
+            underlying_price: spot,
+            volatility: 15.0,
+            days_to_expiration: 0.0,
+            calls: strikes
+                .clone()
+                .map(|s| contract(PutCall::Call, s, spot, expiration))
+                .collect(),
+            puts: strikes
+                .map(|s| contract(PutCall::Put, s, spot, expiration))
+                .collect(),
+        };
+        let levels = [90.0, 96.5, 103.0, 110.0]
+            .map(|price| PriceLevel::new(price, expiration.and_time(NaiveTime::MIN)));
+        let builder = SpreadBuilder::new(
+            &chain,
+            expiration,
+            &levels,
+            (expiration - Days::new(30)).and_hms_opt(10, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+        );
+
+        let condor = builder
+            .build_iron_condor(StrikeTarget::LevelDistance(1.0), 5.0)
+            .unwrap();
+        assert_eq!(condor.kind, SpreadKind::IronCondor);
+        let strikes = condor.legs.iter().map(|l| l.strike).collect::<Vec<_>>();
+        assert_eq!(strikes, vec![95.0, 90.0, 104.0, 109.0]);
+        assert_eq!(condor.anchor_levels, vec![96.5, 103.0]);
+        assert!((condor.credit - 1.3).abs() < 1e-9);
+        assert!((condor.max_loss - 3.7).abs() < 1e-9);
+        assert!((condor.breakevens[0] - 93.7).abs() < 1e-9);
+        assert!((condor.breakevens[1] - 105.3).abs() < 1e-9);
+        assert!(0.0 < condor.pop && condor.pop < 1.0);
+
+        let put = builder
+            .build_vertical(PutCall::Put, StrikeTarget::ShortDelta(0.3), 2.0)
+            .unwrap();
+        assert_eq!(put.kind, SpreadKind::PutVertical);
+        assert_eq!(put.legs[0].strike, 95.0);
+        assert_eq!(put.legs[1].strike, 93.0);
+        assert!(put.pop > condor.pop);
+
+        assert_eq!(
+            builder
+                .candidates(StrikeTarget::LevelDistance(1.0), &[5.0])
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_norm_cdf() {
+        assert!((norm_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((norm_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((norm_cdf(-1.0) - 0.1587).abs() < 1e-4);
+    }
+}
@@ -8,6 +8,7 @@ use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::iter;
 use ta_lib::{momentum, overlap, ta};
+use util::time::MarketTime;
 
 pub fn aggregate(candles: &[Candle], duration: Duration) -> Vec<Candle> {
     fn _truncate_time(candle: &Candle, duration: Duration) -> DateTime<Local> {
@@ -134,8 +135,9 @@ pub fn check_trend(candles: &[Candle]) -> Trend {
     }
 }
 
+/// Timestamp of the exchange's wall clock, how the charts show times.
 pub fn naive_ts(time: DateTime<Local>) -> i64 {
-    time.naive_local().and_utc().timestamp()
+    time.market_naive().and_utc().timestamp()
 }
 
 pub fn cmp_f64(a: f64, b: f64) -> Ordering {
@@ -220,7 +222,11 @@ pub fn gaussian_smooth(data: &[f64], sigma: f64, kernel_size: Option<usize>) ->
     // Auto-calculate kernel size if not provided (6*sigma covers ~99.7% of the distribution)
     let ksize = kernel_size.unwrap_or_else(|| {
         let size = (6.0 * sigma).ceil() as usize;
-        if size % 2 == 0 { size + 1 } else { size }
+        if size.is_multiple_of(2) {
+            size + 1
+        } else {
+            size
+        }
     });
 
     let kernel = _gaussian_kernel(sigma, ksize);
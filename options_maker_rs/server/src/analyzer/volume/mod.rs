@@ -5,7 +5,7 @@ use chrono::{DateTime, Local, NaiveDate};
 use rustc_hash::FxHashMap;
 use schwab_client::Candle;
 use std::collections::BTreeMap;
-use util::time::TradingDay;
+use util::time::{MarketTime, TradingDay};
 
 pub fn group_by_workday(candles: &[Candle]) -> BTreeMap<NaiveDate, Vec<Candle>> {
     let Some(last_candle) = candles.last() else {
@@ -13,18 +13,17 @@ pub fn group_by_workday(candles: &[Candle]) -> BTreeMap<NaiveDate, Vec<Candle>>
     };
 
     let (begin, end) = APP_CONFIG.trade_config.open_hours;
-    let min_working_hours = util::time::regular_trading_hours();
     candles
         .iter()
         .fold(
             FxHashMap::<NaiveDate, (DateTime<Local>, DateTime<Local>, Vec<Candle>)>::default(),
             |mut map, c| {
                 let entry = map
-                    .entry(c.time.date_naive())
+                    .entry(c.time.market_date())
                     .or_insert_with(|| (c.time, c.time, Vec::new()));
                 entry.0 = entry.0.min(c.time);
                 entry.1 = entry.1.max(c.time);
-                if begin <= c.time.time() && c.time.time() < end {
+                if begin <= c.time.market_time() && c.time.market_time() < end {
                     entry.2.push(*c);
                 }
                 map
@@ -33,7 +32,8 @@ pub fn group_by_workday(candles: &[Candle]) -> BTreeMap<NaiveDate, Vec<Candle>>
         .into_iter()
         .filter(|&(key, (min, max, _))| {
             key.is_trading_day()
-                && (key == last_candle.time.date_naive() || max - min >= min_working_hours)
+                && (key == last_candle.time.market_date()
+                    || max - min >= util::time::regular_trading_hours(key))
         })
         .map(|(key, (_, _, candles))| (key, candles))
         .collect()
@@ -49,13 +49,13 @@ pub fn daily_avg_vol_until_now(candles: &[Candle]) -> Option<(f64, f64)> {
                 day,
                 candles
                     .into_iter()
-                    .filter(|c| c.time.time() <= last.time.time())
+                    .filter(|c| c.time.market_time() <= last.time.market_time())
                     .map(|c| c.volume as f64)
                     .sum::<f64>(),
             )
         })
         .collect::<FxHashMap<_, _>>();
-    let today_volume = daily_volumes.remove(&last.time.date_naive())?;
+    let today_volume = daily_volumes.remove(&last.time.market_date())?;
     if daily_volumes.is_empty() {
         return None;
     }
@@ -71,7 +71,7 @@ pub fn daily_avg_volume(candles: &[Candle]) -> f64 {
 
     let daily_volume = group_by_workday(candles)
         .into_iter()
-        .filter(|(key, _)| *key < last.time.date_naive())
+        .filter(|(key, _)| *key < last.time.market_date())
         .map(|(_, candles)| candles.into_iter().map(|c| c.volume as f64).sum::<f64>())
         .collect::<Vec<_>>();
     if daily_volume.is_empty() {
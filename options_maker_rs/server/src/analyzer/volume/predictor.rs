@@ -6,7 +6,8 @@ use itertools::Itertools;
 use rustc_hash::FxHashMap;
 use schwab_client::Candle;
 use tracing::{debug, info};
-use util::time::TradingDay;
+use util::calendar::MarketCalendar;
+use util::time::{MarketTime, TradingDay};
 
 const FEATURES_SIZE: usize = 19;
 
@@ -68,26 +69,33 @@ impl VolumePredictor {
     }
 
     fn is_trading_time(&self, dt: DateTime<Local>) -> bool {
-        if dt.date_naive().is_weekend() {
+        if !dt.market_date().is_trading_day() {
             return false;
         }
 
-        let time = dt.time();
-        self.trading_hours_start <= time && time < self.trading_hours_end
+        let time = dt.market_time();
+        self.trading_hours_start <= time && time < self.trading_hours_end(dt.market_date())
+    }
+
+    /// The configured end of trading hours, moved up on the days the market closes early.
+    fn trading_hours_end(&self, date: NaiveDate) -> NaiveTime {
+        MarketCalendar::day(date).adjusted_close(self.trading_hours_end)
     }
 
     fn get_trading_progress(&self, dt: DateTime<Local>) -> f32 {
         if !self.is_trading_time(dt) {
-            return if dt.time() < self.trading_hours_start {
+            return if dt.market_time() < self.trading_hours_start {
                 0.0
             } else {
                 1.0
             };
         }
 
-        let current_seconds = dt.time().num_seconds_from_midnight() as f32;
+        let current_seconds = dt.market_time().num_seconds_from_midnight() as f32;
         let start_seconds = self.trading_hours_start.num_seconds_from_midnight() as f32;
-        let end_seconds = self.trading_hours_end.num_seconds_from_midnight() as f32;
+        let end_seconds = self
+            .trading_hours_end(dt.market_date())
+            .num_seconds_from_midnight() as f32;
 
         let progress = (current_seconds - start_seconds) / (end_seconds - start_seconds);
         progress.clamp(0.0, 1.0)
@@ -105,7 +113,7 @@ impl VolumePredictor {
             }
 
             daily_candles
-                .entry(candle.time.date_naive())
+                .entry(candle.time.market_date())
                 .or_insert_with(Vec::new)
                 .push(*candle);
         }
@@ -167,29 +175,29 @@ impl VolumePredictor {
         features.extend([total_current_volume as f32, current_progress, candle_count]);
 
         // 8-11: Enhanced time-based features (using full timestamp precision)
-        let (hour_sin, hour_cos, minute_sin, minute_cos) = if let Some(candle) =
-            current_candles.last()
-        {
-            // Hour component (0-23)
-            let hour_angle = 2.0 * std::f32::consts::PI * (candle.time.hour() as f32) / 24.0;
-            let hour_sin = hour_angle.sin();
-            let hour_cos = hour_angle.cos();
-
-            // Minute component (0-59) - important for intraday patterns!
-            let minute_angle = 2.0 * std::f32::consts::PI * (candle.time.minute() as f32) / 60.0;
-            let minute_sin = minute_angle.sin();
-            let minute_cos = minute_angle.cos();
-
-            (hour_sin, hour_cos, minute_sin, minute_cos)
-        } else {
-            (0.0, 0.0, 0.0, 0.0)
-        };
+        let (hour_sin, hour_cos, minute_sin, minute_cos) =
+            if let Some(candle) = current_candles.last() {
+                let time = candle.time.market_time();
+                // Hour component (0-23)
+                let hour_angle = 2.0 * std::f32::consts::PI * (time.hour() as f32) / 24.0;
+                let hour_sin = hour_angle.sin();
+                let hour_cos = hour_angle.cos();
+
+                // Minute component (0-59) - important for intraday patterns!
+                let minute_angle = 2.0 * std::f32::consts::PI * (time.minute() as f32) / 60.0;
+                let minute_sin = minute_angle.sin();
+                let minute_cos = minute_angle.cos();
+
+                (hour_sin, hour_cos, minute_sin, minute_cos)
+            } else {
+                (0.0, 0.0, 0.0, 0.0)
+            };
         features.extend([hour_sin, hour_cos, minute_sin, minute_cos]);
 
         // 12: Day of week effect
         let day_of_week = current_candles
             .last()
-            .map(|c| c.time.weekday().num_days_from_monday())
+            .map(|c| c.time.market_date().weekday().num_days_from_monday())
             .unwrap_or(0) as f32;
         features.push(day_of_week);
 
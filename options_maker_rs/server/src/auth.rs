@@ -0,0 +1,168 @@
+use crate::app_error::{AppError, AppResult};
+use crate::websocket;
+use app_config::APP_CONFIG;
+use axum::Router;
+use axum::extract::{Query, Request};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::routing::get;
+use chrono::{DateTime, Duration, Local};
+use data_provider::provider;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+
+const LOGIN_URL: &str = "/api/auth/login";
+
+/// `state` of the login in flight, the callback is only accepted when it echoes it back.
+static PENDING_LOGIN: Mutex<Option<String>> = Mutex::new(None);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize)]
+struct AuthRequired {
+    expires_at: DateTime<Local>,
+    login_url: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthCallback {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Login routes of the provider, for the first login as well as the later ones. Schwab redirects
+/// to `schwab_callback_url` which has to point at `/api/auth/callback` of this server.
+pub fn router() -> Router {
+    Router::new()
+        .route("/login", get(login))
+        .route("/callback", get(callback))
+}
+
+async fn login() -> AppResult<Redirect> {
+    let state = format!("{:016x}", rand::random::<u64>());
+    let url = data_provider::login_url(&APP_CONFIG.schwab_callback_url, &state)?;
+    PENDING_LOGIN.lock().unwrap().replace(state);
+    Ok(Redirect::to(&url))
+}
+
+async fn callback(Query(params): Query<AuthCallback>) -> AppResult<Html<String>> {
+    if let Some(error) = params.error {
+        let error_desc = params.error_description.unwrap_or_default();
+        warn!("Auth failure: {error}/{error_desc}");
+        return Err(AppError::Generic(format!(
+            "OAuth Error {error}: {error_desc}"
+        )));
+    }
+    let pending = PENDING_LOGIN.lock().unwrap().take();
+    if pending.is_none() || pending != params.state {
+        return Err(AppError::Generic(format!(
+            "Unexpected login callback, start over at {LOGIN_URL}"
+        )));
+    }
+    let Some(code) = params.code else {
+        return Err(AppError::Generic(
+            "Didn't receive auth code from schwab in the response".to_owned(),
+        ));
+    };
+
+    data_provider::login(&code, &APP_CONFIG.schwab_callback_url).await?;
+    info!("Logged in to the data provider");
+    if data_provider::is_ready() {
+        publish();
+    }
+    Ok(Html(
+        r#"
+        <h1>OAuth success!</h1>
+        <p>Tokens are refreshed, head back to the <a href="/">app</a>.</p>
+        "#
+        .to_owned(),
+    ))
+}
+
+/// The rest of the API is served once the services are up.
+pub fn mark_started() {
+    STARTED.store(true, Ordering::Relaxed);
+}
+
+/// Until the services are up, the rest of the API answers with where to log in if that's what
+/// they're waiting for.
+pub async fn require_login(request: Request, next: Next) -> Response {
+    if STARTED.load(Ordering::Relaxed) {
+        return next.run(request).await;
+    }
+    let message = if data_provider::is_ready() {
+        "The server is starting up".to_owned()
+    } else {
+        format!("The data provider is waiting for the login at {LOGIN_URL}")
+    };
+    (StatusCode::SERVICE_UNAVAILABLE, message).into_response()
+}
+
+/// Asks the clients to log in again once the login is a day from expiring.
+pub fn start_auth_monitor() {
+    if provider().login_expires_at().is_none() {
+        return;
+    }
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(600));
+        loop {
+            interval.tick().await;
+            if let Some(auth_required) = auth_required() {
+                warn!(
+                    "Login expires at {}, log in again at {LOGIN_URL}",
+                    auth_required.expires_at
+                );
+                websocket::publish("AUTH_REQUIRED", auth_required);
+            }
+        }
+    });
+}
+
+pub fn publish() {
+    if provider().login_expires_at().is_some() {
+        websocket::publish("AUTH_REQUIRED", auth_required());
+    }
+}
+
+fn auth_required() -> Option<AuthRequired> {
+    let expires_at = provider().login_expires_at()?;
+    (expires_at <= util::time::now() + Duration::days(1)).then_some(AuthRequired {
+        expires_at,
+        login_url: LOGIN_URL,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AuthCallback, PENDING_LOGIN, callback};
+    use axum::extract::Query;
+
+    fn params(state: Option<&str>) -> Query<AuthCallback> {
+        Query(AuthCallback {
+            code: Some("auth-code".to_owned()),
+            state: state.map(str::to_owned),
+            error: None,
+            error_description: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_callback_state() {
+        // Without a login in flight, or with another one's state, the code is never used
+        assert!(callback(params(Some("abc"))).await.is_err());
+
+        PENDING_LOGIN.lock().unwrap().replace("abc".to_owned());
+        assert!(callback(params(Some("xyz"))).await.is_err());
+        // The mismatch used up the pending login, the right state is too late now
+        assert!(PENDING_LOGIN.lock().unwrap().is_none());
+        assert!(callback(params(Some("abc"))).await.is_err());
+
+        PENDING_LOGIN.lock().unwrap().replace("abc".to_owned());
+        assert!(callback(params(None)).await.is_err());
+    }
+}
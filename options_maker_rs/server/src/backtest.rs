@@ -0,0 +1,195 @@
+use anyhow::Context;
+use app_config::APP_CONFIG;
+use chrono::{Duration, NaiveDate, NaiveTime};
+use futures::{StreamExt, stream};
+use itertools::Itertools;
+use persist::outcome::SignalOutcome;
+use rustc_hash::FxHashMap;
+use schwab_client::Candle;
+use serde::Serialize;
+use server::analyzer::{Controller, OutcomeSummary, Signal};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+use tracing::{info, warn};
+use util::time::{MarketTime, TradingDay};
+
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[derive(Default)]
+struct BacktestResult {
+    signals: Vec<Signal>,
+    outcomes: Vec<SignalOutcome>,
+}
+
+#[derive(Serialize)]
+struct ReportEntry<'a, T: Serialize> {
+    kind: &'static str,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let start = Instant::now();
+    util::init::init_main();
+
+    let config = APP_CONFIG
+        .backtest
+        .as_ref()
+        .context("Backtest config is missing")?;
+
+    info!("Initializing database...");
+    persist::init().await?;
+
+    let symbols = if config.symbols.is_empty() {
+        persist::ticker::fetch_instruments()
+            .await?
+            .into_iter()
+            .map(|ins| ins.symbol)
+            .collect()
+    } else {
+        config.symbols.clone()
+    };
+    info!(
+        "Backtesting {} symbols over {} date ranges",
+        symbols.len(),
+        config.date_ranges.len()
+    );
+
+    let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let results = stream::iter(symbols)
+        .map(async |symbol| {
+            let result = backtest_symbol(&symbol, &config.date_ranges).await;
+            (symbol, result)
+        })
+        .buffer_unordered(parallelism)
+        .collect::<Vec<_>>()
+        .await;
+
+    let mut report = BufWriter::new(
+        File::create(&config.report_file)
+            .with_context(|| format!("Failed to create {:?}", config.report_file))?,
+    );
+    let mut summary = FxHashMap::<(String, &str), usize>::default();
+    let mut all_outcomes = Vec::new();
+    for (symbol, result) in results.into_iter().sorted_by(|(s1, _), (s2, _)| s1.cmp(s2)) {
+        let BacktestResult { signals, outcomes } = match result {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Failed to backtest '{symbol}': {e}");
+                continue;
+            }
+        };
+        for signal in &signals {
+            *summary.entry((symbol.clone(), signal.kind())).or_default() += 1;
+            serde_json::to_writer(&mut report, signal)?;
+            writeln!(report)?;
+        }
+        for outcome in &outcomes {
+            let entry = ReportEntry {
+                kind: "Outcome",
+                data: outcome,
+            };
+            serde_json::to_writer(&mut report, &entry)?;
+            writeln!(report)?;
+        }
+        all_outcomes.extend(outcomes);
+    }
+    let outcome_summary = OutcomeSummary::new(&all_outcomes);
+    let entry = ReportEntry {
+        kind: "Summary",
+        data: &outcome_summary,
+    };
+    serde_json::to_writer(&mut report, &entry)?;
+    writeln!(report)?;
+    report.flush()?;
+
+    for ((symbol, kind), count) in summary.into_iter().sorted() {
+        info!("{symbol}: {count} {kind} signals");
+    }
+    let overall = &outcome_summary.overall;
+    info!(
+        "{} outcomes, win rate: {:.2}, 1R hit rate: {:.2}, 2R hit rate: {:.2}, expectancy: {:.2}R",
+        overall.count,
+        overall.win_rate,
+        overall.hit_1r_rate,
+        overall.hit_2r_rate,
+        overall.expectancy
+    );
+    info!(
+        "Backtest report written to {:?} in {:.2?}",
+        config.report_file,
+        start.elapsed()
+    );
+    Ok(())
+}
+
+/// Replays every trading day in the given ranges through a fresh [`Controller`], warmed up with
+/// `look_back_days` of history just like the live server does at startup.
+/// Manually overridden price levels are ignored since they only apply to the day they were drawn.
+async fn backtest_symbol(
+    symbol: &str,
+    date_ranges: &[(NaiveDate, NaiveDate)],
+) -> anyhow::Result<BacktestResult> {
+    let look_back = Duration::days(APP_CONFIG.trade_config.look_back_days as i64);
+    let mut result = BacktestResult::default();
+    for &(start, end) in date_ranges {
+        let load_start = util::time::market_day_start(start) - look_back;
+        let load_end = util::time::market_day_start(end.succ_opt().context("Invalid end date")?);
+        let candles = persist::prices::load_prices(symbol, load_start, Some(load_end)).await?;
+        info!(
+            "Loaded {} candles for {symbol} between {start} and {end}",
+            candles.len()
+        );
+
+        let days = candles
+            .iter()
+            .map(|c| schwab_client::trading_date(symbol, c.time))
+            .filter(|day| start <= *day && *day <= end && day.is_trading_day())
+            .dedup()
+            .collect::<Vec<_>>();
+        for day in days {
+            let symbol = symbol.to_owned();
+            let history_start = day.and_time(NaiveTime::MIN) - look_back;
+            let history = candles
+                .iter()
+                .filter(|c| {
+                    c.time.market_naive() >= history_start
+                        && schwab_client::trading_date(&symbol, c.time) < day
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            let today = candles
+                .iter()
+                .filter(|c| schwab_client::trading_date(&symbol, c.time) == day)
+                .copied()
+                .collect::<Vec<_>>();
+            if history.is_empty() {
+                warn!("No history found for {symbol} before {day}, skipping");
+                continue;
+            }
+
+            let day_result =
+                tokio::task::spawn_blocking(move || replay_day(symbol, history, today)).await?;
+            result.signals.extend(day_result.signals);
+            result.outcomes.extend(day_result.outcomes);
+        }
+    }
+    Ok(result)
+}
+
+fn replay_day(symbol: String, history: Vec<Candle>, today: Vec<Candle>) -> BacktestResult {
+    let mut controller = Controller::new(symbol, history, Vec::new(), false, false);
+    controller.train();
+    let mut result = BacktestResult::default();
+    for candle in today {
+        controller.on_new_candle(candle, false);
+        result.signals.extend_from_slice(controller.signals());
+        result.outcomes.extend_from_slice(controller.outcomes());
+    }
+    // The next day starts with a new controller, nothing carries over
+    result.outcomes.extend(controller.close_outcomes());
+    result
+}
@@ -0,0 +1,391 @@
+use crate::app_error::{AppError, AppResult};
+use crate::{options, websocket};
+use app_config::APP_CONFIG;
+use axum::extract::{Path, Query};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Local};
+use data_provider::provider;
+use rustc_hash::FxHashSet;
+use schwab_client::streaming_client::StreamResponse;
+use schwab_client::{
+    Account, AccountActivity, Balances, Position, PutCall, Quote, futures_multiplier, is_option,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Latest snapshot of the brokerage accounts, marked to market with the streamed quotes.
+static ACCOUNTS: LazyLock<RwLock<Vec<BrokerageAccount>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokerageAccount {
+    pub number: String,
+    pub account_type: String,
+    pub round_trips: i32,
+    pub is_day_trader: bool,
+    pub balances: Balances,
+    pub balance: f64,
+    pub unrealized_pnl: f64,
+    pub day_pnl: f64,
+    pub positions: Vec<BrokeragePosition>,
+    pub updated_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokeragePosition {
+    pub symbol: String,
+    pub underlying: String,
+    pub description: Option<String>,
+    pub asset_type: String,
+    pub put_call: Option<PutCall>,
+    pub quantity: f64,
+    pub avg_price: f64,
+    pub multiplier: f64,
+    pub mark: f64,
+    pub market_value: f64,
+    pub unrealized_pnl: f64,
+    pub day_pnl: f64,
+    /// Market value and day P&L as of the last poll, the live marks are applied on top of them.
+    #[serde(skip)]
+    polled_value: f64,
+    #[serde(skip)]
+    polled_day_pnl: f64,
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/accounts", get(get_accounts))
+        .route("/accounts/{number}", get(get_account))
+        .route("/positions", get(get_positions))
+        .route("/activity", get(get_activity))
+}
+
+#[derive(Debug, Deserialize)]
+struct ActivityQuery {
+    order_id: Option<i64>,
+    #[serde(default = "default_activity_days")]
+    days: u64,
+}
+
+fn default_activity_days() -> u64 {
+    7
+}
+
+async fn get_accounts() -> Json<Vec<BrokerageAccount>> {
+    Json(ACCOUNTS.read().unwrap().clone())
+}
+
+async fn get_account(Path(number): Path<String>) -> AppResult<Json<BrokerageAccount>> {
+    ACCOUNTS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|account| account.number == number)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| AppError::Generic(format!("No account found with number {number}")))
+}
+
+async fn get_positions() -> Json<Vec<BrokeragePosition>> {
+    Json(
+        ACCOUNTS
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|account| account.positions.iter().cloned())
+            .collect(),
+    )
+}
+
+/// Trade journal of the account activity stream.
+async fn get_activity(Query(query): Query<ActivityQuery>) -> AppResult<Json<Vec<AccountActivity>>> {
+    let start = util::time::days_ago(query.days);
+    Ok(Json(
+        persist::journal::load_activity(query.order_id, start).await?,
+    ))
+}
+
+/// Polls the brokerage accounts and keeps their positions marked with the streamed quotes.
+pub async fn start_brokerage() -> anyhow::Result<()> {
+    let config = &APP_CONFIG.brokerage;
+    if !config.enabled || APP_CONFIG.replay_mode {
+        info!("Brokerage account polling is disabled");
+        return Ok(());
+    }
+    // Only a provider logged into the brokerage has accounts to poll
+    if provider().login_expires_at().is_none() {
+        info!("The data provider has no brokerage accounts, not polling them");
+        return Ok(());
+    }
+
+    let mut poll_timer =
+        tokio::time::interval(Duration::from_secs(config.poll_interval_secs.max(5)));
+    let mut publish_timer = tokio::time::interval(Duration::from_secs(1));
+    let mut stream_listener = provider().listener();
+    provider().sub_account_activity();
+    tokio::spawn(async move {
+        let mut streamed = FxHashSet::default();
+        let mut is_dirty = false;
+        loop {
+            tokio::select! {
+                _ = poll_timer.tick() => match provider().fetch_accounts().await {
+                    Ok(accounts) => {
+                        update_accounts(accounts, &mut streamed);
+                        is_dirty = true;
+                    }
+                    Err(e) => warn!("Failed to fetch brokerage accounts: {e}"),
+                },
+                Some(stream_res) = stream_listener.recv() => match &stream_res {
+                    StreamResponse::EquityLevelOne { symbol, quote }
+                    | StreamResponse::FuturesLevelOne { symbol, quote }
+                    | StreamResponse::OptionsLevelOne { symbol, quote } if streamed.contains(symbol) => {
+                        is_dirty |= on_quote(symbol, quote);
+                    }
+                    StreamResponse::OrderFill { activity }
+                    | StreamResponse::PositionChange { activity } => {
+                        on_activity(activity);
+                        // Positions and balances changed, no need to wait for the next poll
+                        poll_timer.reset_immediately();
+                    }
+                    StreamResponse::OrderCancel { activity }
+                    | StreamResponse::OrderStatus { activity } => on_activity(activity),
+                    _ => {}
+                },
+                _ = publish_timer.tick() => {
+                    if is_dirty {
+                        publish();
+                        is_dirty = false;
+                    }
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn update_accounts(accounts: Vec<Account>, streamed: &mut FxHashSet<String>) {
+    let now = util::time::now();
+    let accounts = accounts
+        .into_iter()
+        .map(|account| BrokerageAccount::new(account, now))
+        .collect::<Vec<_>>();
+
+    // Ticks are shared with the analyzer and the paper account, only the interest in the ones
+    // of closed positions is dropped.
+    let held = accounts
+        .iter()
+        .flat_map(|account| &account.positions)
+        .map(|position| position.symbol.clone())
+        .collect::<FxHashSet<_>>();
+    let (opened, closed) = (held.difference(streamed), streamed.difference(&held));
+    let (contracts, opened): (Vec<_>, Vec<_>) = opened.cloned().partition(|s| is_option(s));
+    contracts
+        .iter()
+        .for_each(|contract| options::watch(contract));
+    data_provider::sub_tick(opened);
+    data_provider::unsub_tick(closed.filter(|s| !is_option(s)).cloned().collect());
+    *streamed = held;
+
+    *ACCOUNTS.write().unwrap() = accounts;
+}
+
+fn on_activity(activity: &AccountActivity) {
+    info!(
+        "{} of order {:?} in account {}",
+        activity.message_type, activity.order_id, activity.account
+    );
+    websocket::publish("ORDER_ACTIVITY", activity);
+    let activity = activity.clone();
+    tokio::spawn(async move {
+        if let Err(e) = persist::journal::save_activity(&activity).await {
+            warn!("Failed to journal {}: {e}", activity.message_type);
+        }
+    });
+}
+
+fn on_quote(symbol: &str, quote: &Quote) -> bool {
+    let mark = if quote.bid_price > 0.0 && quote.ask_price > 0.0 {
+        (quote.bid_price + quote.ask_price) / 2.0
+    } else {
+        quote.last_price
+    };
+    if mark <= 0.0 {
+        return false;
+    }
+
+    let mut updated = false;
+    for account in ACCOUNTS.write().unwrap().iter_mut() {
+        let mut marked = false;
+        for position in account.positions.iter_mut() {
+            if position.symbol == symbol && position.mark != mark {
+                position.update_mark(mark);
+                marked = true;
+            }
+        }
+        if marked {
+            account.update_totals();
+            updated = true;
+        }
+    }
+    updated
+}
+
+pub fn publish() {
+    websocket::publish("UPDATE_BROKERAGE", &*ACCOUNTS.read().unwrap());
+}
+
+impl BrokerageAccount {
+    fn new(account: Account, now: DateTime<Local>) -> Self {
+        let mut account = Self {
+            number: account.account_number,
+            account_type: account.account_type,
+            round_trips: account.round_trips,
+            is_day_trader: account.is_day_trader,
+            positions: account
+                .positions
+                .into_iter()
+                .map(BrokeragePosition::from)
+                .collect(),
+            balances: account.current_balances,
+            balance: 0.0,
+            unrealized_pnl: 0.0,
+            day_pnl: 0.0,
+            updated_at: now,
+        };
+        account.update_totals();
+        account
+    }
+
+    fn update_totals(&mut self) {
+        let mark_change = self
+            .positions
+            .iter()
+            .map(|p| p.market_value - p.polled_value)
+            .sum::<f64>();
+        self.balance = self.balances.liquidation_value + mark_change;
+        self.unrealized_pnl = self.positions.iter().map(|p| p.unrealized_pnl).sum();
+        self.day_pnl = self.positions.iter().map(|p| p.day_pnl).sum();
+    }
+}
+
+impl BrokeragePosition {
+    fn update_mark(&mut self, mark: f64) {
+        self.mark = mark;
+        self.market_value = self.quantity * mark * self.multiplier;
+        self.unrealized_pnl = self.quantity * (mark - self.avg_price) * self.multiplier;
+        self.day_pnl = self.polled_day_pnl + self.market_value - self.polled_value;
+    }
+}
+
+impl From<Position> for BrokeragePosition {
+    fn from(position: Position) -> Self {
+        let quantity = position.quantity();
+        let multiplier = match position.instrument.asset_type.as_str() {
+            "OPTION" => 100.0,
+            "FUTURE" => futures_multiplier(&position.instrument.symbol).unwrap_or_else(|| {
+                warn!(
+                    "Unknown multiplier of {}, its P&L is per point",
+                    position.instrument.symbol
+                );
+                1.0
+            }),
+            _ => 1.0,
+        };
+        let mut view = Self {
+            underlying: position
+                .instrument
+                .underlying_symbol
+                .unwrap_or_else(|| position.instrument.symbol.clone()),
+            symbol: position.instrument.symbol,
+            description: position.instrument.description,
+            asset_type: position.instrument.asset_type,
+            put_call: position.instrument.put_call,
+            quantity,
+            avg_price: position.average_price,
+            multiplier,
+            mark: 0.0,
+            market_value: position.market_value,
+            unrealized_pnl: 0.0,
+            day_pnl: position.current_day_profit_loss,
+            polled_value: position.market_value,
+            polled_day_pnl: position.current_day_profit_loss,
+        };
+        if quantity != 0.0 {
+            view.update_mark(position.market_value / (quantity * multiplier));
+        }
+        view
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_marks_positions() {
+        let account = serde_json::from_value::<Account>(serde_json::json!({
+            "type": "MARGIN",
+            "accountNumber": "12345678",
+            "roundTrips": 0,
+            "isDayTrader": false,
+            "isClosingOnlyRestricted": false,
+            "pfcbFlag": false,
+            "initialBalances": { "cashAvailableForTrading": 0.0, "cashAvailableForWithdrawal": 0.0 },
+            "currentBalances": {
+                "cashAvailableForTrading": 5000.0,
+                "cashAvailableForWithdrawal": 5000.0,
+                "liquidationValue": 15500.0
+            },
+            "projectedBalances": { "cashAvailableForTrading": 0.0, "cashAvailableForWithdrawal": 0.0 },
+            "positions": [{
+                "shortQuantity": 0.0,
+                "longQuantity": 50.0,
+                "averagePrice": 190.0,
+                "marketValue": 10000.0,
+                "currentDayProfitLoss": 100.0,
+                "instrument": { "assetType": "EQUITY", "cusip": "037833100", "symbol": "AAPL" }
+            }, {
+                "shortQuantity": 1.0,
+                "longQuantity": 0.0,
+                "averagePrice": 2.0,
+                "marketValue": -150.0,
+                "currentDayProfitLoss": -10.0,
+                "instrument": {
+                    "assetType": "OPTION",
+                    "symbol": "AAPL  250117C00210000",
+                    "putCall": "CALL",
+                    "underlyingSymbol": "AAPL"
+                }
+            }, {
+                "shortQuantity": 0.0,
+                "longQuantity": 1.0,
+                "averagePrice": 6000.0,
+                "marketValue": 301000.0,
+                "currentDayProfitLoss": 0.0,
+                "instrument": { "assetType": "FUTURE", "symbol": "/ESZ25" }
+            }]
+        }))
+        .unwrap();
+        let mut account = BrokerageAccount::new(account, util::time::now());
+        assert_eq!(account.positions.len(), 3);
+        assert_eq!(account.positions[0].mark, 200.0);
+        assert_eq!(account.positions[0].unrealized_pnl, 500.0);
+        assert_eq!(account.positions[1].quantity, -1.0);
+        assert_eq!(account.positions[1].mark, 1.5);
+        assert_eq!(account.positions[1].unrealized_pnl, 50.0);
+        assert_eq!(account.positions[1].underlying, "AAPL");
+        assert_eq!(account.positions[2].mark, 6020.0);
+        assert_eq!(account.positions[2].unrealized_pnl, 1000.0);
+        assert_eq!(account.balance, 15500.0);
+
+        account.positions[0].update_mark(202.0);
+        account.update_totals();
+        assert!((account.positions[0].unrealized_pnl - 600.0).abs() < 1e-9);
+        assert!((account.positions[0].day_pnl - 200.0).abs() < 1e-9);
+        assert!((account.balance - 15600.0).abs() < 1e-9);
+        assert!((account.unrealized_pnl - 1650.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,15 @@
+pub mod analyzer;
+pub mod app_error;
+pub mod auth;
+pub mod brokerage;
+pub mod groups;
+pub mod notifier;
+pub mod options;
+pub mod outcomes;
+pub mod paper;
+pub mod signals;
+pub mod spreads;
+pub mod stocks;
+pub mod ticker;
+pub mod trading_view;
+pub mod websocket;
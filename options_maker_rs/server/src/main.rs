@@ -1,15 +1,11 @@
-mod analyzer;
-mod app_error;
-mod groups;
-mod stocks;
-mod ticker;
-mod trading_view;
-mod websocket;
-
 use anyhow::Context;
 use app_config::APP_CONFIG;
-use axum::Router;
+use axum::{Router, middleware};
 use axum_server::tls_rustls::RustlsConfig;
+use server::{
+    analyzer, auth, brokerage, groups, notifier, options, outcomes, paper, signals, spreads,
+    stocks, ticker, trading_view, websocket,
+};
 use std::net::{Ipv4Addr, SocketAddr};
 use std::path::Path;
 use std::time::Instant;
@@ -33,15 +29,43 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Initializing database...");
     persist::init().await?;
+
+    // Listening right away, the first login of the data provider goes through this server
+    let mut server = tokio::spawn(serve(router().await?));
+    tokio::select! {
+        result = start_services() => result?,
+        result = &mut server => return result?,
+    }
+    auth::mark_started();
+    info!("Initialized server in {:?}", start.elapsed());
+    server.await?
+}
+
+async fn start_services() -> anyhow::Result<()> {
     data_provider::init().await?;
+    notifier::start_notifiers()?;
     analyzer::start_analysis().await?;
+    paper::start_paper_trading().await?;
+    brokerage::start_brokerage().await?;
+    auth::start_auth_monitor();
+    Ok(())
+}
 
+async fn router() -> anyhow::Result<Router> {
     let api_routers = Router::new()
         .nest("/stocks", stocks::router())
         .nest("/ticker", ticker::router())
         .nest("/favorite", groups::router())
+        .nest("/brokerage", brokerage::router())
+        .nest("/options", options::router())
+        .nest("/outcomes", outcomes::router())
+        .nest("/paper", paper::router())
+        .nest("/signals", signals::router())
+        .nest("/spreads", spreads::router())
         .nest("/trading_view", trading_view::router())
-        .merge(websocket::router());
+        .merge(websocket::router())
+        .route_layer(middleware::from_fn(auth::require_login))
+        .nest("/auth", auth::router());
     let mut router = Router::new().nest("/api", api_routers);
     if let Some(asset_dir) = &APP_CONFIG.asset_dir
         && tokio::fs::try_exists(asset_dir).await?
@@ -56,9 +80,10 @@ async fn main() -> anyhow::Result<()> {
                 .not_found_service(ServeFile::new(format!("{asset_dir}/index.html"))),
         );
     }
-    router = router.layer(CompressionLayer::new());
-    info!("Initialized server in {:?}", start.elapsed());
+    Ok(router.layer(CompressionLayer::new()))
+}
 
+async fn serve(router: Router) -> anyhow::Result<()> {
     let http_port = APP_CONFIG.http_port;
     let socket_addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, http_port));
     if APP_CONFIG.use_https {
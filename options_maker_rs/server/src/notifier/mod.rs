@@ -0,0 +1,275 @@
+mod ntfy;
+mod smtp;
+mod webhook;
+
+use crate::analyzer::{Signal, Trend};
+use app_config::{APP_CONFIG, NotifierConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local};
+use chrono_tz::Tz;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+pub use ntfy::NtfyNotifier;
+pub use smtp::SmtpNotifier;
+pub use webhook::WebhookNotifier;
+
+static NOTIFY_SENDER: OnceLock<mpsc::UnboundedSender<Signal>> = OnceLock::new();
+
+/// Delivers signal alerts outside the browser, so they reach us even when no tab is open.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn notify(&self, notification: &Notification) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    pub signal: Signal,
+}
+
+impl From<Signal> for Notification {
+    fn from(signal: Signal) -> Self {
+        Self::new(signal, util::time::market_tz())
+    }
+}
+
+impl Notification {
+    /// Times of the signal are written as the wall clock of `tz`.
+    pub fn new(signal: Signal, tz: Tz) -> Self {
+        let (title, message) = match &signal {
+            Signal::Rejection(rejection) => (
+                format!("{} is {:?}", rejection.symbol, rejection.trend),
+                format!(
+                    "{} is {:?} at {}, rejected from {:.2} and closed at {:.2}.",
+                    rejection.symbol,
+                    rejection.trend,
+                    rejection.found_at.with_timezone(&tz).format("%H:%M:%S"),
+                    rejection.price_level,
+                    rejection.close,
+                ),
+            ),
+            Signal::Divergence(div) => (
+                format!(
+                    "{} {:?} divergence on {}m",
+                    div.symbol,
+                    div.divergence.trend,
+                    div.timeframe / 60
+                ),
+                format!(
+                    "{} {:?} divergence on {}m chart from {:.2} at {} to {:.2} at {}.",
+                    div.symbol,
+                    div.divergence.trend,
+                    div.timeframe / 60,
+                    div.divergence.start_price,
+                    div.divergence.start.format("%H:%M"),
+                    div.divergence.end_price,
+                    div.divergence.end.format("%H:%M"),
+                ),
+            ),
+            Signal::Liquidity(liquidity) => (
+                format!(
+                    "{} {} resting at {:.2}",
+                    liquidity.symbol,
+                    if liquidity.trend == Trend::Bullish {
+                        "bids"
+                    } else {
+                        "asks"
+                    },
+                    liquidity.price
+                ),
+                format!(
+                    "{} has {} resting at {:.2} on {}, near the price level {:.2} at {}.",
+                    liquidity.symbol,
+                    liquidity.size,
+                    liquidity.price,
+                    liquidity.venue,
+                    liquidity.price_level,
+                    liquidity.found_at.with_timezone(&tz).format("%H:%M:%S"),
+                ),
+            ),
+        };
+        Notification {
+            title,
+            message,
+            signal,
+        }
+    }
+}
+
+pub fn start_notifiers() -> anyhow::Result<()> {
+    let notifiers = APP_CONFIG
+        .notifiers
+        .iter()
+        .map(create_notifier)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if notifiers.is_empty() {
+        info!("No notifiers configured, alerts will only be shown in the browser");
+        return Ok(());
+    }
+
+    info!(
+        "Starting notifiers: {:?}",
+        notifiers.iter().map(|n| n.name()).collect::<Vec<_>>()
+    );
+    let (sender, mut signal_recv) = mpsc::unbounded_channel::<Signal>();
+    NOTIFY_SENDER
+        .set(sender)
+        .expect("Failed to initialize Notifier sender");
+    tokio::spawn(async move {
+        let mut sent = SentSignals::default();
+        while let Some(signal) = signal_recv.recv().await {
+            if !sent.insert(&signal) {
+                debug!(
+                    "Already notified {} signal of {}",
+                    signal.kind(),
+                    signal.symbol()
+                );
+                continue;
+            }
+
+            let notification = Notification::from(signal);
+            let deliveries = notifiers.iter().map(async |notifier| {
+                if let Err(e) = notifier.notify(&notification).await {
+                    warn!(
+                        "Failed to deliver '{}' through {}: {e}",
+                        notification.title,
+                        notifier.name()
+                    );
+                }
+            });
+            futures::future::join_all(deliveries).await;
+        }
+    });
+    Ok(())
+}
+
+/// Queues the signals of a favorite symbol for delivery; a no-op if no notifier is configured.
+pub fn notify(signals: &[Signal]) {
+    if let Some(sender) = NOTIFY_SENDER.get() {
+        for signal in signals {
+            sender.send(signal.clone()).ok();
+        }
+    }
+}
+
+fn create_notifier(config: &NotifierConfig) -> anyhow::Result<Box<dyn Notifier>> {
+    Ok(match config {
+        NotifierConfig::Webhook { url } => Box::new(WebhookNotifier::new(url)),
+        NotifierConfig::Ntfy { url, topic, token } => {
+            Box::new(NtfyNotifier::new(url, topic, token.clone()))
+        }
+        NotifierConfig::Smtp {
+            host,
+            port,
+            security,
+            username,
+            password,
+            from,
+            to,
+        } => Box::new(SmtpNotifier::new(
+            host,
+            *port,
+            *security,
+            username.clone().zip(password.clone()),
+            from,
+            to,
+        )?),
+    })
+}
+
+/// Remembers which signals were already delivered, so a rejection which keeps getting re-found on
+/// every tick, or a divergence which keeps extending, is notified only once.
+#[derive(Default)]
+struct SentSignals {
+    sent: FxHashMap<String, DateTime<Local>>,
+}
+
+impl SentSignals {
+    fn insert(&mut self, signal: &Signal) -> bool {
+        let now = signal.found_at();
+        self.sent.retain(|_, at| now - *at < Duration::days(1));
+        self.sent.insert(Self::key(signal), now).is_none()
+    }
+
+    fn key(signal: &Signal) -> String {
+        match signal {
+            Signal::Rejection(rejection) => format!(
+                "{}:{}:{:?}:{}",
+                rejection.symbol,
+                signal.kind(),
+                rejection.trend,
+                rejection.rejected_at.time.timestamp()
+            ),
+            Signal::Divergence(div) => format!(
+                "{}:{}:{}:{}",
+                div.symbol,
+                signal.kind(),
+                div.timeframe,
+                div.divergence.start.and_utc().timestamp()
+            ),
+            Signal::Liquidity(liquidity) => format!(
+                "{}:{}:{:?}:{}",
+                liquidity.symbol,
+                signal.kind(),
+                liquidity.trend,
+                liquidity.price
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SentSignals;
+    use crate::analyzer::{RejectionSignal, Signal, Trend};
+    use chrono::{Duration, Local, TimeZone};
+    use schwab_client::Candle;
+
+    pub fn rejection_signal() -> Signal {
+        let time = Local.with_ymd_and_hms(2025, 7, 1, 10, 0, 0).unwrap();
+        Signal::Rejection(RejectionSignal {
+            symbol: "SPY".to_owned(),
+            trend: Trend::Bullish,
+            price_level: 100.0,
+            is_imminent: false,
+            is_gap_fill: false,
+            found_at: time + Duration::minutes(1),
+            rejected_at: Candle {
+                open: 100.5,
+                low: 99.9,
+                high: 101.0,
+                close: 100.8,
+                volume: 1000,
+                time,
+                duration: 60,
+            },
+            close: 100.8,
+            atr: 1.0,
+            points: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut sent = SentSignals::default();
+        let signal = rejection_signal();
+        assert!(sent.insert(&signal));
+        assert!(!sent.insert(&signal));
+
+        let Signal::Rejection(mut rejection) = signal else {
+            unreachable!()
+        };
+        rejection.found_at += Duration::minutes(5);
+        assert!(!sent.insert(&Signal::Rejection(rejection.clone())));
+
+        rejection.rejected_at.time += Duration::minutes(5);
+        assert!(sent.insert(&Signal::Rejection(rejection)));
+    }
+}
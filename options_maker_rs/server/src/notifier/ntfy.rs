@@ -0,0 +1,57 @@
+use super::{Notification, Notifier};
+use crate::analyzer::{Signal, Trend};
+use async_trait::async_trait;
+use util::http::HTTP_CLIENT;
+
+/// Publishes to an [ntfy](https://ntfy.sh) topic, which pushes it to the phone app.
+pub struct NtfyNotifier {
+    url: String,
+    token: Option<String>,
+}
+
+impl NtfyNotifier {
+    pub fn new(url: &str, topic: &str, token: Option<String>) -> Self {
+        Self {
+            url: format!("{}/{topic}", url.trim_end_matches('/')),
+            token,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &'static str {
+        "Ntfy"
+    }
+
+    async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        let trend = match &notification.signal {
+            Signal::Rejection(rejection) => rejection.trend,
+            Signal::Divergence(div) => div.divergence.trend,
+            Signal::Liquidity(liquidity) => liquidity.trend,
+        };
+        let tag = if trend == Trend::Bearish {
+            "chart_with_downwards_trend"
+        } else {
+            "chart_with_upwards_trend"
+        };
+        let mut request = HTTP_CLIENT
+            .post(&self.url)
+            .header("Title", &notification.title)
+            .header("Tags", tag)
+            .header("Priority", "high")
+            .body(notification.message.clone());
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "Ntfy returned {}: {}",
+                response.status(),
+                response.text().await?
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use super::{Notification, Notifier};
+use app_config::SmtpSecurity;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl SmtpNotifier {
+    pub fn new(
+        host: &str,
+        port: u16,
+        security: SmtpSecurity,
+        credentials: Option<(String, String)>,
+        from: &str,
+        to: &[String],
+    ) -> anyhow::Result<Self> {
+        let mut builder = match security {
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        }
+        .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        if to.is_empty() {
+            anyhow::bail!("SMTP notifier needs at least one recipient");
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.parse()?,
+            to: to.iter().map(|to| to.parse()).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    fn name(&self) -> &'static str {
+        "Smtp"
+    }
+
+    async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(&notification.title)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let message = builder.body(notification.message.clone())?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SmtpNotifier;
+    use crate::notifier::test::rejection_signal;
+    use crate::notifier::{Notification, Notifier};
+    use app_config::SmtpSecurity;
+    use chrono_tz::America::New_York;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Bare minimum SMTP server which accepts a single mail and returns its DATA section.
+    async fn smtp_stub(listener: TcpListener) -> anyhow::Result<String> {
+        let (stream, _) = listener.accept().await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 localhost ESMTP stub\r\n").await?;
+
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await? {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    writer.write_all(b"250 OK\r\n").await?;
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let command = line.to_uppercase();
+            if command.starts_with("EHLO") {
+                writer.write_all(b"250 localhost\r\n").await?;
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                writer.write_all(b"354 Go ahead\r\n").await?;
+            } else if command.starts_with("QUIT") {
+                writer.write_all(b"221 Bye\r\n").await?;
+                break;
+            } else {
+                writer.write_all(b"250 OK\r\n").await?;
+            }
+        }
+        Ok(data)
+    }
+
+    #[tokio::test]
+    async fn test_smtp() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let stub = tokio::spawn(smtp_stub(listener));
+
+        let notifier = SmtpNotifier::new(
+            "127.0.0.1",
+            port,
+            SmtpSecurity::None,
+            None,
+            "alerts@localhost",
+            &["me@localhost".to_owned()],
+        )?;
+        notifier
+            .notify(&Notification::new(rejection_signal(), New_York))
+            .await?;
+
+        let data = stub.await??;
+        assert!(data.contains("Subject: SPY is Bullish"));
+        assert!(data.contains("To: me@localhost"));
+        assert!(data.contains("rejected from 100.00"));
+        Ok(())
+    }
+}
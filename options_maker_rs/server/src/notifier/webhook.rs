@@ -0,0 +1,76 @@
+use super::{Notification, Notifier};
+use async_trait::async_trait;
+use util::http::HTTP_CLIENT;
+
+/// POSTs every notification as json, to be consumed by Slack/Discord relays or custom scripts.
+pub struct WebhookNotifier {
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "Webhook"
+    }
+
+    async fn notify(&self, notification: &Notification) -> anyhow::Result<()> {
+        let response = HTTP_CLIENT
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "Webhook returned {}: {}",
+                response.status(),
+                response.text().await?
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::WebhookNotifier;
+    use crate::notifier::test::rejection_signal;
+    use crate::notifier::{Notification, Notifier};
+    use axum::Router;
+    use axum::routing::post;
+    use chrono_tz::America::New_York;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_webhook() -> anyhow::Result<()> {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<serde_json::Value>();
+        let router = Router::new().route(
+            "/hook",
+            post(
+                async move |axum::Json(body): axum::Json<serde_json::Value>| {
+                    sender.send(body).ok();
+                },
+            ),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let notifier = WebhookNotifier::new(format!("http://{addr}/hook"));
+        notifier
+            .notify(&Notification::new(rejection_signal(), New_York))
+            .await?;
+
+        let body = receiver.recv().await.unwrap();
+        assert_eq!(body["title"], "SPY is Bullish");
+        assert_eq!(body["signal"]["kind"], "Rejection");
+        assert_eq!(body["signal"]["price_level"], 100.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use crate::app_error::{AppError, AppResult};
+use crate::websocket;
+use axum::extract::Path;
+use axum::routing::{delete, get, put};
+use axum::{Json, Router};
+use data_provider::provider;
+use rustc_hash::FxHashMap;
+use schwab_client::Quote;
+use serde_json::json;
+use std::sync::{LazyLock, RwLock};
+use tracing::{debug, info};
+
+/// Latest quote of every subscribed option contract, keyed by the contract symbol.
+static OPTION_QUOTES: LazyLock<RwLock<FxHashMap<String, Quote>>> =
+    LazyLock::new(|| RwLock::new(FxHashMap::default()));
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", get(get_quotes))
+        .route("/{contract}", put(subscribe))
+        .route("/{contract}", delete(unsubscribe))
+}
+
+async fn get_quotes() -> Json<FxHashMap<String, Quote>> {
+    Json(OPTION_QUOTES.read().unwrap().clone())
+}
+
+async fn subscribe(Path(contract): Path<String>) -> AppResult<()> {
+    let contract = contract.trim().to_uppercase();
+    info!("Subscribing to option quotes of {contract:?}");
+    watch(&contract);
+    Ok(())
+}
+
+/// Starts streaming quotes of the contract unless it's already subscribed.
+pub fn watch(contract: &str) {
+    let mut quotes = OPTION_QUOTES.write().unwrap();
+    if !quotes.contains_key(contract) {
+        quotes.insert(contract.to_owned(), Quote::default());
+        provider().sub_options(vec![contract.to_owned()]);
+    }
+}
+
+async fn unsubscribe(Path(contract): Path<String>) -> AppResult<()> {
+    let contract = contract.trim().to_uppercase();
+    info!("Unsubscribing from option quotes of {contract:?}");
+    if OPTION_QUOTES.write().unwrap().remove(&contract).is_none() {
+        return Err(AppError::Generic(format!("{contract:?} is not subscribed")));
+    }
+    provider().unsub_options(vec![contract.clone()]);
+    websocket::publish("REMOVE_OPTION_QUOTE", contract);
+    Ok(())
+}
+
+pub fn on_quote(contract: String, quote: Quote) {
+    let mut quotes = OPTION_QUOTES.write().unwrap();
+    let Some(cached) = quotes.get_mut(&contract) else {
+        debug!("Ignoring option quote of unsubscribed contract {contract}");
+        return;
+    };
+    *cached = quote;
+    let data = json!({ "symbol": contract, "quote": cached });
+    drop(quotes);
+    websocket::publish("UPDATE_OPTION_QUOTE", data);
+}
+
+pub fn publish() {
+    websocket::publish("UPDATE_OPTION_QUOTES", &*OPTION_QUOTES.read().unwrap());
+}
@@ -0,0 +1,39 @@
+use crate::analyzer::OutcomeSummary;
+use crate::app_error::AppResult;
+use axum::extract::Query;
+use axum::routing::get;
+use axum::{Json, Router};
+use persist::outcome::SignalOutcome;
+use serde::Deserialize;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", get(list_outcomes))
+        .route("/summary", get(outcome_summary))
+}
+
+#[derive(Deserialize, Debug)]
+struct OutcomeFilter {
+    symbol: Option<String>,
+    #[serde(default = "default_days")]
+    days: u64,
+}
+
+fn default_days() -> u64 {
+    30
+}
+
+async fn list_outcomes(Query(filter): Query<OutcomeFilter>) -> AppResult<Json<Vec<SignalOutcome>>> {
+    let outcomes = load_outcomes(&filter).await?;
+    Ok(Json(outcomes))
+}
+
+async fn outcome_summary(Query(filter): Query<OutcomeFilter>) -> AppResult<Json<OutcomeSummary>> {
+    let outcomes = load_outcomes(&filter).await?;
+    Ok(Json(OutcomeSummary::new(&outcomes)))
+}
+
+async fn load_outcomes(filter: &OutcomeFilter) -> persist::Result<Vec<SignalOutcome>> {
+    let start = util::time::days_ago(filter.days);
+    persist::outcome::load_outcomes(filter.symbol.as_deref(), start).await
+}
@@ -0,0 +1,458 @@
+use app_config::PaperTradingConfig;
+use chrono::{DateTime, Duration, Local};
+use persist::paper::{PaperAccount, PaperOrder, PaperPosition};
+use rustc_hash::{FxHashMap, FxHashSet};
+use schwab_client::{Candle, Quote};
+use serde::{Deserialize, Serialize};
+
+pub const STATUS_OPEN: &str = "OPEN";
+pub const STATUS_FILLED: &str = "FILLED";
+pub const STATUS_CANCELLED: &str = "CANCELLED";
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AssetType {
+    #[default]
+    Equity,
+    Option,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OrderType {
+    #[default]
+    Market,
+    Limit,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct MarketPrice {
+    pub bid: f64,
+    pub ask: f64,
+    pub last: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PositionView {
+    #[serde(flatten)]
+    pub position: PaperPosition,
+    pub mark: f64,
+    pub market_value: f64,
+    pub unrealized_pnl: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AccountSummary {
+    pub number: &'static str,
+    pub balance: f64,
+    pub cash: f64,
+    pub starting_balance: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub commissions: f64,
+}
+
+/// A filled order along with the position it changed.
+pub struct Fill {
+    pub order: PaperOrder,
+    pub position: PaperPosition,
+}
+
+/// In-memory book of the paper account: cash, positions, working orders and the latest prices
+/// used to fill and mark them.
+pub struct Ledger {
+    account: PaperAccount,
+    positions: FxHashMap<String, PaperPosition>,
+    open_orders: Vec<PaperOrder>,
+    prices: FxHashMap<String, MarketPrice>,
+    commission_per_contract: f64,
+    commission_per_share: f64,
+}
+
+impl AssetType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AssetType::Equity => "EQUITY",
+            AssetType::Option => "OPTION",
+        }
+    }
+}
+
+impl Side {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        }
+    }
+}
+
+impl OrderType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderType::Market => "MARKET",
+            OrderType::Limit => "LIMIT",
+        }
+    }
+}
+
+impl MarketPrice {
+    fn mark(&self) -> f64 {
+        if self.bid > 0.0 && self.ask > 0.0 {
+            (self.bid + self.ask) / 2.0
+        } else {
+            self.last
+        }
+    }
+}
+
+impl Ledger {
+    pub fn new(
+        account: PaperAccount,
+        positions: Vec<PaperPosition>,
+        open_orders: Vec<PaperOrder>,
+        config: &PaperTradingConfig,
+    ) -> Self {
+        Self {
+            account,
+            positions: positions
+                .into_iter()
+                .map(|p| (p.symbol.clone(), p))
+                .collect(),
+            open_orders,
+            prices: FxHashMap::default(),
+            commission_per_contract: config.commission_per_contract,
+            commission_per_share: config.commission_per_share,
+        }
+    }
+
+    pub fn account(&self) -> &PaperAccount {
+        &self.account
+    }
+
+    pub fn open_orders(&self) -> &[PaperOrder] {
+        &self.open_orders
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&PaperPosition> {
+        self.positions.get(symbol)
+    }
+
+    /// Equities with a position or an open order, they need quotes to be marked and filled.
+    pub fn tracked_equities(&self) -> FxHashSet<String> {
+        let option = AssetType::Option.as_str();
+        let positions = self
+            .positions
+            .values()
+            .filter(|p| p.asset_type != option)
+            .map(|p| &p.symbol);
+        let orders = self
+            .open_orders
+            .iter()
+            .filter(|o| o.asset_type != option)
+            .map(|o| &o.symbol);
+        positions.chain(orders).cloned().collect()
+    }
+
+    pub fn is_tracked(&self, symbol: &str) -> bool {
+        self.positions.contains_key(symbol) || self.open_orders.iter().any(|o| o.symbol == symbol)
+    }
+
+    /// Queues a new order, market orders are filled right away if there is a quote to fill at.
+    pub fn place(&mut self, order: PaperOrder, now: DateTime<Local>) -> Option<Fill> {
+        let price = self.prices.get(&order.symbol).copied();
+        self.open_orders.push(order);
+        let price = price?;
+        let idx = self.open_orders.len() - 1;
+        let fill_price = quote_fill_price(&self.open_orders[idx], &price)?;
+        let order = self.open_orders.remove(idx);
+        Some(self.fill(order, fill_price, now))
+    }
+
+    pub fn cancel(&mut self, order_id: i64) -> Option<PaperOrder> {
+        let idx = self
+            .open_orders
+            .iter()
+            .position(|o| o.order_id == order_id)?;
+        let mut order = self.open_orders.remove(idx);
+        order.status = STATUS_CANCELLED.to_owned();
+        Some(order)
+    }
+
+    /// Fills at the bid/ask of the quote.
+    pub fn on_quote(&mut self, symbol: &str, quote: &Quote) -> Vec<Fill> {
+        let price = self.prices.entry(symbol.to_owned()).or_default();
+        price.bid = quote.bid_price;
+        price.ask = quote.ask_price;
+        price.last = quote.last_price;
+        let price = *price;
+        let now = quote.quote_time.unwrap_or_else(util::time::now);
+        self.fill_orders(symbol, now, |order| quote_fill_price(order, &price))
+    }
+
+    /// Fills market orders at the candle close, and limit orders whose price was traded through.
+    pub fn on_candle(&mut self, symbol: &str, candle: &Candle) -> Vec<Fill> {
+        let price = self.prices.entry(symbol.to_owned()).or_default();
+        price.last = candle.close;
+        let now = candle.time + Duration::seconds(candle.duration);
+        self.fill_orders(symbol, now, |order| {
+            let is_buy = order.side == Side::Buy.as_str();
+            match order.limit_price {
+                None => Some(candle.close),
+                Some(limit) if is_buy && candle.low <= limit => Some(limit.min(candle.open)),
+                Some(limit) if !is_buy && candle.high >= limit => Some(limit.max(candle.open)),
+                Some(_) => None,
+            }
+        })
+    }
+
+    pub fn positions(&self) -> Vec<PositionView> {
+        let mut positions = self
+            .positions
+            .values()
+            .map(|position| {
+                let mark = self
+                    .prices
+                    .get(&position.symbol)
+                    .map(MarketPrice::mark)
+                    .filter(|mark| *mark > 0.0)
+                    .unwrap_or(position.avg_price);
+                let quantity = position.quantity as f64;
+                PositionView {
+                    position: position.clone(),
+                    mark,
+                    market_value: quantity * mark * position.multiplier,
+                    unrealized_pnl: quantity * (mark - position.avg_price) * position.multiplier,
+                }
+            })
+            .collect::<Vec<_>>();
+        positions.sort_by_key(|p| p.position.opened_at);
+        positions
+    }
+
+    pub fn summary(&self) -> AccountSummary {
+        let positions = self.positions();
+        let market_value = positions.iter().map(|p| p.market_value).sum::<f64>();
+        AccountSummary {
+            number: "PAPER",
+            balance: self.account.cash + market_value,
+            cash: self.account.cash,
+            starting_balance: self.account.starting_balance,
+            realized_pnl: self.account.realized_pnl,
+            unrealized_pnl: positions.iter().map(|p| p.unrealized_pnl).sum(),
+            commissions: self.account.commissions,
+        }
+    }
+
+    fn fill_orders(
+        &mut self,
+        symbol: &str,
+        now: DateTime<Local>,
+        fill_price: impl Fn(&PaperOrder) -> Option<f64>,
+    ) -> Vec<Fill> {
+        let mut fills = Vec::new();
+        let mut idx = 0;
+        while idx < self.open_orders.len() {
+            let order = &self.open_orders[idx];
+            if order.symbol == symbol
+                && let Some(price) = fill_price(order)
+            {
+                let order = self.open_orders.remove(idx);
+                fills.push(self.fill(order, price, now));
+            } else {
+                idx += 1;
+            }
+        }
+        fills
+    }
+
+    fn fill(&mut self, mut order: PaperOrder, price: f64, now: DateTime<Local>) -> Fill {
+        let is_option = order.asset_type == AssetType::Option.as_str();
+        let multiplier = if is_option { 100.0 } else { 1.0 };
+        let per_unit = if is_option {
+            self.commission_per_contract
+        } else {
+            self.commission_per_share
+        };
+        let commission = per_unit * order.quantity as f64;
+        let delta = if order.side == Side::Buy.as_str() {
+            order.quantity
+        } else {
+            -order.quantity
+        };
+
+        order.status = STATUS_FILLED.to_owned();
+        order.fill_price = Some(price);
+        order.filled_at = Some(now);
+        order.commission = commission;
+
+        let position = self
+            .positions
+            .entry(order.symbol.clone())
+            .or_insert_with(|| PaperPosition {
+                symbol: order.symbol.clone(),
+                asset_type: order.asset_type.clone(),
+                quantity: 0,
+                avg_price: 0.0,
+                multiplier,
+                realized_pnl: 0.0,
+                opened_at: now,
+                updated_at: now,
+            });
+        let old_qty = position.quantity;
+        let new_qty = old_qty + delta;
+        let mut realized = 0.0;
+        if old_qty != 0 && old_qty.signum() != delta.signum() {
+            let closed = old_qty.abs().min(delta.abs()) as f64;
+            realized = closed * (price - position.avg_price) * old_qty.signum() as f64 * multiplier;
+        }
+        if old_qty == 0 || (new_qty != 0 && new_qty.signum() != old_qty.signum()) {
+            // Newly opened or flipped to the other side
+            position.avg_price = price;
+            position.opened_at = now;
+        } else if old_qty.signum() == delta.signum() {
+            position.avg_price = (position.avg_price * old_qty.abs() as f64
+                + price * delta.abs() as f64)
+                / new_qty.abs() as f64;
+        }
+        position.quantity = new_qty;
+        position.realized_pnl += realized;
+        position.updated_at = now;
+
+        self.account.cash -= delta as f64 * price * multiplier + commission;
+        self.account.realized_pnl += realized;
+        self.account.commissions += commission;
+
+        let position = if new_qty == 0 {
+            self.positions.remove(&order.symbol).unwrap()
+        } else {
+            position.clone()
+        };
+        Fill { order, position }
+    }
+}
+
+fn quote_fill_price(order: &PaperOrder, price: &MarketPrice) -> Option<f64> {
+    if order.side == Side::Buy.as_str() {
+        let ask = Some(price.ask).filter(|ask| *ask > 0.0)?;
+        order
+            .limit_price
+            .is_none_or(|limit| ask <= limit)
+            .then_some(ask)
+    } else {
+        let bid = Some(price.bid).filter(|bid| *bid > 0.0)?;
+        order
+            .limit_price
+            .is_none_or(|limit| bid >= limit)
+            .then_some(bid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AssetType, Ledger, OrderType, STATUS_FILLED, STATUS_OPEN, Side};
+    use app_config::PaperTradingConfig;
+    use chrono::{Local, TimeZone};
+    use persist::paper::{PaperAccount, PaperOrder};
+    use schwab_client::{Candle, Quote};
+
+    fn order(order_id: i64, side: Side, quantity: i64, limit_price: Option<f64>) -> PaperOrder {
+        PaperOrder {
+            order_id,
+            symbol: "SPXW  250703P06200000".to_owned(),
+            asset_type: AssetType::Option.as_str().to_owned(),
+            side: side.as_str().to_owned(),
+            quantity,
+            order_type: if limit_price.is_some() {
+                OrderType::Limit
+            } else {
+                OrderType::Market
+            }
+            .as_str()
+            .to_owned(),
+            limit_price,
+            status: STATUS_OPEN.to_owned(),
+            created_at: Local::now(),
+            filled_at: None,
+            fill_price: None,
+            commission: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_fills_and_pnl() {
+        let account = PaperAccount {
+            starting_balance: 10_000.0,
+            cash: 10_000.0,
+            realized_pnl: 0.0,
+            commissions: 0.0,
+            created_at: Local::now(),
+        };
+        let config = PaperTradingConfig {
+            starting_balance: 10_000.0,
+            commission_per_contract: 0.5,
+            commission_per_share: 0.0,
+        };
+        let mut ledger = Ledger::new(account, Vec::new(), Vec::new(), &config);
+        let symbol = "SPXW  250703P06200000";
+        let now = Local::now();
+
+        // No quote yet, so the order keeps waiting
+        assert!(ledger.place(order(1, Side::Sell, 2, None), now).is_none());
+        let quote = Quote {
+            bid_price: 2.0,
+            ask_price: 2.2,
+            last_price: 2.1,
+            ..Default::default()
+        };
+        let fills = ledger.on_quote(symbol, &quote);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order.status, STATUS_FILLED);
+        assert_eq!(fills[0].order.fill_price, Some(2.0));
+        assert_eq!(fills[0].position.quantity, -2);
+        assert_eq!(ledger.account().cash, 10_000.0 + 400.0 - 1.0);
+
+        let summary = ledger.summary();
+        assert!((summary.unrealized_pnl + 20.0).abs() < 1e-9);
+
+        // Buy back one contract with a limit order, filled when a candle trades through it
+        assert!(
+            ledger
+                .place(order(2, Side::Buy, 1, Some(1.5)), now)
+                .is_none()
+        );
+        let candle = Candle {
+            open: 1.8,
+            low: 1.4,
+            high: 1.9,
+            close: 1.6,
+            volume: 10,
+            time: Local.with_ymd_and_hms(2025, 7, 3, 10, 0, 0).unwrap(),
+            duration: 60,
+        };
+        let fills = ledger.on_candle(symbol, &candle);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order.fill_price, Some(1.5));
+        assert_eq!(fills[0].position.quantity, -1);
+        assert_eq!(fills[0].position.avg_price, 2.0);
+        assert_eq!(ledger.account().realized_pnl, 50.0);
+        assert!(ledger.open_orders().is_empty());
+
+        // Cancelled orders never fill
+        assert!(
+            ledger
+                .place(order(3, Side::Buy, 1, Some(0.5)), now)
+                .is_none()
+        );
+        assert!(ledger.cancel(3).is_some());
+        assert!(ledger.on_candle(symbol, &candle).is_empty());
+        assert!(ledger.position(symbol).is_some());
+    }
+}
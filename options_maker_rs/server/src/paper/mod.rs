@@ -0,0 +1,362 @@
+mod ledger;
+
+use crate::app_error::{AppError, AppResult};
+use crate::{options, websocket};
+use app_config::APP_CONFIG;
+use axum::extract::{Path, Query};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use chrono::Duration as ChronoDuration;
+use data_provider::provider;
+pub use ledger::{AccountSummary, AssetType, OrderType, PositionView, Side};
+use ledger::{Fill, Ledger, STATUS_OPEN};
+use persist::paper::{PaperAccount, PaperOrder};
+use rustc_hash::FxHashSet;
+use schwab_client::streaming_client::StreamResponse;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+
+static CMD_SENDER: OnceLock<mpsc::UnboundedSender<PaperCmd>> = OnceLock::new();
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/account", get(get_account))
+        .route("/positions", get(get_positions))
+        .route("/positions/{symbol}", delete(close_position))
+        .route("/orders", get(get_orders))
+        .route("/orders", post(post_order))
+        .route("/orders/{order_id}", delete(cancel_order))
+}
+
+pub enum PaperCmd {
+    PlaceOrder(OrderRequest, oneshot::Sender<anyhow::Result<PaperOrder>>),
+    CancelOrder(i64, oneshot::Sender<anyhow::Result<PaperOrder>>),
+    ClosePosition(String, oneshot::Sender<anyhow::Result<PaperOrder>>),
+    Summary(oneshot::Sender<AccountSummary>),
+    Positions(oneshot::Sender<Vec<PositionView>>),
+    Publish,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderRequest {
+    pub symbol: String,
+    #[serde(default)]
+    pub asset_type: AssetType,
+    pub side: Side,
+    pub quantity: u32,
+    #[serde(default)]
+    pub order_type: OrderType,
+    pub limit_price: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct OrdersQuery {
+    status: Option<String>,
+    #[serde(default = "default_days")]
+    days: i64,
+}
+
+fn default_days() -> i64 {
+    7
+}
+
+async fn get_account() -> AppResult<Json<AccountSummary>> {
+    let summary = account_summary()
+        .await
+        .ok_or_else(|| AppError::Generic("Paper trading is not running".to_owned()))?;
+    Ok(Json(summary))
+}
+
+async fn get_positions() -> AppResult<Json<Vec<PositionView>>> {
+    let (sender, receiver) = oneshot::channel();
+    send_paper_cmd(PaperCmd::Positions(sender));
+    let positions = receiver
+        .await
+        .map_err(|_| AppError::Generic("Paper trading is not running".to_owned()))?;
+    Ok(Json(positions))
+}
+
+async fn get_orders(Query(query): Query<OrdersQuery>) -> AppResult<Json<Vec<PaperOrder>>> {
+    let start = util::time::now() - ChronoDuration::days(query.days);
+    let status = query.status.map(|s| s.to_uppercase());
+    let orders = persist::paper::load_orders(status.as_deref(), start).await?;
+    Ok(Json(orders))
+}
+
+async fn post_order(Json(request): Json<OrderRequest>) -> AppResult<Json<PaperOrder>> {
+    let (sender, receiver) = oneshot::channel();
+    send_paper_cmd(PaperCmd::PlaceOrder(request, sender));
+    wait_for_order(receiver).await
+}
+
+async fn cancel_order(Path(order_id): Path<i64>) -> AppResult<Json<PaperOrder>> {
+    let (sender, receiver) = oneshot::channel();
+    send_paper_cmd(PaperCmd::CancelOrder(order_id, sender));
+    wait_for_order(receiver).await
+}
+
+async fn close_position(Path(symbol): Path<String>) -> AppResult<Json<PaperOrder>> {
+    let (sender, receiver) = oneshot::channel();
+    send_paper_cmd(PaperCmd::ClosePosition(symbol, sender));
+    wait_for_order(receiver).await
+}
+
+async fn wait_for_order(
+    receiver: oneshot::Receiver<anyhow::Result<PaperOrder>>,
+) -> AppResult<Json<PaperOrder>> {
+    let order = receiver
+        .await
+        .map_err(|_| AppError::Generic("Paper trading is not running".to_owned()))??;
+    Ok(Json(order))
+}
+
+/// Runs the paper account off the same stream feed as the analyzer, filling working orders at
+/// the bid/ask of option and equity quotes, or at candle closes when there are no quotes.
+pub async fn start_paper_trading() -> anyhow::Result<()> {
+    let (sender, mut cmd_recv) = mpsc::unbounded_channel::<PaperCmd>();
+    CMD_SENDER
+        .set(sender)
+        .expect("Failed to initialize Paper trading commander");
+
+    let config = &APP_CONFIG.paper_trading;
+    let account = match persist::paper::load_account().await? {
+        Some(account) => account,
+        None => {
+            info!(
+                "Creating paper account with balance of {}",
+                config.starting_balance
+            );
+            let account = PaperAccount {
+                starting_balance: config.starting_balance,
+                cash: config.starting_balance,
+                realized_pnl: 0.0,
+                commissions: 0.0,
+                created_at: util::time::now(),
+            };
+            persist::paper::save_account(&account).await?;
+            account
+        }
+    };
+    let positions = persist::paper::load_positions().await?;
+    let open_orders =
+        persist::paper::load_orders(Some(STATUS_OPEN), chrono::DateTime::default()).await?;
+    info!(
+        "Loaded paper account with {} positions and {} open orders",
+        positions.len(),
+        open_orders.len()
+    );
+    for order in &open_orders {
+        watch(
+            order.asset_type == AssetType::Option.as_str(),
+            &order.symbol,
+        );
+    }
+    for position in &positions {
+        watch(
+            position.asset_type == AssetType::Option.as_str(),
+            &position.symbol,
+        );
+    }
+
+    let mut ledger = Ledger::new(account, positions, open_orders, config);
+    let mut watched = FxHashSet::default();
+    watch_equities(&ledger, &mut watched);
+    let mut stream_listener = provider().listener();
+    let mut publish_timer = tokio::time::interval(Duration::from_secs(1));
+    tokio::spawn(async move {
+        let mut is_dirty = false;
+        loop {
+            tokio::select! {
+                Some(stream_res) = stream_listener.recv() => {
+                    let fills = match &stream_res {
+                        StreamResponse::Equity { symbol, candle }
+                        | StreamResponse::Futures { symbol, candle } if ledger.is_tracked(symbol) => {
+                            ledger.on_candle(symbol, candle)
+                        }
+                        StreamResponse::EquityLevelOne { symbol, quote }
+                        | StreamResponse::FuturesLevelOne { symbol, quote }
+                        | StreamResponse::OptionsLevelOne { symbol, quote } if ledger.is_tracked(symbol) => {
+                            ledger.on_quote(symbol, quote)
+                        }
+                        _ => continue,
+                    };
+                    is_dirty = true;
+                    if !fills.is_empty() {
+                        save_fills(&ledger, fills).await;
+                        watch_equities(&ledger, &mut watched);
+                    }
+                }
+                Some(cmd) = cmd_recv.recv() => {
+                    handle_cmd(&mut ledger, cmd).await;
+                    watch_equities(&ledger, &mut watched);
+                    is_dirty = true;
+                }
+                _ = publish_timer.tick() => {
+                    if is_dirty {
+                        publish(&ledger);
+                        is_dirty = false;
+                    }
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn handle_cmd(ledger: &mut Ledger, cmd: PaperCmd) {
+    match cmd {
+        PaperCmd::PlaceOrder(request, sender) => {
+            sender.send(place_order(ledger, request).await).ok();
+        }
+        PaperCmd::CancelOrder(order_id, sender) => {
+            let result = match ledger.cancel(order_id) {
+                Some(order) => persist::paper::save_order(&order)
+                    .await
+                    .map(|_| order)
+                    .map_err(Into::into),
+                None => Err(anyhow::anyhow!("No open order found with id {order_id}")),
+            };
+            sender.send(result).ok();
+        }
+        PaperCmd::ClosePosition(symbol, sender) => {
+            let result = match ledger.position(&symbol) {
+                Some(position) => {
+                    let request = OrderRequest {
+                        symbol,
+                        asset_type: if position.asset_type == AssetType::Option.as_str() {
+                            AssetType::Option
+                        } else {
+                            AssetType::Equity
+                        },
+                        side: if position.quantity > 0 {
+                            Side::Sell
+                        } else {
+                            Side::Buy
+                        },
+                        quantity: position.quantity.unsigned_abs() as u32,
+                        order_type: OrderType::Market,
+                        limit_price: None,
+                    };
+                    place_order(ledger, request).await
+                }
+                None => Err(anyhow::anyhow!("No open position found for {symbol}")),
+            };
+            sender.send(result).ok();
+        }
+        PaperCmd::Summary(sender) => {
+            sender.send(ledger.summary()).ok();
+        }
+        PaperCmd::Positions(sender) => {
+            sender.send(ledger.positions()).ok();
+        }
+        PaperCmd::Publish => publish(ledger),
+    }
+}
+
+async fn place_order(ledger: &mut Ledger, request: OrderRequest) -> anyhow::Result<PaperOrder> {
+    if request.quantity == 0 {
+        anyhow::bail!("Order quantity must be positive");
+    }
+    if (request.order_type == OrderType::Limit) != request.limit_price.is_some() {
+        anyhow::bail!("Only limit orders must have a limit price");
+    }
+
+    let symbol = request.symbol.trim().to_uppercase();
+    let is_option = request.asset_type == AssetType::Option;
+    let now = util::time::now();
+    let mut order = PaperOrder {
+        order_id: 0,
+        symbol: symbol.clone(),
+        asset_type: request.asset_type.as_str().to_owned(),
+        side: request.side.as_str().to_owned(),
+        quantity: request.quantity as i64,
+        order_type: request.order_type.as_str().to_owned(),
+        limit_price: request.limit_price,
+        status: STATUS_OPEN.to_owned(),
+        created_at: now,
+        filled_at: None,
+        fill_price: None,
+        commission: 0.0,
+    };
+    order.order_id = persist::paper::save_order(&order).await?;
+    info!(
+        "Placed paper order #{}: {} {} {symbol}",
+        order.order_id, order.side, order.quantity
+    );
+    watch(is_option, &symbol);
+
+    match ledger.place(order.clone(), now) {
+        Some(fill) => {
+            let order = fill.order.clone();
+            save_fills(ledger, vec![fill]).await;
+            Ok(order)
+        }
+        None => Ok(order),
+    }
+}
+
+async fn save_fills(ledger: &Ledger, fills: Vec<Fill>) {
+    if fills.is_empty() {
+        return;
+    }
+
+    for Fill { order, position } in fills {
+        info!(
+            "Filled paper order #{}: {} {} {} at {:?}",
+            order.order_id, order.side, order.quantity, order.symbol, order.fill_price
+        );
+        if let Err(e) = persist::paper::save_order(&order).await {
+            warn!("Failed to save paper order #{}: {e}", order.order_id);
+        }
+        if let Err(e) = persist::paper::save_position(&position).await {
+            warn!("Failed to save paper position of {}: {e}", position.symbol);
+        }
+    }
+    if let Err(e) = persist::paper::save_account(ledger.account()).await {
+        warn!("Failed to save paper account: {e}");
+    }
+}
+
+/// Option quotes are only streamed for subscribed contracts, the equities are followed by
+/// [watch_equities].
+fn watch(is_option: bool, symbol: &str) {
+    if is_option {
+        options::watch(symbol);
+    }
+}
+
+/// Streams the quotes of the equities the ledger tracks, whether they're on the watchlist or
+/// not, and drops the interest in the ones it no longer does.
+fn watch_equities(ledger: &Ledger, watched: &mut FxHashSet<String>) {
+    let tracked = ledger.tracked_equities();
+    data_provider::sub_tick(tracked.difference(watched).cloned().collect());
+    data_provider::unsub_tick(watched.difference(&tracked).cloned().collect());
+    *watched = tracked;
+}
+
+fn publish(ledger: &Ledger) {
+    websocket::publish(
+        "UPDATE_POSITIONS",
+        json!({
+            "positions": ledger.positions(),
+            "orders": ledger.open_orders(),
+        }),
+    );
+    websocket::publish("UPDATE_ACCOUNT", ledger.summary());
+}
+
+pub async fn account_summary() -> Option<AccountSummary> {
+    let (sender, receiver) = oneshot::channel();
+    send_paper_cmd(PaperCmd::Summary(sender));
+    receiver.await.ok()
+}
+
+pub fn send_paper_cmd(cmd: PaperCmd) {
+    if let Some(sender) = CMD_SENDER.get() {
+        sender.send(cmd).ok();
+    }
+}
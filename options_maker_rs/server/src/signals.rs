@@ -0,0 +1,56 @@
+use crate::app_error::{AppError, AppResult};
+use axum::extract::Query;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Local, NaiveDate};
+use persist::signal::SignalRecord;
+use serde::Deserialize;
+use util::time::MarketTime;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/", get(list_signals))
+        .route("/symbols", get(list_symbols))
+}
+
+#[derive(Deserialize, Debug)]
+struct SignalFilter {
+    symbol: Option<String>,
+    kind: Option<String>,
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+}
+
+async fn list_signals(Query(filter): Query<SignalFilter>) -> AppResult<Json<Vec<SignalRecord>>> {
+    let (start, end) = filter.date_range()?;
+    let signals =
+        persist::signal::load_signals(filter.symbol.as_deref(), filter.kind.as_deref(), start, end)
+            .await?;
+    Ok(Json(signals))
+}
+
+async fn list_symbols(Query(filter): Query<SignalFilter>) -> AppResult<Json<Vec<String>>> {
+    let (start, end) = filter.date_range()?;
+    Ok(Json(persist::signal::signal_symbols(start, end).await?))
+}
+
+impl SignalFilter {
+    /// Defaults to today's session when no dates are given, `end` is inclusive.
+    fn date_range(&self) -> AppResult<(DateTime<Local>, DateTime<Local>)> {
+        let today = util::time::now().market_date();
+        let start = self.start.unwrap_or(today);
+        let end = self.end.unwrap_or(today);
+        if start > end {
+            return Err(AppError::Generic(format!(
+                "Invalid date range: {start} > {end}"
+            )));
+        }
+        let end = end
+            .succ_opt()
+            .ok_or_else(|| AppError::Generic(format!("Invalid date range: {start} - {end}")))?;
+        Ok((
+            util::time::market_day_start(start),
+            util::time::market_day_start(end),
+        ))
+    }
+}
@@ -0,0 +1,74 @@
+use crate::analyzer;
+use crate::analyzer::{AnalyzerCmd, SpreadBuilder, SpreadCandidate, StrikeTarget};
+use crate::app_error::{AppError, AppResult};
+use crate::websocket;
+use app_config::APP_CONFIG;
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use data_provider::provider;
+use schwab_client::OptionChainParams;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::oneshot;
+use tracing::info;
+use util::time::MarketTime;
+
+pub fn router() -> Router {
+    Router::new().route("/", post(build_spreads))
+}
+
+#[derive(Deserialize)]
+struct SpreadRequest {
+    symbol: String,
+    expiration: Option<NaiveDate>,
+    target: StrikeTarget,
+    #[serde(default = "default_widths")]
+    widths: Vec<f64>,
+}
+
+fn default_widths() -> Vec<f64> {
+    vec![5.0]
+}
+
+async fn build_spreads(
+    Json(request): Json<SpreadRequest>,
+) -> AppResult<Json<Vec<SpreadCandidate>>> {
+    let symbol = request.symbol.trim().to_uppercase();
+    let now = util::time::now();
+    let expiration = request.expiration.unwrap_or(now.market_date());
+    info!(
+        "Building {:?} spreads of {symbol} expiring on {expiration}",
+        request.target
+    );
+
+    let (sender, receiver) = oneshot::channel();
+    analyzer::send_analyzer_cmd(AnalyzerCmd::PriceLevels(symbol.clone(), sender));
+    let price_levels = receiver.await.ok().flatten().unwrap_or_default();
+    if price_levels.is_empty() && matches!(request.target, StrikeTarget::LevelDistance(_)) {
+        return Err(AppError::Generic(format!(
+            "No price levels found for {symbol}"
+        )));
+    }
+
+    let params = OptionChainParams {
+        from_date: Some(expiration),
+        to_date: Some(expiration),
+        ..Default::default()
+    };
+    let chain = provider().fetch_option_chain(&symbol, &params).await?;
+    let builder = SpreadBuilder::new(
+        &chain,
+        expiration,
+        &price_levels,
+        now.market_naive(),
+        APP_CONFIG.trade_config.trading_hours.1,
+    );
+    let candidates = builder.candidates(request.target, &request.widths);
+    info!("Found {} spread candidates for {symbol}", candidates.len());
+    websocket::publish(
+        "UPDATE_SPREADS",
+        json!({ "symbol": symbol, "candidates": candidates }),
+    );
+    Ok(Json(candidates))
+}
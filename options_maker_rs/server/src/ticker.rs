@@ -1,11 +1,12 @@
 use crate::analyzer;
 use crate::analyzer::AnalyzerCmd;
 use crate::app_error::{AppError, AppResult};
+use crate::websocket;
 use app_config::APP_CONFIG;
 use axum::extract::Query;
 use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
-use data_provider::{ReplayInfo, provider};
+use data_provider::{ReplayCmd, ReplayInfo, provider};
 use serde::Deserialize;
 use std::collections::HashMap;
 use tokio::sync::oneshot;
@@ -16,6 +17,7 @@ pub fn router() -> Router {
         .route("/add", put(add_new_ticker))
         .route("/remove", delete(remove_ticker))
         .route("/replay_info", post(update_replay_info))
+        .route("/replay", post(control_replay))
         .route("/reload", get(reload_ticker))
         .route("/reset_levels", get(reset_levels))
         .route("/update_price_levels", post(override_price_levels))
@@ -78,6 +80,28 @@ async fn update_replay_info(Json(replay): Json<ReplayInfo>) -> AppResult<()> {
     Ok(())
 }
 
+async fn control_replay(Json(cmd): Json<ReplayCmd>) -> AppResult<()> {
+    run_replay_cmd(cmd).await
+}
+
+/// Seeking and rewinding warm the controllers up again with the candles up to the new clock time.
+pub async fn run_replay_cmd(cmd: ReplayCmd) -> AppResult<()> {
+    let needs_rebuild = cmd.needs_rebuild();
+    provider().replay_cmd(cmd).await?;
+    if needs_rebuild {
+        for instrument in persist::ticker::fetch_instruments().await? {
+            match analyzer::init_controller(&instrument).await {
+                Ok(controller) => {
+                    analyzer::send_analyzer_cmd(AnalyzerCmd::ReInitialize(controller.into()))
+                }
+                Err(e) => warn!("Failed to rebuild controller of {}: {e}", instrument.symbol),
+            }
+        }
+    }
+    websocket::publish("REPLAY_MODE", provider().replay_info(None).await);
+    Ok(())
+}
+
 async fn reload_ticker(Query(symbols): Query<HashMap<String, String>>) -> AppResult<()> {
     if !APP_CONFIG.replay_mode {
         return Err(AppError::Generic(
@@ -1,15 +1,17 @@
-use crate::analyzer;
 use crate::analyzer::AnalyzerCmd;
+use crate::paper::PaperCmd;
+use crate::{analyzer, auth, brokerage, options, paper, ticker};
 use app_config::APP_CONFIG;
 use axum::Router;
 use axum::extract::WebSocketUpgrade;
 use axum::extract::ws::{Message, WebSocket};
 use axum::routing::get;
-use data_provider::provider;
+use data_provider::{ReplayCmd, provider};
 use flate2::Compression;
 use flate2::read::DeflateEncoder;
 use futures::{SinkExt, StreamExt};
 use rustc_hash::FxHashMap;
+use serde::Deserialize;
 use serde_json::{Value, json};
 use std::io::Read;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -44,15 +46,16 @@ async fn handle_websocket(socket: WebSocket) -> anyhow::Result<()> {
     WS_SENDERS.write().unwrap().insert(ws_id, sender);
 
     let (mut ws_writer, mut ws_reader) = socket.split();
+    let mut account = match paper::account_summary().await {
+        Some(summary) => serde_json::to_value(summary)?,
+        None => json!({ "number": "NA", "balance": 0 }),
+    };
+    account["ws_id"] = ws_id.into();
     ws_writer
         .send(Message::text(
             json!({
                 "action": "UPDATE_ACCOUNT",
-                "data": {
-                    "ws_id": ws_id,
-                    "number": "NA",
-                    "balance": 0,
-                },
+                "data": account,
             })
             .to_string(),
         ))
@@ -62,6 +65,10 @@ async fn handle_websocket(socket: WebSocket) -> anyhow::Result<()> {
     tokio::spawn(async move {
         publish("REPLAY_MODE", provider().replay_info(None).await);
         analyzer::send_analyzer_cmd(AnalyzerCmd::Publish);
+        options::publish();
+        paper::send_paper_cmd(PaperCmd::Publish);
+        brokerage::publish();
+        auth::publish();
     });
 
     loop {
@@ -72,12 +79,14 @@ async fn handle_websocket(socket: WebSocket) -> anyhow::Result<()> {
                     break;
                 }
             }
-            Some(Ok(message)) = ws_reader.next() => {
-                if let Message::Close(_) = message {
+            Some(Ok(message)) = ws_reader.next() => match message {
+                Message::Close(_) => {
                     debug!("Closing websocket connection: {ws_id}");
                     break;
                 }
-            }
+                Message::Text(text) => on_client_message(ws_id, text.as_str()),
+                _ => {}
+            },
             _ = heartbeat_timer => {
                 let msg = json!({
                     "action": "HEARTBEAT",
@@ -95,6 +104,33 @@ async fn handle_websocket(socket: WebSocket) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Commands sent by the GUI over the websocket, in the same shape as the published messages.
+#[derive(Deserialize)]
+#[serde(tag = "action", content = "data")]
+enum ClientMessage {
+    #[serde(rename = "REPLAY")]
+    Replay(ReplayCmd),
+}
+
+fn on_client_message(ws_id: usize, text: &str) {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("Ignoring unexpected message from websocket {ws_id}: {e}");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        match message {
+            ClientMessage::Replay(cmd) => {
+                if let Err(e) = ticker::run_replay_cmd(cmd).await {
+                    warn!("Failed to run replay command of websocket {ws_id}: {e}");
+                }
+            }
+        }
+    });
+}
+
 pub fn publish(action: impl AsRef<str>, message: impl serde::Serialize) {
     let senders = WS_SENDERS.read().unwrap();
     if senders.is_empty() {
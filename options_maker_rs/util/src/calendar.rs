@@ -0,0 +1,202 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, RwLock};
+
+static CALENDAR: LazyLock<RwLock<BTreeMap<NaiveDate, MarketDay>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+/// NYSE full day closures, used for the days the data provider hasn't told us about.
+const HOLIDAYS: &[(i32, u32, u32)] = &[
+    (2023, 1, 2),
+    (2023, 1, 16),
+    (2023, 2, 20),
+    (2023, 4, 7),
+    (2023, 5, 29),
+    (2023, 6, 19),
+    (2023, 7, 4),
+    (2023, 9, 4),
+    (2023, 11, 23),
+    (2023, 12, 25),
+    (2024, 1, 1),
+    (2024, 1, 15),
+    (2024, 2, 19),
+    (2024, 3, 29),
+    (2024, 5, 27),
+    (2024, 6, 19),
+    (2024, 7, 4),
+    (2024, 9, 2),
+    (2024, 11, 28),
+    (2024, 12, 25),
+    (2025, 1, 1),
+    (2025, 1, 9),
+    (2025, 1, 20),
+    (2025, 2, 17),
+    (2025, 4, 18),
+    (2025, 5, 26),
+    (2025, 6, 19),
+    (2025, 7, 4),
+    (2025, 9, 1),
+    (2025, 11, 27),
+    (2025, 12, 25),
+    (2026, 1, 1),
+    (2026, 1, 19),
+    (2026, 2, 16),
+    (2026, 4, 3),
+    (2026, 5, 25),
+    (2026, 6, 19),
+    (2026, 7, 3),
+    (2026, 9, 7),
+    (2026, 11, 26),
+    (2026, 12, 25),
+    (2027, 1, 1),
+    (2027, 1, 18),
+    (2027, 2, 15),
+    (2027, 3, 26),
+    (2027, 5, 31),
+    (2027, 6, 18),
+    (2027, 7, 5),
+    (2027, 9, 6),
+    (2027, 11, 25),
+    (2027, 12, 24),
+];
+
+/// NYSE half days, the regular session closes at 13:00.
+const EARLY_CLOSES: &[(i32, u32, u32)] = &[
+    (2023, 7, 3),
+    (2023, 11, 24),
+    (2024, 7, 3),
+    (2024, 11, 29),
+    (2024, 12, 24),
+    (2025, 7, 3),
+    (2025, 11, 28),
+    (2025, 12, 24),
+    (2026, 11, 27),
+    (2026, 12, 24),
+    (2027, 11, 26),
+];
+
+/// Sessions of a day of the equity market, `None` when it's closed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarketDay {
+    pub date: NaiveDate,
+    pub regular: Option<(NaiveTime, NaiveTime)>,
+    pub extended: Option<(NaiveTime, NaiveTime)>,
+}
+
+/// Trading days and session boundaries of the equity market. Days learned from the data
+/// provider take precedence over the bundled NYSE holiday table.
+pub struct MarketCalendar;
+
+impl MarketCalendar {
+    pub fn day(date: NaiveDate) -> MarketDay {
+        CALENDAR
+            .read()
+            .unwrap()
+            .get(&date)
+            .copied()
+            .unwrap_or_else(|| MarketDay::nyse(date))
+    }
+
+    pub fn update(days: impl IntoIterator<Item = MarketDay>) {
+        let mut calendar = CALENDAR.write().unwrap();
+        for day in days {
+            calendar.insert(day.date, day);
+        }
+    }
+
+    pub fn is_known(date: NaiveDate) -> bool {
+        CALENDAR.read().unwrap().contains_key(&date)
+    }
+}
+
+impl MarketDay {
+    /// The day as per the bundled NYSE schedule.
+    pub fn nyse(date: NaiveDate) -> Self {
+        let ymd = (date.year(), date.month(), date.day());
+        let is_weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+        if is_weekend || HOLIDAYS.contains(&ymd) {
+            return Self::closed(date);
+        }
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let (close, extended_close) = if EARLY_CLOSES.contains(&ymd) {
+            (time(13, 0), time(17, 0))
+        } else {
+            (time(16, 0), time(20, 0))
+        };
+        Self {
+            date,
+            regular: Some((time(9, 30), close)),
+            extended: Some((time(7, 0), extended_close)),
+        }
+    }
+
+    pub fn closed(date: NaiveDate) -> Self {
+        Self {
+            date,
+            regular: None,
+            extended: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.regular.is_some()
+    }
+
+    pub fn is_early_close(&self) -> bool {
+        self.regular
+            .is_some_and(|(_, close)| close < NaiveTime::from_hms_opt(16, 0, 0).unwrap())
+    }
+
+    /// How much earlier than 16:00 the regular session ends, zero on a regular or closed day.
+    pub fn closes_early_by(&self) -> Duration {
+        match self.regular {
+            Some((_, close)) if self.is_early_close() => {
+                NaiveTime::from_hms_opt(16, 0, 0).unwrap() - close
+            }
+            _ => Duration::zero(),
+        }
+    }
+
+    /// A configured session `close` of a regular day, moved up as much as this day closes early.
+    pub fn adjusted_close(&self, close: NaiveTime) -> NaiveTime {
+        close - self.closes_early_by()
+    }
+
+    /// Length of the regular session, zero when the market is closed.
+    pub fn regular_hours(&self) -> Duration {
+        self.regular
+            .map(|(open, close)| close - open)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MarketCalendar, MarketDay};
+    use chrono::{Duration, NaiveDate, NaiveTime};
+
+    #[test]
+    fn test_market_calendar() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        assert!(MarketCalendar::day(date(7, 1)).is_open());
+        assert!(!MarketCalendar::day(date(7, 4)).is_open());
+        assert!(!MarketCalendar::day(date(7, 5)).is_open());
+
+        let half_day = MarketCalendar::day(date(11, 28));
+        assert!(half_day.is_early_close());
+        assert_eq!(half_day.regular_hours(), Duration::minutes(210));
+        assert_eq!(half_day.closes_early_by(), Duration::hours(3));
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(half_day.adjusted_close(time(15, 45)), time(12, 45));
+        assert_eq!(
+            MarketCalendar::day(date(7, 1)).adjusted_close(time(15, 45)),
+            time(15, 45)
+        );
+
+        // What the data provider says wins over the bundled table
+        MarketCalendar::update([MarketDay::closed(date(7, 2))]);
+        assert!(MarketCalendar::is_known(date(7, 2)));
+        assert!(!MarketCalendar::day(date(7, 2)).is_open());
+    }
+}
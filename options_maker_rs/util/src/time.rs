@@ -1,5 +1,8 @@
+use crate::calendar::MarketCalendar;
 use app_config::APP_CONFIG;
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, Weekday};
+use chrono::offset::LocalResult;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
 use serde::{Deserialize, Deserializer};
 
 #[inline]
@@ -13,6 +16,13 @@ pub fn from_ts(secs: i64) -> DateTime<Local> {
     datetime.with_timezone(&Local)
 }
 
+#[inline]
+pub fn from_ts_millis(millis: i64) -> DateTime<Local> {
+    let datetime =
+        DateTime::from_timestamp_millis(millis).expect("invalid or out-of-range datetime");
+    datetime.with_timezone(&Local)
+}
+
 #[inline]
 pub fn days_ago(days: u64) -> DateTime<Local> {
     let time = Local::now() - Duration::days(days as i64);
@@ -20,6 +30,62 @@ pub fn days_ago(days: u64) -> DateTime<Local> {
         .unwrap()
 }
 
+/// Timezone of the exchanges, session hours and trading days are in its wall clock.
+#[inline]
+pub fn market_tz() -> Tz {
+    APP_CONFIG.trade_config.timezone
+}
+
+/// Timezone of `exchange`, the market's unless the config overrides it.
+pub fn exchange_tz(exchange: &str) -> Tz {
+    APP_CONFIG
+        .trade_config
+        .exchange_timezones
+        .get(exchange)
+        .copied()
+        .unwrap_or_else(market_tz)
+}
+
+/// Instant of a wall clock time of `tz`. A time skipped when the clocks spring forward is moved
+/// past the gap, one repeated when they fall back resolves to its first occurrence.
+pub fn from_wall_clock(tz: Tz, naive: NaiveDateTime) -> DateTime<Local> {
+    let time = match tz.from_local_datetime(&naive) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time,
+        LocalResult::None => {
+            let before_gap = naive - Duration::hours(1);
+            tz.from_local_datetime(&before_gap)
+                .earliest()
+                .expect("DST gap wider than an hour")
+                + Duration::hours(1)
+        }
+    };
+    time.with_timezone(&Local)
+}
+
+/// Midnight at the exchange on `date`.
+pub fn market_day_start(date: NaiveDate) -> DateTime<Local> {
+    from_wall_clock(market_tz(), date.and_time(NaiveTime::MIN))
+}
+
+/// Wall clock of an instant at the exchange, what session hours and trading days compare to.
+pub trait MarketTime {
+    fn market_naive(&self) -> NaiveDateTime;
+
+    fn market_date(&self) -> NaiveDate {
+        self.market_naive().date()
+    }
+
+    fn market_time(&self) -> NaiveTime {
+        self.market_naive().time()
+    }
+}
+
+impl<T: TimeZone> MarketTime for DateTime<T> {
+    fn market_naive(&self) -> NaiveDateTime {
+        self.with_timezone(&market_tz()).naive_local()
+    }
+}
+
 pub fn parse_timestamp_opt<'de, D>(deserializer: D) -> Result<Option<DateTime<Local>>, D::Error>
 where
     D: Deserializer<'de>,
@@ -28,33 +94,45 @@ where
     Ok(ts.map(|ts| from_ts(ts / 1000)))
 }
 
-pub fn regular_trading_hours() -> Duration {
-    let trading_hours = if APP_CONFIG.trade_config.use_extended_hour {
-        8
+/// Minimum span of candles for `date` to count as a full day of data, half an hour short of
+/// the regular session so that a late first or early last candle doesn't disqualify the day.
+pub fn regular_trading_hours(date: NaiveDate) -> Duration {
+    let session = MarketCalendar::day(date).regular_hours();
+    if session.is_zero() {
+        return session;
+    }
+    let trading_hours = session - Duration::minutes(30);
+    if APP_CONFIG.trade_config.use_extended_hour {
+        trading_hours + Duration::hours(2)
     } else {
-        6
-    };
-    Duration::hours(trading_hours)
+        trading_hours
+    }
 }
 
-pub trait TradingDay {
-    fn is_weekend(&self) -> bool;
-
-    fn is_trading_day(&self) -> bool {
-        !self.is_weekend()
+/// Trading date of an exchange wall clock time, an overnight session (starting after it ends)
+/// belongs to the day it ends on.
+pub fn session_date(time: NaiveDateTime, session: (NaiveTime, NaiveTime)) -> NaiveDate {
+    let (start, end) = session;
+    if start > end && time.time() >= start {
+        time.date() + Duration::days(1)
+    } else {
+        time.date()
     }
 }
 
+pub trait TradingDay {
+    fn is_trading_day(&self) -> bool;
+}
+
 impl TradingDay for NaiveDate {
-    fn is_weekend(&self) -> bool {
-        let week_day = self.weekday();
-        week_day == Weekday::Sat || week_day == Weekday::Sun
+    fn is_trading_day(&self) -> bool {
+        MarketCalendar::day(*self).is_open()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use chrono::{Local, Utc};
+    use chrono::{Local, TimeZone, Utc};
 
     #[test]
     fn test() {
@@ -69,6 +147,50 @@ mod test {
         println!("Now: {} {} {}", time, naive, tt);
     }
 
+    #[test]
+    fn test_from_wall_clock() {
+        use chrono::NaiveDate;
+        use chrono_tz::America::New_York;
+
+        let at = |m, d, h, min| {
+            let naive = NaiveDate::from_ymd_opt(2025, m, d)
+                .unwrap()
+                .and_hms_opt(h, min, 0)
+                .unwrap();
+            super::from_wall_clock(New_York, naive).with_timezone(&Utc)
+        };
+        let utc = |m, d, h, min| Utc.with_ymd_and_hms(2025, m, d, h, min, 0).unwrap();
+        assert_eq!(at(7, 1, 9, 30), utc(7, 1, 13, 30));
+        assert_eq!(at(12, 1, 9, 30), utc(12, 1, 14, 30));
+        // 02:30 doesn't exist when the clocks spring forward, it's 03:30 EDT
+        assert_eq!(at(3, 9, 2, 30), utc(3, 9, 7, 30));
+        // 01:30 happens twice when they fall back, the EDT one comes first
+        assert_eq!(at(11, 2, 1, 30), utc(11, 2, 5, 30));
+    }
+
+    #[test]
+    fn test_session_date() {
+        use chrono::{NaiveDate, NaiveTime};
+
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let at = |d, h| {
+            NaiveDate::from_ymd_opt(2025, 7, d)
+                .unwrap()
+                .and_time(time(h))
+        };
+        let date = |d| NaiveDate::from_ymd_opt(2025, 7, d).unwrap();
+        assert_eq!(super::session_date(at(1, 19), (time(9), time(16))), date(1));
+        // The futures session opening in the evening trades the next day
+        assert_eq!(
+            super::session_date(at(1, 19), (time(18), time(17))),
+            date(2)
+        );
+        assert_eq!(
+            super::session_date(at(2, 10), (time(18), time(17))),
+            date(2)
+        );
+    }
+
     #[test]
     fn test_timestamp() {
         let time = super::now();